@@ -1,13 +1,86 @@
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
-use ratatui::widgets::ListState;
+use rand::seq::SliceRandom;
+use ratatui::layout::Rect;
+use ratatui::widgets::{ListState, ScrollbarState};
+use ratatui_image::picker::Picker;
+use ratatui_image::protocol::StatefulProtocol;
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
 
-use crate::player::Player;
+use crate::bookmarks::Bookmarks;
+use crate::config::{Config, EnterMode, Panel};
+use crate::cover::CoverArt;
+use crate::dedupe;
+use crate::favorites::Favorites;
+use crate::history::{HISTORY_LEN, History};
+use crate::library_cache::LibraryCache;
+use crate::play_counts::PlayCounts;
+use crate::eq;
+use crate::metadata::Tags;
+use crate::player::{Format, Playback, Player};
+use crate::playlist;
+use crate::resume::{ResumeMode, ResumePositions};
+use crate::session::{Session, track_key};
+use crate::stream::{is_stream, stream_name};
+use crate::theme::Theme;
+use crate::track_eq::TrackEq;
+use crate::watcher::LibraryWatcher;
 
-#[derive(Clone, Copy, PartialEq)]
+/// Library directory used when none is given on the command line.
+const DEFAULT_MUSIC_DIR: &str = "music";
+
+/// How close to the end of a track the next one is appended to the player,
+/// so it starts without a gap.
+const PRELOAD_AHEAD: Duration = Duration::from_secs(3);
+
+/// Seeking to less than this before the end of a track finishes it.
+const END_MARGIN: Duration = Duration::from_secs(2);
+
+/// How long a playback error stays on screen.
+const ERROR_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a count or `g` prefix waits for the key that completes it.
+const PENDING_KEY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Largest count prefix; more digits are ignored.
+const MAX_COUNT: usize = 9999;
+
+/// How long a status message confirming an action stays on screen.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Put between the end of a scrolling title and its start coming round again.
+pub const TITLE_SCROLL_GAP: &str = "   ·   ";
+
+/// Balance change per key press, in percent.
+const BALANCE_STEP: i16 = 5;
+
+/// Factor applied per step of the visualizer sensitivity keys.
+const SENSITIVITY_STEP: f32 = 1.25;
+
+/// Two clicks on the same row within this interval count as a double-click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Maximum number of subdirectory levels `scan_music` descends into.
+const MAX_SCAN_DEPTH: usize = 8;
+
+/// Frequency ratio of one EQ centre-frequency step (a sixth of an octave).
+const EQ_FREQ_STEP: f32 = 1.122_462;
+
+/// EQ band gain change per ↑/↓ press, and with Shift held.
+const EQ_GAIN_STEP: f32 = 1.0;
+const EQ_GAIN_FINE_STEP: f32 = 0.5;
+
+/// Longest typed EQ gain, e.g. "-12.5".
+const EQ_EDIT_MAX_LEN: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum RepeatMode {
     Off,
     All,
@@ -24,10 +97,239 @@ impl RepeatMode {
     }
 }
 
+/// Colour scheme of the spectrum bars, cycled with `v`.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum Palette {
+    /// Flat cyan, the original look.
+    #[default]
+    Cyan,
+    /// Green → yellow → red by height, like a VU meter.
+    Vu,
+    /// Blue → cyan → white.
+    Ocean,
+    /// Red → orange → yellow.
+    Fire,
+}
+
+impl Palette {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Cyan => Self::Vu,
+            Self::Vu => Self::Ocean,
+            Self::Ocean => Self::Fire,
+            Self::Fire => Self::Cyan,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Cyan => "Cyan",
+            Self::Vu => "VU",
+            Self::Ocean => "Ocean",
+            Self::Fire => "Fire",
+        }
+    }
+}
+
+/// Order of the song list, cycled with `o`.
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum SortOrder {
+    /// As scanned (by path), or as listed in an opened playlist.
+    #[default]
+    Path,
+    NameAsc,
+    NameDesc,
+    DurationAsc,
+    DurationDesc,
+}
+
+impl SortOrder {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Path => Self::NameAsc,
+            Self::NameAsc => Self::NameDesc,
+            Self::NameDesc => Self::DurationAsc,
+            Self::DurationAsc => Self::DurationDesc,
+            Self::DurationDesc => Self::Path,
+        }
+    }
+}
+
 pub struct Song {
+    /// Display name: "Artist — Title" from tags, else the file stem.
     pub name: String,
     pub path: PathBuf,
     pub duration: Option<Duration>,
+    pub tags: Tags,
+    /// Times played to the end, from `PlayCounts`.
+    pub play_count: u32,
+    /// Starred by the user, from `Favorites`.
+    pub starred: bool,
+    /// When it last started playing, from `History`: larger is more recent.
+    pub last_played: Option<u64>,
+}
+
+impl Song {
+    /// Build a song for `path`, reading its tags. `fallback_name` is shown
+    /// when the file has no title tag.
+    pub fn new(path: PathBuf, fallback_name: String, duration: Option<Duration>) -> Self {
+        let tags = Tags::read(&path);
+        Self::with_tags(path, fallback_name, duration, tags)
+    }
+
+    /// Build a song from already known tags (e.g. from the library cache).
+    pub fn with_tags(
+        path: PathBuf,
+        fallback_name: String,
+        duration: Option<Duration>,
+        tags: Tags,
+    ) -> Self {
+        let name = tags.display_name().unwrap_or(fallback_name);
+        Self {
+            name,
+            path,
+            duration,
+            tags,
+            play_count: 0,
+            starred: false,
+            last_played: None,
+        }
+    }
+
+    /// Whether this entry is an M3U playlist rather than a playable track.
+    pub fn is_playlist(&self) -> bool {
+        playlist::is_playlist(&self.path)
+    }
+}
+
+/// The `[library] prefer_formats` when duplicates are to be skipped.
+fn prefer_formats(config: &Config) -> Option<&[String]> {
+    config
+        .library
+        .skip_duplicates
+        .then_some(config.library.prefer_formats.as_slice())
+}
+
+/// Display name derived from a file path: its stem, or "Unknown".
+pub fn stem_name(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Parse `h:mm:ss`, `m:ss` or plain seconds (each part a whole number, the
+/// seconds possibly with a fraction). Times too long for a `Duration` are
+/// refused like any other bad input.
+fn parse_time(text: &str) -> Option<Duration> {
+    let mut parts = text.rsplit(':');
+    let secs: f64 = parts.next()?.parse().ok()?;
+    if !secs.is_finite() || secs < 0.0 {
+        return None;
+    }
+    let mut total = secs;
+    for scale in [60.0, 3600.0] {
+        let Some(part) = parts.next() else { break };
+        total += part.parse::<u32>().ok()? as f64 * scale;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+/// Track count and total length of the song list, shown in its title.
+#[derive(Clone, Copy, Default)]
+pub struct LibraryStats {
+    pub songs: usize,
+    /// Sum of the known lengths.
+    pub total: Duration,
+    /// Songs whose length is not known (yet), making `total` a lower bound.
+    pub unknown: usize,
+}
+
+/// A folder, or an entry in one, that the library scan could not read.
+pub struct ScanError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+/// Result of a background library scan: the songs, the entries that could
+/// not be read and the scan's copy of the cache with the tags it read.
+type LibraryScan = (Vec<Song>, Vec<ScanError>, LibraryCache);
+
+/// What the text typed into a `Prompt` is used for.
+#[derive(Clone, Copy, PartialEq)]
+pub enum PromptKind {
+    /// Minutes until playback stops (0 cancels the timer).
+    SleepTimer,
+    /// A time to seek the playing track to: `m:ss`, `h:mm:ss` or seconds,
+    /// relative to the current position with a leading `+` or `-`.
+    SeekTo,
+    /// A volume in percent, up to this maximum.
+    Volume(u16),
+    /// Yes or no: continue the playing track from this saved position.
+    Resume(Duration),
+}
+
+impl PromptKind {
+    pub fn label(self) -> String {
+        match self {
+            Self::SleepTimer => "Sleep timer (minutes, 0 = off)".to_string(),
+            Self::SeekTo => "Jump to time (1:23, 90, +30, -1:00)".to_string(),
+            Self::Volume(max) => format!("Volume (0–{max}%)"),
+            Self::Resume(pos) => {
+                let secs = pos.as_secs();
+                format!("Resume at {}:{:02}?", secs / 60, secs % 60)
+            }
+        }
+    }
+
+    /// False for yes/no questions, which are answered with a single key.
+    pub fn takes_input(self) -> bool {
+        !matches!(self, Self::Resume(_))
+    }
+}
+
+/// A single-line input box shown over the player while the user types, or a
+/// yes/no question.
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
+
+/// Vim-style prefix typed on the main screen, waiting for the key that
+/// completes it: a count (`5j`, `12G`) and/or a first `g` (`gg`).
+#[derive(Clone, Copy)]
+pub struct PendingKeys {
+    pub count: Option<usize>,
+    pub g: bool,
+    at: Instant,
+}
+
+/// One set of bars and peaks read from the analyser. In mono mode `left`
+/// holds the mix.
+#[derive(Clone, Default)]
+pub struct SpectrumFrame {
+    pub left: Vec<u64>,
+    pub right: Vec<u64>,
+    pub left_peaks: Vec<u64>,
+    pub right_peaks: Vec<u64>,
+    /// When the analyser produced it.
+    at: Option<Instant>,
+}
+
+/// Folder browser (`b`): the folders of `dir` on the left, its tracks on
+/// the right.
+pub struct Browse {
+    /// Folder whose tracks are listed; `music_dir` or a folder below it.
+    pub dir: PathBuf,
+    /// Entries of the folder pane: a label and the folder it opens ("..",
+    /// then the subfolders holding songs, by name).
+    pub folders: Vec<(String, PathBuf)>,
+    pub folder_state: ListState,
+    /// Tab: keys move in the folder pane rather than the track list.
+    pub folders_focused: bool,
 }
 
 /// State for the Equalizer popup: visibility and which band is selected.
@@ -35,330 +337,3212 @@ pub struct Song {
 pub struct EqState {
     pub popup_open: bool,
     pub selected_band: usize,
+    /// Index into `eq::PRESETS` of the applied preset; `None` once a band is
+    /// nudged away from it ("Custom").
+    pub preset: Option<usize>,
+    /// Gain being typed for the selected band, until Enter or Esc.
+    pub edit: Option<String>,
 }
 
 impl Default for EqState {
     fn default() -> Self {
-        Self { popup_open: false, selected_band: 0 }
+        Self { popup_open: false, selected_band: 0, preset: Some(0), edit: None }
     }
 }
 
 impl EqState {
-    pub const BAND_COUNT: usize = 3;
-    pub fn band_name(i: usize) -> &'static str {
-        match i {
-            0 => "Bass",
-            1 => "Mid",
-            2 => "Treble",
-            _ => "?",
+    /// Label of band `i` out of `count`: Bass, Mid and Treble for the
+    /// default three bands, numbers otherwise.
+    pub fn band_name(i: usize, count: usize) -> String {
+        match (count, i) {
+            (3, 0) => "Bass".to_string(),
+            (3, 1) => "Mid".to_string(),
+            (3, 2) => "Treble".to_string(),
+            _ => format!("Band {}", i + 1),
         }
     }
 }
 
 pub struct App {
     pub songs: Vec<Song>,
+    /// Directory the library was scanned from.
+    pub music_dir: PathBuf,
+    /// Extensions of the audio files listed (`[library]`), lower-case.
+    extensions: Vec<String>,
+    /// Formats to keep, most preferred first, when a track is stored in
+    /// several (`[library] skip_duplicates`); `None` lists every file.
+    prefer_formats: Option<Vec<String>>,
+    /// What the last library scan could not read; the rest is listed.
+    pub scan_errors: Vec<ScanError>,
+    /// Playlist whose entries currently replace the library, if any.
+    pub playlist: Option<PathBuf>,
+    /// Folder browser, while it is shown.
+    pub browse: Option<Browse>,
+    /// Count or `g` typed so far, see `PendingKeys`.
+    pub pending_keys: Option<PendingKeys>,
+    /// Index into `songs` of the highlighted song.
     pub selected: usize,
+    /// Indices into `songs` currently shown in the list (all songs unless a
+    /// search filter is active). `list_state` holds a position in this view.
+    pub view: Vec<usize>,
+    /// Whether typed characters go to `search_query` (after pressing `/`).
+    pub search_mode: bool,
+    pub search_query: String,
     pub now_playing: Option<usize>,
-    pub player: Player,
+    pub player: Box<dyn Playback>,
     pub repeat: RepeatMode,
+    pub shuffle: bool,
+    /// Songs not yet played in the current shuffle pass; popped from the end.
+    pub shuffle_order: Vec<usize>,
+    /// Songs played so far in shuffle mode, so `prev_track` can walk back.
+    pub shuffle_history: Vec<usize>,
+    /// "Up next" songs, played before advancing through the library.
+    pub queue: VecDeque<usize>,
     pub should_quit: bool,
     pub list_state: ListState,
+    pub sort: SortOrder,
+    /// Scrollbar beside the list; kept in step with `list_state`.
+    pub scroll_state: ScrollbarState,
     pub eq_state: EqState,
+    /// Whether the keybinding help overlay is shown.
+    pub help_open: bool,
+    /// The last two frames from the analyser; the visualizer draws a blend
+    /// of them, so its motion does not depend on when the screen redraws.
+    pub spectrum_previous: SpectrumFrame,
+    pub spectrum_current: SpectrumFrame,
+    /// The visualizer is off screen (no room, or behind the help), so the
+    /// analyser is told to rest.
+    visualizer_hidden: bool,
+    /// Open text prompt, if any; it captures all keys until closed.
+    pub prompt: Option<Prompt>,
+    /// When the sleep timer stops playback.
+    pub sleep_deadline: Option<Instant>,
+    /// Let the current track finish once the sleep deadline has passed.
+    pub sleep_finish_track: bool,
+    /// Screen areas of the list rows and the progress bar from the last draw,
+    /// used to map mouse clicks.
+    pub list_area: Rect,
+    pub progress_area: Rect,
+    /// Song and time of the last list click, for double-click detection.
+    last_click: Option<(usize, Instant)>,
+    /// Song already appended to the player behind the current one (gapless
+    /// playback), and whether it was taken from the queue.
+    preloaded: Option<(usize, bool)>,
+    /// A-B loop points in the current track; once both are set playback
+    /// jumps back to A whenever it passes B.
+    pub loop_a: Option<Duration>,
+    pub loop_b: Option<Duration>,
+    /// Saved positions within tracks, persisted as soon as they change.
+    pub bookmarks: Bookmarks,
+    /// Show the time left (`-m:ss`) instead of the total duration.
+    pub show_remaining: bool,
+    pub palette: Palette,
+    /// Height of the visualizer's baseline (`[visualizer] min_bar`).
+    pub min_bar: u64,
+    /// Embedded cover art of the playing track, loaded in the background.
+    cover: CoverArt,
+    /// Durations measured by the background scan, merged into `songs` by
+    /// `poll_durations`. Keyed by path because indices change on re-sort.
+    durations: Option<Receiver<(PathBuf, Duration)>>,
+    /// Tags and durations of the library from earlier runs.
+    library_cache: LibraryCache,
+    /// Reports changes in `music_dir`; `None` when it cannot be watched.
+    watcher: Option<LibraryWatcher>,
+    /// Rescan started by a change in `music_dir`, running in the
+    /// background; applied by `check_library_changes` when it is done.
+    rescan: Option<Receiver<LibraryScan>>,
+    /// Full plays per track, persisted as soon as they change.
+    play_counts: PlayCounts,
+    /// Show the play-count column in the song list.
+    pub show_play_counts: bool,
+    favorites: Favorites,
+    /// Limit the view to starred songs (combines with the search filter).
+    pub starred_only: bool,
+    /// Recently played tracks, persisted as soon as one starts.
+    history: History,
+    /// Show only recently played songs, most recent first (combines with
+    /// the other filters).
+    pub history_only: bool,
+    /// Interface colours from the config.
+    pub theme: Theme,
+    pub stats: LibraryStats,
+    resume_positions: ResumePositions,
+    resume_mode: ResumeMode,
+    enter_mode: EnterMode,
+    /// Volume as shown, in percent; `volume_gain` turns it into the sink
+    /// volume.
+    volume: u16,
+    /// Volume change per key press and highest volume, in percent.
+    volume_step: u16,
+    volume_max: u16,
+    volume_curve: f32,
+    /// Draw the volume as a bar too, not only as a percentage.
+    pub volume_bar: bool,
+    /// Left/right balance in percent, -100 (left only) to 100 (right only).
+    balance: i16,
+    /// How far the seek keys jump, without and with Shift.
+    seek_step: Duration,
+    seek_step_long: Duration,
+    track_eq: TrackEq,
+    /// Global band gains and preset, set aside while the playing track's own
+    /// EQ curve is applied.
+    global_eq: Option<(Vec<f32>, Option<usize>)>,
+    /// Set while the track being loaded was reached by advancing: if it
+    /// turns out unplayable the one after it is tried. Counts the tracks
+    /// skipped so far.
+    advance_skips: Option<usize>,
+    /// When the player started loading the current track, for the spinner.
+    load_started: Instant,
+    /// When the player was started, for the listening time beside the clock.
+    session_start: Instant,
+    /// Show the time of day and the listening time in the song list title.
+    pub clock: bool,
+    /// Panels under the song list, top to bottom (`[ui] panels`).
+    pub panels: Vec<Panel>,
+    /// Characters the now-playing title has scrolled by while it is wider
+    /// than the panel.
+    title_scroll: usize,
+    /// Cells the title had at the last draw; 0 until it first overflows.
+    title_width: usize,
+    /// Why the last track failed to play, shown briefly under the progress bar.
+    pub last_error: Option<String>,
+    last_error_at: Instant,
+    /// Confirmation of the last action and when it was set, shown in the
+    /// same line as errors for `STATUS_TIMEOUT`.
+    pub status: Option<(String, Instant)>,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
-        let player = Player::new()?;
-        let songs = Self::scan_music();
+    /// Create the app, scanning `dir` (or `./music` when `None`) for songs
+    /// and playing through `device` (or the default output when `None`).
+    /// A `dir` of `-` or an HTTP(S) URL instead plays that stream as the only
+    /// entry. `picker` draws cover art in the terminal's image protocol.
+    pub fn new(
+        dir: Option<PathBuf>,
+        device: Option<&str>,
+        config: &Config,
+        picker: Picker,
+    ) -> Result<Self> {
+        let mut player = Player::new(
+            config.visualizer,
+            &config.equalizer,
+            config.playback.output_rate,
+        )?;
+        if let Some(name) = device {
+            player.switch_device(name)?;
+        }
+        player.set_mono(config.playback.mono)?;
+        let music_dir = dir.unwrap_or_else(|| PathBuf::from(DEFAULT_MUSIC_DIR));
+        let streaming = is_stream(&music_dir);
+        let mut library_cache = LibraryCache::load();
+        let (songs, scan_errors) = if streaming {
+            let name = stream_name(&music_dir);
+            let song = Song::with_tags(music_dir.clone(), name, None, Tags::default());
+            (vec![song], Vec::new())
+        } else {
+            Self::scan_music(
+                &music_dir,
+                &mut library_cache,
+                &config.library.audio_extensions(),
+                prefer_formats(config),
+            )
+        };
+        let mut app = Self::with_playback(
+            music_dir,
+            songs,
+            library_cache,
+            Box::new(player),
+            config,
+            picker,
+        );
+        app.set_scan_errors(scan_errors);
+        if !streaming {
+            app.watcher = LibraryWatcher::new(&app.music_dir);
+            app.scan_durations();
+        }
+        if let Some(session) = Session::load() {
+            app.restore_session(session);
+        }
+        if streaming {
+            app.play_index(0);
+        }
+        Ok(app)
+    }
+
+    /// Create the app for already scanned `songs`, playing through
+    /// `player`. Nothing is watched or restored; see `new`.
+    fn with_playback(
+        music_dir: PathBuf,
+        songs: Vec<Song>,
+        library_cache: LibraryCache,
+        player: Box<dyn Playback>,
+        config: &Config,
+        picker: Picker,
+    ) -> Self {
+        let view: Vec<usize> = (0..songs.len()).collect();
+        let scroll_state = ScrollbarState::new(view.len());
         let mut list_state = ListState::default();
         if !songs.is_empty() {
             list_state.select(Some(0));
         }
 
-        Ok(Self {
+        let mut app = Self {
             songs,
+            music_dir,
+            scan_errors: Vec::new(),
+            playlist: None,
+            browse: None,
+            pending_keys: None,
             selected: 0,
+            view,
+            search_mode: false,
+            search_query: String::new(),
             now_playing: None,
             player,
             repeat: RepeatMode::Off,
+            shuffle: false,
+            shuffle_order: Vec::new(),
+            shuffle_history: Vec::new(),
+            queue: VecDeque::new(),
             should_quit: false,
             list_state,
+            sort: SortOrder::default(),
+            scroll_state,
             eq_state: EqState::default(),
-        })
+            help_open: false,
+            spectrum_previous: SpectrumFrame::default(),
+            spectrum_current: SpectrumFrame::default(),
+            visualizer_hidden: false,
+            prompt: None,
+            sleep_deadline: None,
+            sleep_finish_track: false,
+            list_area: Rect::default(),
+            progress_area: Rect::default(),
+            last_click: None,
+            preloaded: None,
+            loop_a: None,
+            loop_b: None,
+            bookmarks: Bookmarks::load(),
+            show_remaining: false,
+            palette: Palette::default(),
+            min_bar: config.visualizer.min_bar,
+            cover: CoverArt::new(picker),
+            durations: None,
+            library_cache,
+            watcher: None,
+            rescan: None,
+            play_counts: PlayCounts::load(),
+            show_play_counts: false,
+            favorites: Favorites::load(),
+            starred_only: false,
+            history: History::load(),
+            history_only: false,
+            theme: config.theme,
+            stats: LibraryStats::default(),
+            resume_positions: ResumePositions::load(),
+            resume_mode: config.playback.resume,
+            enter_mode: config.playback.enter,
+            volume: 100,
+            balance: 0,
+            volume_step: config.playback.volume_step,
+            volume_max: config.playback.volume_max,
+            volume_curve: config.playback.volume_curve,
+            volume_bar: config.ui.volume_bar,
+            extensions: config.library.audio_extensions(),
+            prefer_formats: prefer_formats(config).map(<[String]>::to_vec),
+            seek_step: Duration::from_secs(config.playback.seek_step),
+            seek_step_long: Duration::from_secs(config.playback.seek_step_long),
+            track_eq: TrackEq::load(),
+            global_eq: None,
+            advance_skips: None,
+            load_started: Instant::now(),
+            session_start: Instant::now(),
+            clock: config.ui.clock,
+            panels: config.ui.panels.clone(),
+            title_scroll: 0,
+            title_width: 0,
+            last_error: None,
+            last_error_at: Instant::now(),
+            status: None,
+        };
+        app.apply_saved_track_state();
+        app.update_stats();
+        app
     }
 
-    fn scan_music() -> Vec<Song> {
-        let music_dir = PathBuf::from("music");
-        if !music_dir.exists() {
-            return Vec::new();
+    /// Apply a saved session: volume, repeat and EQ, then re-open the saved
+    /// track at its position, paused. A track no longer in the library is skipped.
+    fn restore_session(&mut self, session: Session) {
+        let percent = (session.volume.max(0.0) * 100.0).round() as u16;
+        self.set_volume(percent);
+        self.set_balance(session.balance);
+        self.repeat = session.repeat;
+        // Bands saved with a different band layout do not carry over.
+        let bands = self.eq_band_count();
+        if session.eq_db.len() == bands {
+            for (band, &db) in session.eq_db.iter().enumerate() {
+                self.eq_set_band_gain_db(band, db);
+            }
+        }
+        if session.eq_freqs.len() == bands {
+            for (band, &hz) in session.eq_freqs.iter().enumerate() {
+                self.eq_set_band_freq(band, hz);
+            }
+        }
+        if let Some(q) = session.eq_q {
+            self.player.eq_gains().set_q(q);
+        }
+        self.player.eq_gains().set_preamp_db(session.eq_preamp_db);
+        self.player.eq_gains().set_enabled(session.eq_enabled);
+        self.player
+            .set_visualizer_sensitivity(session.visualizer_sensitivity);
+        let _ = self.player.set_replay_gain(session.replay_gain);
+        self.eq_state.preset = eq::PRESETS.iter().position(|(_, points)| {
+            (0..bands).all(|band| {
+                let db = eq::preset_gain(points, self.eq_band_freq(band));
+                (self.eq_band_gain_db(band) - db).abs() < 0.01
+            })
+        });
+
+        let Some(track) = session.track else { return };
+        let Some(idx) = self
+            .songs
+            .iter()
+            .position(|s| fs::canonicalize(&s.path).is_ok_and(|p| p == track))
+        else {
+            return;
+        };
+        self.select_song(idx);
+        if self.player.load_file_from(&track, session.position).is_ok() {
+            self.now_playing = Some(idx);
+            self.measure_duration(idx);
+            self.apply_track_eq(idx);
         }
+    }
 
-        let extensions = ["mp3", "wav", "ogg", "flac", "m4a", "aac"];
-        let Ok(entries) = fs::read_dir(&music_dir) else {
-            return Vec::new();
+    /// Save the current track, position, volume, repeat mode and EQ gains
+    /// (the global ones, not a track's own curve), and remember the position
+    /// for resuming the track later.
+    pub fn save_session(&mut self) -> Result<()> {
+        self.remember_position();
+        let track = self
+            .now_playing
+            .and_then(|idx| fs::canonicalize(&self.songs[idx].path).ok());
+        let session = Session {
+            track,
+            position: self.current_position(),
+            volume: f32::from(self.volume) / 100.0,
+            balance: self.balance,
+            repeat: self.repeat,
+            eq_db: match &self.global_eq {
+                Some((gains, _)) => gains.clone(),
+                None => self.eq_gains_db(),
+            },
+            eq_freqs: (0..self.eq_band_count())
+                .map(|band| self.eq_band_freq(band))
+                .collect(),
+            eq_q: Some(self.eq_q()),
+            eq_preamp_db: self.eq_preamp_db(),
+            eq_enabled: self.eq_enabled(),
+            visualizer_sensitivity: self.visualizer_sensitivity(),
+            replay_gain: self.replay_gain(),
         };
+        session.save()
+    }
 
-        let mut files: Vec<_> = entries
-            .filter_map(|e| e.ok())
-            .filter(|e| {
-                e.path()
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .is_some_and(|ext| extensions.contains(&ext.to_lowercase().as_str()))
-            })
-            .collect();
+    /// List the songs under `music_dir`, taking tags and durations from
+    /// `cache` where the file is unchanged and reading (and caching) the
+    /// tags of the others. Folders and entries that cannot be read are
+    /// returned alongside, the rest is still listed.
+    fn scan_music(
+        music_dir: &Path,
+        cache: &mut LibraryCache,
+        extensions: &[String],
+        prefer_formats: Option<&[String]>,
+    ) -> (Vec<Song>, Vec<ScanError>) {
+        if !music_dir.exists() {
+            return (Vec::new(), Vec::new());
+        }
+
+        let mut files = Vec::new();
+        let mut errors = Vec::new();
+        Self::collect_audio_files(music_dir, extensions, 0, &mut files, &mut errors);
+
+        // Sort by path relative to the music dir so albums stay grouped and
+        // the order is alphabetical across the flattened result.
+        files.sort();
 
-        files.sort_by_key(|e| e.file_name());
+        let mut stem_counts: HashMap<String, usize> = HashMap::new();
+        for path in &files {
+            *stem_counts.entry(stem_name(path)).or_default() += 1;
+        }
 
-        files
+        let mut songs: Vec<Song> = files
             .into_iter()
-            .map(|entry| {
-                let path = entry.path();
-                let name = path
-                    .file_stem()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("Unknown")
-                    .to_string();
-                let duration = Player::get_duration(&path);
-                Song {
-                    name,
-                    path,
-                    duration,
+            .map(|path| {
+                let stem = stem_name(&path);
+                // Disambiguate duplicate stems ("Track 01") with their folder.
+                let name = match path.parent().and_then(|p| p.strip_prefix(music_dir).ok()) {
+                    Some(dir) if stem_counts[&stem] > 1 && !dir.as_os_str().is_empty() => {
+                        format!("{stem} ({})", dir.display())
+                    }
+                    _ => stem,
+                };
+                if playlist::is_playlist(&path) {
+                    return Song {
+                        name,
+                        path,
+                        duration: None,
+                        tags: Tags::default(),
+                        play_count: 0,
+                        starred: false,
+                        last_played: None,
+                    };
+                }
+                if let Some((tags, duration)) = cache.get(&path) {
+                    return Song::with_tags(path, name, duration, tags);
                 }
+                // The duration is measured later by `scan_durations`.
+                let song = Song::new(path, name, None);
+                cache.insert(&song.path, &song.tags);
+                song
             })
-            .collect()
-    }
-
-    pub fn play_selected(&mut self) {
-        if !self.songs.is_empty() {
-            self.play_index(self.selected);
+            .collect();
+        if let Some(prefer) = prefer_formats {
+            dedupe::remove_duplicates(&mut songs, prefer);
         }
+        (songs, errors)
     }
 
-    fn play_index(&mut self, idx: usize) {
-        if idx < self.songs.len() && self.player.play_file(&self.songs[idx].path).is_ok() {
-            self.now_playing = Some(idx);
+    /// Keep the errors of a library scan for the song list title, and show
+    /// the first of them on the error line unless the last scan already
+    /// failed on the same paths (a rescan after every change in the folder
+    /// would repeat it).
+    fn set_scan_errors(&mut self, errors: Vec<ScanError>) {
+        let paths = |errors: &[ScanError]| -> Vec<PathBuf> {
+            errors.iter().map(|e| e.path.clone()).collect()
+        };
+        if paths(&errors) != paths(&self.scan_errors)
+            && let Some(first) = errors.first()
+        {
+            let others = match errors.len() {
+                1 => String::new(),
+                n => format!(" (and {} more)", n - 1),
+            };
+            self.set_error(format!(
+                "Cannot read {}: {}{others}",
+                first.path.display(),
+                first.error
+            ));
         }
+        self.scan_errors = errors;
     }
 
-    pub fn toggle_pause(&self) {
-        if self.now_playing.is_some() {
-            self.player.toggle_pause();
+    /// Measure the unknown durations of `songs` on a background thread,
+    /// replacing any scan still running for a previous list.
+    fn scan_durations(&mut self) {
+        let paths: Vec<PathBuf> = self
+            .songs
+            .iter()
+            .filter(|s| s.duration.is_none() && !s.is_playlist())
+            .map(|s| s.path.clone())
+            .collect();
+        if paths.is_empty() {
+            self.durations = None;
+            return;
         }
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for path in paths {
+                if let Some(duration) = Player::get_duration(&path)
+                    && tx.send((path, duration)).is_err()
+                {
+                    // The list was replaced; its scan is no longer wanted.
+                    return;
+                }
+            }
+        });
+        self.durations = Some(rx);
     }
 
-    pub fn next_track(&mut self) {
-        if self.songs.is_empty() {
-            return;
-        }
-        match self.now_playing {
-            Some(idx) => {
-                let next = if idx + 1 >= self.songs.len() {
-                    match self.repeat {
-                        RepeatMode::All => 0,
-                        _ => return,
+    /// Fill in the durations measured since the last call (each tick). A
+    /// list sorted by length is re-sorted as they arrive.
+    pub fn poll_durations(&mut self) {
+        let Some(rx) = &self.durations else { return };
+        let mut changed = false;
+        loop {
+            match rx.try_recv() {
+                Ok((path, duration)) => {
+                    for song in self.songs.iter_mut().filter(|s| s.path == path) {
+                        song.duration.get_or_insert(duration);
+                        changed = true;
                     }
-                } else {
-                    idx + 1
-                };
-                self.selected = next;
-                self.list_state.select(Some(next));
-                self.play_index(next);
+                    self.library_cache.set_duration(&path, duration);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // Scan finished: keep its results even if we crash later.
+                    self.durations = None;
+                    self.save_library_cache();
+                    break;
+                }
             }
-            None => self.play_selected(),
+        }
+        if changed {
+            self.update_stats();
+        }
+        if changed && matches!(self.sort, SortOrder::DurationAsc | SortOrder::DurationDesc) {
+            self.sort_songs();
+            self.refresh_view();
         }
     }
 
-    pub fn prev_track(&mut self) {
-        if self.songs.is_empty() {
-            return;
+    /// Measure the duration of song `idx` right away if the background scan
+    /// has not reached it yet, so the playing track always shows its length.
+    fn measure_duration(&mut self, idx: usize) {
+        let song = &mut self.songs[idx];
+        if song.duration.is_none() {
+            song.duration = Player::get_duration(&song.path);
+            if let Some(duration) = song.duration {
+                self.library_cache.set_duration(&song.path, duration);
+                self.update_stats();
+            }
         }
-        match self.now_playing {
-            Some(idx) => {
-                // If more than 3 seconds in, restart current track
-                if self.player.position().as_secs() > 3 {
-                    self.play_index(idx);
-                    return;
-                }
-                let prev = if idx == 0 {
-                    match self.repeat {
-                        RepeatMode::All => self.songs.len() - 1,
-                        _ => return,
-                    }
-                } else {
-                    idx - 1
-                };
-                self.selected = prev;
-                self.list_state.select(Some(prev));
-                self.play_index(prev);
+    }
+
+    /// Recount `stats` after the song list or a duration changed.
+    fn update_stats(&mut self) {
+        let mut stats = LibraryStats::default();
+        for song in self.songs.iter().filter(|s| !s.is_playlist()) {
+            stats.songs += 1;
+            match song.duration {
+                Some(duration) => stats.total += duration,
+                None => stats.unknown += 1,
             }
-            None => self.play_selected(),
         }
+        self.stats = stats;
     }
 
-    pub fn select_next(&mut self) {
-        if !self.songs.is_empty() {
-            self.selected = (self.selected + 1).min(self.songs.len() - 1);
-            self.list_state.select(Some(self.selected));
+    /// Write the library cache if it changed. Failures are ignored: the
+    /// cache is only a speed-up and is rebuilt by the next scan.
+    pub fn save_library_cache(&mut self) {
+        let _ = self.library_cache.save();
+    }
+
+    /// Recursively collect the files with one of `extensions` (lower-case) and
+    /// playlists under `dir`, descending at most `MAX_SCAN_DEPTH` levels (also
+    /// guards against symlink loops). Folders and entries that cannot be
+    /// read go to `errors`.
+    fn collect_audio_files(
+        dir: &Path,
+        extensions: &[String],
+        depth: usize,
+        out: &mut Vec<PathBuf>,
+        errors: &mut Vec<ScanError>,
+    ) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(error) => {
+                errors.push(ScanError { path: dir.to_path_buf(), error });
+                return;
+            }
+        };
+
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(error) => {
+                    errors.push(ScanError { path: dir.to_path_buf(), error });
+                    continue;
+                }
+            };
+            if path.is_dir() {
+                if depth < MAX_SCAN_DEPTH {
+                    Self::collect_audio_files(&path, extensions, depth + 1, out, errors);
+                }
+            } else if playlist::is_playlist(&path)
+                || path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .is_some_and(|ext| extensions.contains(&ext.to_lowercase()))
+            {
+                out.push(path);
+            }
         }
     }
 
-    pub fn select_prev(&mut self) {
-        if !self.songs.is_empty() {
-            self.selected = self.selected.saturating_sub(1);
-            self.list_state.select(Some(self.selected));
+    /// Enter or a double click: play the selected track or, with `enter =
+    /// "enqueue"` while another is playing, queue it up next. Playlists
+    /// always open.
+    pub fn activate_selected(&mut self) {
+        if self.enter_mode == EnterMode::Enqueue
+            && self.now_playing.is_some()
+            && self.is_track(self.selected)
+        {
+            self.enqueue_selected();
+        } else {
+            self.play_selected();
         }
     }
 
-    pub fn volume_up(&self) {
-        let vol = self.player.volume();
-        self.player.set_volume((vol + 0.05).min(1.5));
+    pub fn play_selected(&mut self) {
+        if self.songs.is_empty() {
+            return;
+        }
+        let idx = self.selected;
+        if !self.view.contains(&idx) {
+            return;
+        }
+        if self.songs[idx].is_playlist() {
+            self.open_playlist(self.songs[idx].path.clone());
+            return;
+        }
+        if self.shuffle {
+            self.shuffle_order.retain(|&i| i != idx);
+            if let Some(cur) = self.now_playing {
+                self.shuffle_history.push(cur);
+            }
+        }
+        self.play_index(idx);
     }
 
-    pub fn volume_down(&self) {
-        let vol = self.player.volume();
-        self.player.set_volume((vol - 0.05).max(0.0));
+    /// Replace the song list with the entries of the playlist at `path` and
+    /// start playing its first track. Empty or unreadable playlists are ignored.
+    fn open_playlist(&mut self, path: PathBuf) {
+        let songs = playlist::load_m3u(&path);
+        if songs.is_empty() {
+            return;
+        }
+        self.songs = songs;
+        self.apply_saved_track_state();
+        self.update_stats();
+        self.scan_durations();
+        self.playlist = Some(path);
+        self.now_playing = None;
+        self.preloaded = None;
+        self.queue.clear();
+        self.shuffle_order.clear();
+        self.shuffle_history.clear();
+        self.search_query.clear();
+        self.selected = 0;
+        // Keep the playlist's own order unless another sort was chosen.
+        if self.sort != SortOrder::Path {
+            self.sort_songs();
+            self.selected = 0;
+        }
+        self.refresh_view();
+        self.play_index(0);
+        if self.shuffle {
+            self.reshuffle();
+        }
     }
 
-    pub fn seek_forward(&mut self) {
-        let Some(idx) = self.now_playing else { return };
-        let pos = self.player.position();
-        let new_pos = pos + Duration::from_secs(5);
-        let end = self.current_duration().unwrap_or(Duration::MAX);
-        let start = new_pos.min(end);
-        if self.player.play_file_from(&self.songs[idx].path, start).is_ok() {
-            // now_playing unchanged
+    /// Leave the active playlist and return to the scanned library, keeping
+    /// the playing track highlighted if it is part of the library.
+    pub fn close_playlist(&mut self) {
+        if self.playlist.take().is_none() {
+            return;
+        }
+        let playing = self.now_playing.map(|idx| self.songs[idx].path.clone());
+        // The preloaded song's index is about to become meaningless.
+        self.preloaded = None;
+        let _ = self.player.cancel_preload();
+        let (songs, scan_errors) = Self::scan_music(
+            &self.music_dir,
+            &mut self.library_cache,
+            &self.extensions,
+            self.prefer_formats.as_deref(),
+        );
+        self.songs = songs;
+        self.set_scan_errors(scan_errors);
+        self.apply_saved_track_state();
+        self.update_stats();
+        self.scan_durations();
+        self.now_playing = playing
+            .as_ref()
+            .and_then(|p| self.songs.iter().position(|s| &s.path == p));
+        if playing.is_some() && self.now_playing.is_none() {
+            // The playlist's track is not in the library (e.g. an absolute
+            // path elsewhere): stop it rather than play on unseen.
+            self.player.pause();
+        }
+        if let Some(idx) = self.now_playing {
+            self.measure_duration(idx);
+        }
+        self.selected = self.now_playing.unwrap_or(0);
+        self.search_query.clear();
+        self.queue.clear();
+        self.shuffle_order.clear();
+        self.shuffle_history.clear();
+        self.sort_songs();
+        self.refresh_view();
+        if self.shuffle {
+            self.reshuffle();
         }
     }
 
-    pub fn seek_backward(&mut self) {
-        let Some(idx) = self.now_playing else { return };
-        let pos = self.player.position();
-        let start = pos.saturating_sub(Duration::from_secs(5));
-        if self.player.play_file_from(&self.songs[idx].path, start).is_ok() {
-            // now_playing unchanged
+    // ── Play counts ────────────────────────────────────────────────────────
+
+    /// Fill in `Song::play_count`, `Song::starred` and `Song::last_played`
+    /// after the song list was replaced.
+    fn apply_saved_track_state(&mut self) {
+        for song in &mut self.songs {
+            // One canonicalization per song serves all three stores.
+            let key = track_key(&song.path);
+            song.play_count = self.play_counts.get(&key);
+            song.starred = self.favorites.contains(&key);
+            song.last_played = self.history.stamp(&key);
         }
     }
 
-    pub fn toggle_repeat(&mut self) {
-        self.repeat = match self.repeat {
-            RepeatMode::Off => RepeatMode::All,
-            RepeatMode::All => RepeatMode::One,
-            RepeatMode::One => RepeatMode::Off,
-        };
+    /// Record that song `idx` played to the end (skips are not counted).
+    fn count_play(&mut self, idx: usize) {
+        let song = &mut self.songs[idx];
+        song.play_count = self.play_counts.increment(&song.path);
+        let _ = self.play_counts.save();
     }
 
-    pub fn check_track_end(&mut self) {
+    // ── Resume positions ───────────────────────────────────────────────────
+
+    /// Save where the playing track is, to offer resuming it next time.
+    fn remember_position(&mut self) {
         let Some(idx) = self.now_playing else { return };
-        if !self.player.is_empty() || self.player.is_paused() {
+        // A stream cannot seek, so there is nothing to resume.
+        if is_stream(&self.songs[idx].path) {
             return;
         }
-        match self.repeat {
-            RepeatMode::One => self.play_index(idx),
-            RepeatMode::All => {
-                let next = (idx + 1) % self.songs.len();
-                self.selected = next;
-                self.list_state.select(Some(next));
-                self.play_index(next);
-            }
-            RepeatMode::Off => {
-                if idx + 1 < self.songs.len() {
-                    let next = idx + 1;
-                    self.selected = next;
-                    self.list_state.select(Some(next));
-                    self.play_index(next);
-                } else {
-                    self.now_playing = None;
-                }
-            }
-        }
+        let position = self.current_position();
+        let duration = self.current_duration();
+        self.resume_positions
+            .set(&self.songs[idx].path, position, duration);
+        let _ = self.resume_positions.save();
     }
 
-    pub fn current_position(&self) -> Duration {
-        if self.now_playing.is_some() {
-            self.player.position()
-        } else {
-            Duration::ZERO
-        }
+    /// Drop the saved position of track `idx` once it has played to the end.
+    fn forget_position(&mut self, idx: usize) {
+        self.resume_positions.remove(&self.songs[idx].path);
+        let _ = self.resume_positions.save();
     }
 
-    pub fn current_duration(&self) -> Option<Duration> {
-        self.now_playing.and_then(|idx| self.songs[idx].duration)
+    /// Track `idx` just started: continue from its saved position, or ask
+    /// whether to, depending on `[playback] resume`.
+    fn offer_resume(&mut self, idx: usize) {
+        let Some(pos) = self.resume_positions.get(&self.songs[idx].path) else {
+            return;
+        };
+        match self.resume_mode {
+            ResumeMode::Never => {}
+            ResumeMode::Always => self.seek_to(pos),
+            ResumeMode::Ask => self.prompt_open(PromptKind::Resume(pos)),
+        }
     }
 
-    pub fn is_playing(&self) -> bool {
-        self.now_playing.is_some() && !self.player.is_paused()
+    /// `c`: show or hide the play-count column.
+    pub fn toggle_play_counts(&mut self) {
+        self.show_play_counts = !self.show_play_counts;
     }
 
-    pub fn volume_percent(&self) -> u16 {
-        (self.player.volume() * 100.0).round() as u16
-    }
+    // ── Favorites ──────────────────────────────────────────────────────────
 
-    pub fn now_playing_name(&self) -> &str {
-        self.now_playing
-            .map(|idx| self.songs[idx].name.as_str())
-            .unwrap_or("Nothing playing")
+    /// `f`: star or unstar the selected song (playlists cannot be starred).
+    pub fn toggle_star(&mut self) {
+        if !self.is_track(self.selected) || !self.view.contains(&self.selected) {
+            return;
+        }
+        let song = &mut self.songs[self.selected];
+        song.starred = self.favorites.toggle(&song.path);
+        let _ = self.favorites.save();
+        let status = if song.starred { "★ Starred" } else { "Unstarred" };
+        self.set_status(status);
+        if self.starred_only {
+            self.refresh_view();
+        }
     }
 
-    pub fn spectrum(&self) -> Vec<u64> {
-        self.player.spectrum()
+    /// `F`: show only starred songs, or everything again.
+    pub fn toggle_starred_only(&mut self) {
+        self.starred_only = !self.starred_only;
+        self.refresh_view();
     }
 
-    // ── Equalizer popup and band gains ─────────────────────────────────────
+    // ── History ────────────────────────────────────────────────────────────
 
-    pub fn eq_popup_toggle(&mut self) {
-        self.eq_state.popup_open = !self.eq_state.popup_open;
+    /// Song `idx` started playing: put it at the top of the history.
+    /// Streams are not remembered.
+    fn record_play(&mut self, idx: usize) {
+        let song = &mut self.songs[idx];
+        if is_stream(&song.path) {
+            return;
+        }
+        song.last_played = Some(self.history.push(&song.path));
+        let _ = self.history.save();
+        if self.history_only {
+            self.refresh_view();
+        }
     }
 
-    pub fn eq_popup_open(&self) -> bool {
-        self.eq_state.popup_open
+    /// `H`: show only the recently played songs, most recent first, or
+    /// everything again.
+    pub fn toggle_history_only(&mut self) {
+        self.history_only = !self.history_only;
+        self.refresh_view();
     }
 
-    pub fn eq_selected_band(&self) -> usize {
-        self.eq_state.selected_band
+    // ── Folder browser ─────────────────────────────────────────────────────
+
+    /// `b`: show or hide the folder browser. It starts in the playing
+    /// track's folder, or at the top of the library.
+    pub fn toggle_browse(&mut self) {
+        if self.browse.take().is_none() {
+            let dir = self
+                .now_playing
+                .and_then(|idx| self.songs[idx].path.parent())
+                .filter(|dir| dir.starts_with(&self.music_dir))
+                .map_or_else(|| self.music_dir.clone(), Path::to_path_buf);
+            self.browse = Some(Browse {
+                dir,
+                folders: Vec::new(),
+                folder_state: ListState::default(),
+                folders_focused: false,
+            });
+        }
+        self.refresh_view();
     }
 
-    pub fn eq_select_prev_band(&mut self) {
-        self.eq_state.selected_band = self.eq_state.selected_band.saturating_sub(1);
+    /// Whether the folder browser is shown. It browses the library, so it
+    /// is set aside while a playlist is open.
+    pub fn browsing(&self) -> bool {
+        self.browse.is_some() && self.playlist.is_none()
     }
 
-    pub fn eq_select_next_band(&mut self) {
-        self.eq_state.selected_band = (self.eq_state.selected_band + 1).min(EqState::BAND_COUNT.saturating_sub(1));
+    /// Whether list keys currently move in the folder pane.
+    pub fn folders_focused(&self) -> bool {
+        self.browsing() && self.browse.as_ref().is_some_and(|b| b.folders_focused)
     }
 
-    pub fn eq_band_gain_db(&self, band: usize) -> f32 {
-        let g = self.player.eq_gains();
-        match band {
-            0 => g.bass_db(),
-            1 => g.mid_db(),
-            2 => g.treble_db(),
-            _ => 0.0,
+    fn browsed_dir(&self) -> Option<&Path> {
+        match &self.browse {
+            Some(browse) if self.playlist.is_none() => Some(&browse.dir),
+            _ => None,
         }
     }
 
-    pub fn eq_band_up(&mut self) {
-        let band = self.eq_state.selected_band;
-        let g = self.player.eq_gains();
-        let db = self.eq_band_gain_db(band);
-        let new_db = (db + 1.0).min(12.0);
-        match band {
-            0 => g.set_bass_db(new_db),
-            1 => g.set_mid_db(new_db),
-            2 => g.set_treble_db(new_db),
-            _ => {}
+    /// Tab: move the focus between the folder pane and the track list.
+    pub fn toggle_browse_focus(&mut self) {
+        if let Some(browse) = self.browse.as_mut()
+            && self.playlist.is_none()
+        {
+            browse.folders_focused = !browse.folders_focused;
         }
     }
 
-    pub fn eq_band_down(&mut self) {
-        let band = self.eq_state.selected_band;
-        let g = self.player.eq_gains();
-        let db = self.eq_band_gain_db(band);
-        let new_db = (db - 1.0).max(-12.0);
-        match band {
-            0 => g.set_bass_db(new_db),
-            1 => g.set_mid_db(new_db),
-            2 => g.set_treble_db(new_db),
-            _ => {}
+    /// Rebuild the folder pane from the songs below the browsed folder.
+    fn refresh_folders(&mut self) {
+        let Some(dir) = self.browsed_dir().map(Path::to_path_buf) else {
+            return;
+        };
+        let names: BTreeSet<&std::ffi::OsStr> = self
+            .songs
+            .iter()
+            .filter_map(|song| song.path.strip_prefix(&dir).ok())
+            .filter_map(|rel| {
+                let mut parts = rel.components();
+                let first = parts.next()?;
+                // Only folders: the file itself is the last component.
+                parts.next()?;
+                Some(first.as_os_str())
+            })
+            .collect();
+        let mut folders = Vec::new();
+        if dir != self.music_dir
+            && let Some(parent) = dir.parent()
+        {
+            folders.push(("..".to_string(), parent.to_path_buf()));
+        }
+        folders.extend(
+            names
+                .into_iter()
+                .map(|name| (format!("{}/", name.to_string_lossy()), dir.join(name))),
+        );
+        let Some(browse) = self.browse.as_mut() else {
+            return;
+        };
+        let selected = browse.folder_state.selected().unwrap_or(0);
+        browse.folder_state.select(if folders.is_empty() {
+            None
+        } else {
+            Some(selected.min(folders.len() - 1))
+        });
+        browse.folders = folders;
+    }
+
+    /// Enter in the folder pane: open the selected folder.
+    pub fn browse_open_folder(&mut self) {
+        let Some(browse) = self.browse.as_mut() else {
+            return;
+        };
+        let Some(target) = browse
+            .folder_state
+            .selected()
+            .and_then(|i| browse.folders.get(i))
+            .map(|(_, path)| path.clone())
+        else {
+            return;
+        };
+        self.browse_to(target);
+    }
+
+    /// Backspace while browsing: go up to the parent folder, stopping at the
+    /// top of the library.
+    pub fn browse_up(&mut self) {
+        let Some(dir) = self.browsed_dir() else { return };
+        if dir == self.music_dir {
+            return;
+        }
+        if let Some(parent) = dir.parent().map(Path::to_path_buf) {
+            self.browse_to(parent);
+        }
+    }
+
+    fn browse_to(&mut self, dir: PathBuf) {
+        let Some(browse) = self.browse.as_mut() else {
+            return;
+        };
+        browse.dir = dir;
+        browse.folder_state.select(Some(0));
+        self.refresh_view();
+    }
+
+    /// Select the folder pane entry computed by `target` from the current
+    /// one, clamped to the last entry (the list keys while it has focus).
+    pub fn browse_select_at(&mut self, target: impl FnOnce(usize) -> usize) {
+        let Some(browse) = self.browse.as_mut() else {
+            return;
+        };
+        if browse.folders.is_empty() {
+            return;
+        }
+        let current = browse.folder_state.selected().unwrap_or(0);
+        let pos = target(current).min(browse.folders.len() - 1);
+        browse.folder_state.select(Some(pos));
+    }
+
+    // ── Live library updates ──────────────────────────────────────────────
+
+    /// Called every tick: rescan the library in the background once files
+    /// in the music directory have changed and settled, and show the new
+    /// list when the scan is done. Waits while a playlist is open, since
+    /// closing it rescans anyway.
+    pub fn check_library_changes(&mut self) {
+        if self.playlist.is_some() {
+            // A scan still running would replace the playlist's songs.
+            self.rescan = None;
+            return;
+        }
+        if self.watcher.as_mut().is_some_and(|w| w.poll()) {
+            self.start_rescan();
+        }
+        let Some(rx) = &self.rescan else { return };
+        match rx.try_recv() {
+            Ok((songs, scan_errors, cache)) => {
+                self.rescan = None;
+                self.library_cache.merge(cache);
+                self.apply_rescan(songs, scan_errors);
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.rescan = None,
+        }
+    }
+
+    /// Scan `music_dir` on a background thread with a copy of the cache,
+    /// replacing any rescan still running for an earlier change.
+    fn start_rescan(&mut self) {
+        let music_dir = self.music_dir.clone();
+        let mut cache = self.library_cache.clone();
+        let extensions = self.extensions.clone();
+        let prefer_formats = self.prefer_formats.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (songs, errors) =
+                Self::scan_music(&music_dir, &mut cache, &extensions, prefer_formats.as_deref());
+            // A newer rescan or an open playlist may have dropped `rx`.
+            let _ = tx.send((songs, errors, cache));
+        });
+        self.rescan = Some(rx);
+    }
+
+    /// Re-scan `music_dir` right away; see `apply_rescan`.
+    fn rescan_library(&mut self) {
+        let (songs, scan_errors) = Self::scan_music(
+            &self.music_dir,
+            &mut self.library_cache,
+            &self.extensions,
+            self.prefer_formats.as_deref(),
+        );
+        self.apply_rescan(songs, scan_errors);
+    }
+
+    /// Replace the list with a fresh scan of the library, carrying the
+    /// playing and selected songs, the queue, the preloaded track and the
+    /// shuffle state over by path. If the playing file was removed,
+    /// playback stops.
+    fn apply_rescan(&mut self, songs: Vec<Song>, scan_errors: Vec<ScanError>) {
+        let old_paths: Vec<PathBuf> = self.songs.iter().map(|s| s.path.clone()).collect();
+        let path_of = |&i: &usize| old_paths[i].clone();
+        let playing = self.now_playing.as_ref().map(path_of);
+        let selected = old_paths.get(self.selected).cloned();
+        let preloaded = self.preloaded.map(|(i, q)| (path_of(&i), q));
+        let queue: Vec<PathBuf> = self.queue.iter().map(path_of).collect();
+        let order: Vec<PathBuf> = self.shuffle_order.iter().map(path_of).collect();
+        let history: Vec<PathBuf> = self.shuffle_history.iter().map(path_of).collect();
+
+        // Forget every index into the old list before replacing it.
+        self.now_playing = None;
+        self.preloaded = None;
+        self.queue.clear();
+        self.shuffle_order.clear();
+        self.shuffle_history.clear();
+        self.selected = 0;
+        self.songs = songs;
+        self.set_scan_errors(scan_errors);
+        self.apply_saved_track_state();
+        self.update_stats();
+        self.sort_songs();
+
+        let index: HashMap<PathBuf, usize> = self
+            .songs
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.path.clone(), i))
+            .collect();
+        let find = |path: &PathBuf| index.get(path).copied();
+
+        self.now_playing = playing.as_ref().and_then(find);
+        if playing.is_some() && self.now_playing.is_none() {
+            // The playing file is gone; the queued one may be too.
+            self.player.pause();
+        } else if let Some((path, from_queue)) = preloaded {
+            match find(&path) {
+                Some(idx) => self.preloaded = Some((idx, from_queue)),
+                None => {
+                    let _ = self.player.cancel_preload();
+                }
+            }
+        }
+        if let Some(idx) = self.now_playing {
+            self.measure_duration(idx);
+        }
+        self.selected = selected
+            .as_ref()
+            .and_then(find)
+            .or(self.now_playing)
+            .unwrap_or(0);
+        self.queue = queue.iter().filter_map(find).collect();
+        self.shuffle_order = order.iter().filter_map(find).collect();
+        self.shuffle_history = history.iter().filter_map(find).collect();
+        if self.shuffle {
+            // Songs that were just added join the pass still to be played.
+            let old: HashSet<&PathBuf> = old_paths.iter().collect();
+            let added = (0..self.songs.len())
+                .filter(|&i| self.is_track(i) && !old.contains(&self.songs[i].path));
+            self.shuffle_order.extend(added.collect::<Vec<_>>());
+            self.shuffle_order.shuffle(&mut rand::thread_rng());
+        }
+
+        self.scan_durations();
+        self.refresh_view();
+    }
+
+    /// `music_dir` as an absolute path, for telling the user where the
+    /// player looks for music.
+    pub fn music_dir_absolute(&self) -> PathBuf {
+        std::path::absolute(&self.music_dir).unwrap_or_else(|_| self.music_dir.clone())
+    }
+
+    /// Enter on an empty library whose folder does not exist: create it and
+    /// start watching it, so files copied in show up right away.
+    pub fn create_music_dir(&mut self) {
+        if self.music_dir.exists() || is_stream(&self.music_dir) {
+            return;
+        }
+        let dir = self.music_dir_absolute();
+        if let Err(e) = fs::create_dir_all(&self.music_dir) {
+            self.set_error(format!("Cannot create {}: {e}", dir.display()));
+            return;
+        }
+        self.watcher = LibraryWatcher::new(&self.music_dir);
+        self.rescan_library();
+        self.set_status(format!("Created {}", dir.display()));
+    }
+
+    // ── Sorting ────────────────────────────────────────────────────────────
+
+    /// Switch to the next sort order (`o`) and re-sort the list.
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.sort_songs();
+        self.refresh_view();
+    }
+
+    /// Re-sort `songs` by `sort`, remapping every stored song index (selection,
+    /// now playing, queue and shuffle state) so they keep pointing at the same songs.
+    fn sort_songs(&mut self) {
+        let mut indexed: Vec<(usize, Song)> = self.songs.drain(..).enumerate().collect();
+        match self.sort {
+            SortOrder::Path => indexed.sort_by(|(_, a), (_, b)| a.path.cmp(&b.path)),
+            SortOrder::NameAsc | SortOrder::NameDesc => {
+                indexed.sort_by_cached_key(|(_, s)| s.name.to_lowercase())
+            }
+            SortOrder::DurationAsc | SortOrder::DurationDesc => {
+                indexed.sort_by_key(|(_, s)| s.duration)
+            }
+        }
+        if matches!(self.sort, SortOrder::NameDesc | SortOrder::DurationDesc) {
+            indexed.reverse();
+        }
+
+        let mut new_index = vec![0; indexed.len()];
+        for (new, (old, _)) in indexed.iter().enumerate() {
+            new_index[*old] = new;
+        }
+        self.songs = indexed.into_iter().map(|(_, song)| song).collect();
+
+        let remap = |i: &mut usize| *i = new_index[*i];
+        if !self.songs.is_empty() {
+            remap(&mut self.selected);
+        }
+        if let Some(playing) = self.now_playing.as_mut() {
+            remap(playing);
+        }
+        if let Some((preloaded, _)) = self.preloaded.as_mut() {
+            remap(preloaded);
+        }
+        self.queue.iter_mut().for_each(remap);
+        self.shuffle_order.iter_mut().for_each(remap);
+        self.shuffle_history.iter_mut().for_each(remap);
+    }
+
+    /// Whether `idx` is a playable track (playlists are skipped when advancing).
+    fn is_track(&self, idx: usize) -> bool {
+        !self.songs[idx].is_playlist()
+    }
+
+    /// Play song `idx`. Returns false, with the reason in `last_error` and
+    /// nothing playing, when playback cannot start; a file that cannot be
+    /// opened or decoded is only found out by `check_load`.
+    fn play_index(&mut self, idx: usize) -> bool {
+        // Playing a file rebuilds the sink, discarding any preloaded track.
+        self.drop_preload();
+        self.clear_ab_loop();
+        self.advance_skips = None;
+        if idx >= self.songs.len() {
+            return false;
+        }
+        self.apply_track_eq(idx);
+        // Restarting or repeating the same track keeps its saved position.
+        let switching = self.now_playing != Some(idx);
+        if switching {
+            self.remember_position();
+            if self.prompt.as_ref().is_some_and(|p| !p.kind.takes_input()) {
+                self.prompt = None;
+            }
+            self.title_scroll = 0;
+        }
+        let song = &self.songs[idx];
+        match self.player.play_file(&song.path) {
+            Ok(()) => {
+                self.now_playing = Some(idx);
+                self.record_play(idx);
+                self.load_started = Instant::now();
+                self.measure_duration(idx);
+                if switching {
+                    self.offer_resume(idx);
+                }
+                true
+            }
+            Err(e) => {
+                self.set_error(format!("Cannot play {}: {e}", song.name));
+                self.now_playing = None;
+                false
+            }
+        }
+    }
+
+    /// Select and play `next`, recording the current track in the shuffle
+    /// history. Songs that fail to open are skipped for the ones after them.
+    fn advance_to(&mut self, next: usize) {
+        self.skip_to(next, 0);
+    }
+
+    /// `advance_to`, with `skipped` songs already passed over as unplayable.
+    fn skip_to(&mut self, next: usize, skipped: usize) {
+        let mut next = next;
+        // Bounded, in case no song in the list can be played.
+        for skipped in skipped..self.songs.len() {
+            self.record_advance(next);
+            if self.play_index(next) {
+                self.advance_skips = Some(skipped);
+                return;
+            }
+            match self.following_index(next) {
+                Some(following) => next = following,
+                None => return,
+            }
+        }
+    }
+
+    /// Called every tick: see whether the track `play_index` started loading
+    /// could be opened. One that cannot is reported and nothing plays; when
+    /// it was reached by advancing, the songs after it are tried instead.
+    pub fn check_load(&mut self) {
+        let Some(result) = self.player.poll_load() else {
+            return;
+        };
+        let skipped = self.advance_skips.take();
+        let Err(e) = result else { return };
+        let Some(idx) = self.now_playing.take() else {
+            return;
+        };
+        self.set_error(format!("Cannot play {}: {e}", self.songs[idx].name));
+        // A resume offer for the failed track is moot.
+        if self.prompt.as_ref().is_some_and(|p| !p.kind.takes_input()) {
+            self.prompt = None;
+        }
+        if let Some(skipped) = skipped
+            && let Some(next) = self.following_index(idx)
+        {
+            self.skip_to(next, skipped + 1);
+        }
+    }
+
+    /// Spinner frame while the playing track is still being opened.
+    pub fn loading_frame(&self) -> Option<usize> {
+        self.player
+            .is_loading()
+            .then(|| (self.load_started.elapsed().as_millis() / 100) as usize)
+    }
+
+    /// How long the player has been running.
+    pub fn session_elapsed(&self) -> Duration {
+        self.session_start.elapsed()
+    }
+
+    /// Show `message` in the now-playing panel for `ERROR_TIMEOUT`.
+    fn set_error(&mut self, message: String) {
+        self.last_error = Some(message);
+        self.last_error_at = Instant::now();
+    }
+
+    /// Called every tick: hide the error message once it has been shown
+    /// long enough.
+    pub fn check_error_timeout(&mut self) {
+        if self.last_error.is_some() && self.last_error_at.elapsed() >= ERROR_TIMEOUT {
+            self.last_error = None;
+        }
+    }
+
+    /// Called every tick: move a now-playing title that does not fit on by
+    /// one character, unless playback is paused. `title_width` is in
+    /// columns, so wide (CJK, emoji) titles are measured as they are drawn.
+    pub fn check_title_scroll(&mut self) {
+        let name = self.now_playing_name();
+        let len = name.chars().count();
+        if self.title_width == 0 || name.width() <= self.title_width || !self.is_playing() {
+            return;
+        }
+        self.title_scroll = (self.title_scroll + 1) % (len + TITLE_SCROLL_GAP.chars().count());
+    }
+
+    /// Where the now-playing title starts, given `width` cells to show it
+    /// in. A change of width starts the scroll over.
+    pub fn title_scroll(&mut self, width: usize) -> usize {
+        if width != self.title_width {
+            self.title_width = width;
+            self.title_scroll = 0;
+        }
+        self.title_scroll
+    }
+
+    /// Confirm an action in the now-playing panel for `STATUS_TIMEOUT`.
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status = Some((message.into(), Instant::now()));
+    }
+
+    /// Called every tick: hide the status message once it has expired.
+    pub fn check_status_timeout(&mut self) {
+        if self
+            .status
+            .as_ref()
+            .is_some_and(|(_, at)| at.elapsed() >= STATUS_TIMEOUT)
+        {
+            self.status = None;
+        }
+    }
+
+    /// Book-keeping for moving on to `next`: shuffle history and selection.
+    fn record_advance(&mut self, next: usize) {
+        if self.shuffle
+            && let Some(cur) = self.now_playing
+        {
+            self.shuffle_history.push(cur);
+        }
+        self.select_song(next);
+    }
+
+    /// Forget the preloaded song, handing it back to the queue or shuffle
+    /// order it was taken from so the next advance picks it again.
+    fn drop_preload(&mut self) {
+        let Some((idx, from_queue)) = self.preloaded.take() else {
+            return;
+        };
+        if from_queue {
+            self.queue.push_front(idx);
+        } else if self.shuffle && self.repeat != RepeatMode::One {
+            self.shuffle_order.push(idx);
+        }
+    }
+
+    /// The track that follows `idx`: the head of the queue if any, otherwise
+    /// the next song honouring shuffle and `RepeatMode::All` wrap-around.
+    /// `None` means the end of the playlist was reached.
+    fn following_index(&mut self, idx: usize) -> Option<usize> {
+        // Indices left over from a longer list lead nowhere.
+        let len = self.songs.len();
+        self.queue.retain(|&i| i < len);
+        self.shuffle_order.retain(|&i| i < len);
+        if let Some(next) = self.queue.pop_front() {
+            self.shuffle_order.retain(|&i| i != next);
+            return Some(next);
+        }
+        if self.shuffle {
+            return self.next_shuffled();
+        }
+        let after = (idx + 1..self.songs.len()).find(|&i| self.is_track(i));
+        match self.repeat {
+            RepeatMode::All => after.or_else(|| (0..=idx).find(|&i| self.is_track(i))),
+            _ => after,
+        }
+    }
+
+    /// The playing song's index. If it no longer points into `songs` (the
+    /// list shrank under it), playback stops and `None` is returned; a
+    /// preloaded song past the end is dropped either way.
+    fn playing_index(&mut self) -> Option<usize> {
+        if self.preloaded.is_some_and(|(next, _)| next >= self.songs.len()) {
+            self.preloaded = None;
+            let _ = self.player.cancel_preload();
+        }
+        let idx = self.now_playing?;
+        if idx < self.songs.len() {
+            return Some(idx);
+        }
+        self.now_playing = None;
+        self.preloaded = None;
+        let _ = self.player.cancel_preload();
+        self.player.pause();
+        None
+    }
+
+    pub fn toggle_pause(&self) {
+        if self.now_playing.is_some() {
+            self.player.toggle_pause();
+        }
+    }
+
+    pub fn next_track(&mut self) {
+        let playing = self.playing_index();
+        if self.songs.is_empty() {
+            return;
+        }
+        self.drop_preload();
+        match playing {
+            Some(idx) => {
+                if let Some(next) = self.following_index(idx) {
+                    self.advance_to(next);
+                }
+            }
+            None => self.play_selected(),
+        }
+    }
+
+    pub fn prev_track(&mut self) {
+        let playing = self.playing_index();
+        if self.songs.is_empty() {
+            return;
+        }
+        self.drop_preload();
+        match playing {
+            Some(idx) => {
+                // If more than 3 seconds in, restart current track
+                if self.player.position().as_secs() > 3 {
+                    self.play_index(idx);
+                    return;
+                }
+                let prev = if self.shuffle {
+                    let len = self.songs.len();
+                    let Some(prev) = self.shuffle_history.pop().filter(|&i| i < len) else {
+                        return;
+                    };
+                    // Put the current track back so `next_track` returns to it.
+                    self.shuffle_order.push(idx);
+                    prev
+                } else {
+                    let before = (0..idx).rev().find(|&i| self.is_track(i));
+                    let wrapped = match self.repeat {
+                        RepeatMode::All => (idx..self.songs.len()).rev().find(|&i| self.is_track(i)),
+                        _ => None,
+                    };
+                    match before.or(wrapped) {
+                        Some(prev) => prev,
+                        None => return,
+                    }
+                };
+                self.select_song(prev);
+                self.play_index(prev);
+            }
+            None => self.play_selected(),
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        if self.view.is_empty() {
+            return;
+        }
+        let pos = self
+            .view_position()
+            .map_or(0, |p| (p + 1).min(self.view.len() - 1));
+        self.selected = self.view[pos];
+        self.select_view_position(Some(pos));
+    }
+
+    pub fn select_prev(&mut self) {
+        if self.view.is_empty() {
+            return;
+        }
+        let pos = self.view_position().map_or(0, |p| p.saturating_sub(1));
+        self.selected = self.view[pos];
+        self.select_view_position(Some(pos));
+    }
+
+    /// PageUp: move the selection up by one screenful of rows.
+    pub fn select_page_up(&mut self) {
+        let page = self.page_size();
+        self.select_view_at(|pos| pos.map_or(0, |p| p.saturating_sub(page)));
+    }
+
+    /// PageDown: move the selection down by one screenful of rows.
+    pub fn select_page_down(&mut self) {
+        let page = self.page_size();
+        self.select_view_at(|pos| pos.map_or(0, |p| p + page));
+    }
+
+    /// Home: select the first visible song.
+    pub fn select_first(&mut self) {
+        self.select_view_at(|_| 0);
+    }
+
+    /// End: select the last visible song.
+    pub fn select_last(&mut self) {
+        self.select_view_at(|_| usize::MAX);
+    }
+
+    /// Select row `n` (1-based) of the list, or the last row if it is
+    /// shorter (`12G`).
+    pub fn select_row(&mut self, n: usize) {
+        self.select_view_at(|_| n.saturating_sub(1));
+    }
+
+    /// Add `digit` to the count being typed.
+    pub fn push_count(&mut self, digit: u32) {
+        let pending = self.pending_keys.get_or_insert(PendingKeys {
+            count: None,
+            g: false,
+            at: Instant::now(),
+        });
+        let count = pending.count.unwrap_or(0) * 10 + digit as usize;
+        pending.count = Some(count.min(MAX_COUNT));
+        pending.at = Instant::now();
+    }
+
+    /// A first `g`, keeping any count typed before it.
+    pub fn push_g(&mut self) {
+        let count = self.pending_keys.and_then(|pending| pending.count);
+        self.pending_keys = Some(PendingKeys {
+            count,
+            g: true,
+            at: Instant::now(),
+        });
+    }
+
+    /// Called every tick: a prefix left waiting too long, which the caller
+    /// then handles as plain key presses.
+    pub fn take_expired_keys(&mut self) -> Option<PendingKeys> {
+        self.pending_keys
+            .filter(|pending| pending.at.elapsed() >= PENDING_KEY_TIMEOUT)
+            .and_then(|_| self.pending_keys.take())
+    }
+
+    /// `i`: select the playing track, scrolling the list to it. Filters
+    /// that hide it are cleared, and the folder browser moves to its folder.
+    pub fn select_playing(&mut self) {
+        let Some(idx) = self.now_playing else {
+            self.set_status("Nothing playing");
+            return;
+        };
+        if let Some(browse) = self.browse.as_mut() {
+            browse.folders_focused = false;
+            if let Some(dir) = self.songs[idx].path.parent()
+                && dir.starts_with(&self.music_dir)
+                && dir != browse.dir
+            {
+                self.browse_to(dir.to_path_buf());
+            }
+        }
+        if !self.view.contains(&idx) {
+            self.search_mode = false;
+            self.search_query.clear();
+            self.starred_only = false;
+            self.history_only = false;
+            self.refresh_view();
+        }
+        self.select_song(idx);
+    }
+
+    /// Rows of the song list shown on screen (at least one), from the last
+    /// drawn frame.
+    pub fn page_size(&self) -> usize {
+        usize::from(self.list_area.height).max(1)
+    }
+
+    /// Select the view position computed by `target` from the current one,
+    /// clamped to the end of the view.
+    fn select_view_at(&mut self, target: impl FnOnce(Option<usize>) -> usize) {
+        if self.view.is_empty() {
+            return;
+        }
+        let pos = target(self.view_position()).min(self.view.len() - 1);
+        self.selected = self.view[pos];
+        self.select_view_position(Some(pos));
+    }
+
+    /// Alt+letter: select the next visible song whose name starts with `c`
+    /// (ignoring case), wrapping around. Starts after the selection, so
+    /// repeating the letter cycles through every match.
+    pub fn jump_to_letter(&mut self, c: char) {
+        let c = c.to_lowercase().collect::<String>();
+        let len = self.view.len();
+        let start = self.view_position().map_or(0, |p| p + 1);
+        let found = (0..len).map(|offset| (start + offset) % len).find(|&pos| {
+            self.songs[self.view[pos]]
+                .name
+                .to_lowercase()
+                .starts_with(&c)
+        });
+        if let Some(pos) = found {
+            self.selected = self.view[pos];
+            self.select_view_position(Some(pos));
+        }
+    }
+
+    /// Highlight song `idx`; the list shows no highlight if it is filtered out.
+    fn select_song(&mut self, idx: usize) {
+        self.selected = idx;
+        self.select_view_position(self.view_position());
+    }
+
+    /// Highlight row `pos` of the view and move the scrollbar with it.
+    fn select_view_position(&mut self, pos: Option<usize>) {
+        self.list_state.select(pos);
+        self.scroll_state = self
+            .scroll_state
+            .content_length(self.view.len())
+            .position(pos.unwrap_or(0));
+    }
+
+    /// Position of the selected song within `view`, if it is visible.
+    fn view_position(&self) -> Option<usize> {
+        self.view.iter().position(|&i| i == self.selected)
+    }
+
+    /// Recompute `view` from the search query, the starred-only filter and
+    /// the browsed folder, keeping the selection when it is still visible and
+    /// otherwise moving it to the first match.
+    fn refresh_view(&mut self) {
+        let query = self.search_query.to_lowercase();
+        let dir = self.browsed_dir().map(Path::to_path_buf);
+        self.view = (0..self.songs.len())
+            .filter(|&i| {
+                let song = &self.songs[i];
+                (query.is_empty() || song.name.to_lowercase().contains(&query))
+                    && (!self.starred_only || song.starred)
+                    && (!self.history_only || song.last_played.is_some())
+                    && dir.as_deref().is_none_or(|dir| song.path.parent() == Some(dir))
+            })
+            .collect();
+        if self.history_only {
+            self.view
+                .sort_by_key(|&i| std::cmp::Reverse(self.songs[i].last_played));
+            self.view.truncate(HISTORY_LEN);
+        }
+        self.refresh_folders();
+        if !self.view.contains(&self.selected)
+            && let Some(&first) = self.view.first()
+        {
+            self.selected = first;
+        }
+        self.select_view_position(self.view_position());
+    }
+
+    // ── Search ─────────────────────────────────────────────────────────────
+
+    /// Enter search mode (`/`): typed characters filter the list by name.
+    pub fn search_start(&mut self) {
+        self.search_mode = true;
+    }
+
+    pub fn search_push(&mut self, c: char) {
+        self.search_query.push(c);
+        self.refresh_view();
+    }
+
+    pub fn search_pop(&mut self) {
+        self.search_query.pop();
+        self.refresh_view();
+    }
+
+    /// Play (or queue, see `activate_selected`) the highlighted match and
+    /// stop typing, keeping the filter shown.
+    pub fn search_play(&mut self) {
+        self.search_mode = false;
+        self.activate_selected();
+    }
+
+    /// Clear the filter and restore the full list.
+    pub fn search_clear(&mut self) {
+        self.search_mode = false;
+        self.search_query.clear();
+        self.refresh_view();
+    }
+
+    pub fn search_active(&self) -> bool {
+        self.search_mode || !self.search_query.is_empty()
+    }
+
+    /// Raise the volume by the configured step, up to the configured
+    /// maximum.
+    pub fn volume_up(&mut self) {
+        let percent = (self.volume + self.volume_step).min(self.volume_max);
+        self.set_volume_percent(percent);
+    }
+
+    pub fn volume_down(&mut self) {
+        let percent = self.volume.saturating_sub(self.volume_step);
+        self.set_volume_percent(percent);
+    }
+
+    /// Set the volume to `percent`, at most the configured maximum.
+    pub fn set_volume(&mut self, percent: u16) {
+        self.set_volume_percent(percent.min(self.volume_max));
+    }
+
+    fn set_volume_percent(&mut self, percent: u16) {
+        self.volume = percent;
+        self.player.set_volume(self.volume_gain());
+    }
+
+    /// `<` / `>`: move the balance towards the left / right speaker.
+    pub fn balance_left(&mut self) {
+        self.set_balance(self.balance - BALANCE_STEP);
+    }
+
+    pub fn balance_right(&mut self) {
+        self.set_balance(self.balance + BALANCE_STEP);
+    }
+
+    /// Set the balance to `percent`, limited to ±100.
+    pub fn set_balance(&mut self, percent: i16) {
+        self.balance = percent.clamp(-100, 100);
+        self.player.set_balance(f32::from(self.balance) / 100.0);
+    }
+
+    pub fn balance_percent(&self) -> i16 {
+        self.balance
+    }
+
+    /// Sink gain for the shown volume. Up to 100% the volume is raised to
+    /// `volume_curve`, so equal steps sound about equally loud; above that the
+    /// gain grows linearly, keeping 150% at 1.5×.
+    fn volume_gain(&self) -> f32 {
+        let level = f32::from(self.volume) / 100.0;
+        if level <= 1.0 {
+            level.powf(self.volume_curve)
+        } else {
+            level
+        }
+    }
+
+    /// Right (`long`: Shift+Right): skip ahead by the configured step.
+    pub fn seek_forward(&mut self, long: bool) {
+        if self.now_playing.is_none() {
+            return;
+        }
+        let pos = self.player.position();
+        self.seek_within(pos + self.seek_step(long));
+    }
+
+    /// Left (`long`: Shift+Left): go back by the configured step.
+    pub fn seek_backward(&mut self, long: bool) {
+        if self.now_playing.is_none() {
+            return;
+        }
+        let pos = self.player.position();
+        let _ = self.player.seek(pos.saturating_sub(self.seek_step(long)));
+    }
+
+    /// How far Left/Right (`long`: with Shift) seek, from the config.
+    pub fn seek_step(&self, long: bool) -> Duration {
+        if long { self.seek_step_long } else { self.seek_step }
+    }
+
+    /// `R`: start the playing track over from the beginning, whatever the
+    /// position (unlike `p`, which goes back a track early on).
+    pub fn restart_track(&mut self) {
+        self.seek_within(Duration::ZERO);
+    }
+
+    // ── Mouse ──────────────────────────────────────────────────────────────
+
+    /// Left click at a terminal cell: select a list row (double-click plays
+    /// it) or seek to the clicked point of the progress bar.
+    pub fn click(&mut self, column: u16, row: u16) {
+        if self.list_area.contains((column, row).into()) {
+            let pos = self.list_state.offset() + (row - self.list_area.y) as usize;
+            let Some(&idx) = self.view.get(pos) else {
+                return;
+            };
+            let double = self
+                .last_click
+                .is_some_and(|(last, at)| last == idx && at.elapsed() < DOUBLE_CLICK);
+            self.select_song(idx);
+            if double {
+                self.last_click = None;
+                self.activate_selected();
+            } else {
+                self.last_click = Some((idx, Instant::now()));
+            }
+        } else if self.progress_area.contains((column, row).into()) {
+            let ratio = (column - self.progress_area.x) as f64 / self.progress_area.width as f64;
+            self.seek_to_ratio(ratio);
+        }
+    }
+
+    /// Seek to a fraction (0.0–1.0) of the current track's duration (mouse
+    /// clicks and the `0`–`9` keys). Ignored when the duration is unknown.
+    pub fn seek_to_ratio(&mut self, ratio: f64) {
+        if self.now_playing.is_none() {
+            return;
+        }
+        let Some(dur) = self.current_duration() else {
+            return;
+        };
+        self.seek_within(dur.mul_f64(ratio.clamp(0.0, 1.0)));
+    }
+
+    /// Seek to `pos` in the current track (see `seek_within`).
+    #[cfg_attr(not(all(feature = "mpris", target_os = "linux")), allow(dead_code))]
+    pub fn seek_to(&mut self, pos: Duration) {
+        self.seek_within(pos);
+    }
+
+    /// Seek the playing track to `pos`. A target at or within `END_MARGIN`
+    /// of the end finishes the track instead, as if it had played out, rather
+    /// than re-opening it with nothing left to play.
+    fn seek_within(&mut self, pos: Duration) {
+        let Some(idx) = self.now_playing else { return };
+        if let Some(dur) = self.current_duration()
+            && pos.saturating_add(END_MARGIN) >= dur
+        {
+            self.finish_track(idx);
+            return;
+        }
+        let _ = self.player.seek(pos);
+    }
+
+    /// Step the playback speed by 0.05 (0.5x–2.0x).
+    pub fn speed_up(&mut self) {
+        let speed = self.player.speed() + 0.05;
+        let _ = self.player.set_speed((speed * 100.0).round() / 100.0);
+    }
+
+    pub fn speed_down(&mut self) {
+        let speed = self.player.speed() - 0.05;
+        let _ = self.player.set_speed((speed * 100.0).round() / 100.0);
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.player.speed()
+    }
+
+    /// `g`: toggle ReplayGain normalisation.
+    pub fn toggle_replay_gain(&mut self) {
+        let on = !self.player.replay_gain();
+        let _ = self.player.set_replay_gain(on);
+        self.set_status(if on { "ReplayGain on" } else { "ReplayGain off" });
+    }
+
+    pub fn replay_gain(&self) -> bool {
+        self.player.replay_gain()
+    }
+
+    /// `D`: toggle the mono downmix of the output.
+    pub fn toggle_mono(&mut self) {
+        let on = !self.player.mono();
+        let _ = self.player.set_mono(on);
+        self.set_status(if on { "Mono on" } else { "Mono off" });
+    }
+
+    /// Track gain in dB of the playing song, if it is tagged with one.
+    pub fn now_playing_gain_db(&self) -> Option<f32> {
+        self.now_playing
+            .and_then(|idx| self.songs[idx].tags.track_gain_db)
+    }
+
+    pub fn cycle_palette(&mut self) {
+        self.palette = self.palette.next();
+    }
+
+    pub fn toggle_remaining(&mut self) {
+        self.show_remaining = !self.show_remaining;
+    }
+
+    pub fn toggle_repeat(&mut self) {
+        self.repeat = match self.repeat {
+            RepeatMode::Off => RepeatMode::All,
+            RepeatMode::All => RepeatMode::One,
+            RepeatMode::One => RepeatMode::Off,
+        };
+        self.set_status(format!("Repeat: {}", self.repeat.label()));
+    }
+
+    /// Append the selected song to the "up next" queue.
+    pub fn enqueue_selected(&mut self) {
+        if self.view.contains(&self.selected) && self.is_track(self.selected) {
+            self.queue.push_back(self.selected);
+            self.set_status(format!("Queued ({} up next)", self.queue.len()));
+        }
+    }
+
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+        self.set_status("Queue cleared");
+    }
+
+    /// The folder `P` (and `a` in the folder pane) acts on: the one
+    /// highlighted in the folder pane, else the browsed one, else that of
+    /// the selected song.
+    pub fn target_folder(&self) -> Option<PathBuf> {
+        if self.folders_focused() {
+            let browse = self.browse.as_ref()?;
+            let i = browse.folder_state.selected()?;
+            return browse.folders.get(i).map(|(_, dir)| dir.clone());
+        }
+        if let Some(dir) = self.browsed_dir() {
+            return Some(dir.to_path_buf());
+        }
+        self.view
+            .contains(&self.selected)
+            .then(|| self.songs[self.selected].path.parent())
+            .flatten()
+            .map(Path::to_path_buf)
+    }
+
+    /// Tracks in `dir` and the folders below it, in the list's sort order.
+    /// Playlists are left out.
+    fn folder_tracks(&self, dir: &Path) -> Vec<usize> {
+        (0..self.songs.len())
+            .filter(|&i| self.is_track(i) && self.songs[i].path.starts_with(dir))
+            .collect()
+    }
+
+    /// `P`: play every track in `dir` and its subfolders as an ad-hoc
+    /// playlist. The first starts now and the rest replace the queue.
+    pub fn play_folder(&mut self, dir: &Path) {
+        let mut tracks = VecDeque::from(self.folder_tracks(dir));
+        let Some(first) = tracks.pop_front() else {
+            self.set_status("No tracks in this folder");
+            return;
+        };
+        if self.shuffle {
+            self.shuffle_order.retain(|&i| i != first);
+            if let Some(cur) = self.now_playing {
+                self.shuffle_history.push(cur);
+            }
+        }
+        if self.play_index(first) {
+            self.queue = tracks;
+            self.set_status(format!("Playing folder ({} up next)", self.queue.len()));
+        }
+    }
+
+    /// `a` in the folder pane: append every track in `dir` and its
+    /// subfolders to the queue.
+    pub fn enqueue_folder(&mut self, dir: &Path) {
+        let tracks = self.folder_tracks(dir);
+        if tracks.is_empty() {
+            self.set_status("No tracks in this folder");
+            return;
+        }
+        let count = tracks.len();
+        self.queue.extend(tracks);
+        self.set_status(format!("Queued {count} tracks ({} up next)", self.queue.len()));
+    }
+
+    pub fn toggle_shuffle(&mut self) {
+        self.shuffle = !self.shuffle;
+        self.shuffle_history.clear();
+        if self.shuffle {
+            self.reshuffle();
+        } else {
+            self.shuffle_order.clear();
+        }
+        self.set_status(if self.shuffle { "Shuffle on" } else { "Shuffle off" });
+    }
+
+    /// Start a new shuffle pass over every song except the one playing.
+    fn reshuffle(&mut self) {
+        let playing = self.now_playing;
+        self.shuffle_order = (0..self.songs.len())
+            .filter(|&i| Some(i) != playing && self.is_track(i))
+            .collect();
+        self.shuffle_order.shuffle(&mut rand::thread_rng());
+    }
+
+    /// Pop the next unplayed shuffled song. When the pass is exhausted it is
+    /// reshuffled under `RepeatMode::All`; otherwise playback ends.
+    fn next_shuffled(&mut self) -> Option<usize> {
+        if self.shuffle_order.is_empty() && self.repeat == RepeatMode::All {
+            self.reshuffle();
+            // A one-song library has nothing else to shuffle in; replay it.
+            if self.shuffle_order.is_empty() {
+                return self.now_playing;
+            }
+        }
+        self.shuffle_order.pop()
+    }
+
+    /// Called every tick: follow the player onto a preloaded track, preload
+    /// the next one near the end of the current track, and start the next
+    /// track when playback ran out without one.
+    pub fn check_track_end(&mut self) {
+        let Some(idx) = self.playing_index() else {
+            return;
+        };
+        if self.player.take_advanced()
+            && let Some((next, _)) = self.preloaded.take()
+        {
+            if self.repeat != RepeatMode::One {
+                self.record_advance(next);
+            }
+            self.count_play(idx);
+            self.forget_position(idx);
+            self.now_playing = Some(next);
+            self.record_play(next);
+            self.measure_duration(next);
+            self.apply_track_eq(next);
+            self.clear_ab_loop();
+            if next != idx {
+                self.offer_resume(next);
+                self.title_scroll = 0;
+            }
+            if self.sleep_expired() {
+                self.sleep_stop();
+            }
+            return;
+        }
+        if self.player.is_paused() {
+            return;
+        }
+        if !self.player.is_empty() {
+            self.maybe_preload(idx);
+            return;
+        }
+        self.count_play(idx);
+        self.forget_position(idx);
+        self.finish_track(idx);
+    }
+
+    /// Move on from track `idx` as when it plays out: stop for an expired
+    /// sleep timer, otherwise play the next song or repeat it.
+    fn finish_track(&mut self, idx: usize) {
+        if self.sleep_expired() {
+            self.sleep_stop();
+            return;
+        }
+        // A preloaded song is still the right next one if the player dropped
+        // it (e.g. after a re-open for seeking or a speed change).
+        let next = match self.preloaded.take() {
+            Some((next, _)) => Some(next),
+            None if self.repeat == RepeatMode::One => Some(idx),
+            None => self.following_index(idx),
+        };
+        match next {
+            Some(next) if next == idx && self.repeat == RepeatMode::One => {
+                self.play_index(idx);
+            }
+            Some(next) => self.advance_to(next),
+            None => self.now_playing = None,
+        }
+    }
+
+    /// Append the following track to the player once the current one is
+    /// within `PRELOAD_AHEAD` of its end.
+    fn maybe_preload(&mut self, idx: usize) {
+        if self.preloaded.is_some() || self.sleep_expired() {
+            return;
+        }
+        let Some(duration) = self.current_duration() else {
+            return;
+        };
+        if duration.saturating_sub(self.player.position()) > PRELOAD_AHEAD {
+            return;
+        }
+        let from_queue = self.repeat != RepeatMode::One && !self.queue.is_empty();
+        let next = match self.repeat {
+            RepeatMode::One => Some(idx),
+            RepeatMode::All | RepeatMode::Off => self.following_index(idx),
+        };
+        let Some(next) = next else { return };
+        // Remembered even if appending fails, so the end-of-track path picks
+        // the same song (and handles the error) instead of retrying every tick.
+        self.preloaded = Some((next, from_queue));
+        let _ = self.player.preload(&self.songs[next].path);
+    }
+
+    // ── A-B loop ───────────────────────────────────────────────────────────
+
+    /// `l`: mark A at the current position, then B, then clear both.
+    pub fn cycle_ab_loop(&mut self) {
+        if self.now_playing.is_none() {
+            return;
+        }
+        let pos = self.player.position();
+        match (self.loop_a, self.loop_b) {
+            (None, _) => self.loop_a = Some(pos),
+            (Some(a), None) if pos > a => self.loop_b = Some(pos),
+            // B before A: start over with A here.
+            (Some(_), None) => self.loop_a = Some(pos),
+            (Some(_), Some(_)) => self.clear_ab_loop(),
+        }
+        let status = match (self.loop_a, self.loop_b) {
+            (Some(_), None) => "Loop point A set",
+            (Some(_), Some(_)) => "Loop point B set, looping A-B",
+            _ => "A-B loop cleared",
+        };
+        self.set_status(status);
+    }
+
+    fn clear_ab_loop(&mut self) {
+        self.loop_a = None;
+        self.loop_b = None;
+    }
+
+    /// Called every tick: jump back to A once playback passes B.
+    pub fn check_ab_loop(&mut self) {
+        if let (Some(a), Some(b)) = (self.loop_a, self.loop_b)
+            && self.now_playing.is_some()
+            && self.player.position() >= b
+        {
+            let _ = self.player.seek(a);
+        }
+    }
+
+    // ── Bookmarks ──────────────────────────────────────────────────────────
+
+    /// Bookmarks of the playing track.
+    pub fn current_bookmarks(&self) -> &[Duration] {
+        match self.now_playing {
+            Some(idx) => self.bookmarks.get(&self.songs[idx].path),
+            None => &[],
+        }
+    }
+
+    /// `m`: bookmark the current position of the playing track.
+    pub fn add_bookmark(&mut self) {
+        let Some(idx) = self.now_playing else { return };
+        let pos = self.player.position();
+        self.bookmarks.add(&self.songs[idx].path, pos);
+        let _ = self.bookmarks.save();
+        self.set_status("Bookmark added");
+    }
+
+    /// `M`: remove every bookmark of the playing track.
+    pub fn clear_bookmarks(&mut self) {
+        let Some(idx) = self.now_playing else { return };
+        self.bookmarks.clear(&self.songs[idx].path);
+        let _ = self.bookmarks.save();
+        self.set_status("Bookmarks cleared");
+    }
+
+    /// Seek to the first bookmark after the current position.
+    pub fn next_bookmark(&mut self) {
+        let pos = self.current_position();
+        let next = self
+            .current_bookmarks()
+            .iter()
+            .copied()
+            .find(|&m| m > pos + Duration::from_millis(500));
+        if let Some(at) = next {
+            let _ = self.player.seek(at);
+        }
+    }
+
+    /// Seek to the last bookmark before the current position. Marks less than
+    /// two seconds back are skipped so repeated presses keep going back.
+    pub fn prev_bookmark(&mut self) {
+        let pos = self.current_position();
+        let prev = self
+            .current_bookmarks()
+            .iter()
+            .copied()
+            .rfind(|&m| m + Duration::from_secs(2) < pos);
+        if let Some(at) = prev {
+            let _ = self.player.seek(at);
+        }
+    }
+
+    // ── Sleep timer ────────────────────────────────────────────────────────
+
+    /// Start a sleep timer of `minutes`; zero cancels it. A timer too far
+    /// off for the clock is refused, returning false.
+    pub fn set_sleep_timer(&mut self, minutes: u64) -> bool {
+        if minutes == 0 {
+            self.sleep_deadline = None;
+            return true;
+        }
+        let deadline = minutes
+            .checked_mul(60)
+            .and_then(|secs| Instant::now().checked_add(Duration::from_secs(secs)));
+        if deadline.is_some() {
+            self.sleep_deadline = deadline;
+        }
+        deadline.is_some()
+    }
+
+    pub fn toggle_sleep_finish_track(&mut self) {
+        self.sleep_finish_track = !self.sleep_finish_track;
+        self.set_status(if self.sleep_finish_track {
+            "Sleep timer will finish the track"
+        } else {
+            "Sleep timer stops mid-track"
+        });
+    }
+
+    /// Time left on the sleep timer, if one is set.
+    pub fn sleep_remaining(&self) -> Option<Duration> {
+        self.sleep_deadline
+            .map(|d| d.saturating_duration_since(Instant::now()))
+    }
+
+    fn sleep_expired(&self) -> bool {
+        self.sleep_deadline.is_some_and(|d| Instant::now() >= d)
+    }
+
+    fn sleep_stop(&mut self) {
+        self.player.pause();
+        self.preloaded = None;
+        self.now_playing = None;
+        self.sleep_deadline = None;
+    }
+
+    /// Called every tick: stop playback once the sleep deadline passes (or,
+    /// with "finish track" on, leave it to `check_track_end` at the track's end).
+    pub fn check_sleep_timer(&mut self) {
+        if self.sleep_expired() && !self.sleep_finish_track {
+            self.sleep_stop();
+        }
+    }
+
+    // ── Text prompt ────────────────────────────────────────────────────────
+
+    pub fn prompt_open(&mut self, kind: PromptKind) {
+        self.prompt = Some(Prompt {
+            kind,
+            input: String::new(),
+        });
+    }
+
+    /// Type `c` into the prompt. Yes/no questions take `y` or `n` instead.
+    pub fn prompt_push(&mut self, c: char) {
+        let Some(prompt) = &mut self.prompt else { return };
+        if prompt.kind.takes_input() {
+            prompt.input.push(c);
+            return;
+        }
+        match c.to_ascii_lowercase() {
+            'y' => self.prompt_submit(),
+            'n' => self.prompt_cancel(),
+            _ => {}
+        }
+    }
+
+    pub fn prompt_pop(&mut self) {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input.pop();
+        }
+    }
+
+    pub fn prompt_cancel(&mut self) {
+        self.prompt = None;
+    }
+
+    /// Close the prompt and apply its input. Unparseable input is ignored.
+    pub fn prompt_submit(&mut self) {
+        let Some(prompt) = self.prompt.take() else { return };
+        let input = prompt.input.trim();
+        match prompt.kind {
+            PromptKind::SleepTimer => self.sleep_timer_input(input),
+            PromptKind::SeekTo => self.seek_to_input(input),
+            PromptKind::Volume(_) => self.volume_input(input),
+            PromptKind::Resume(pos) => self.seek_to(pos),
+        }
+    }
+
+    /// `:`: open the prompt for a time to jump to.
+    pub fn prompt_seek(&mut self) {
+        if self.now_playing.is_none() {
+            self.set_status("Nothing playing");
+            return;
+        }
+        self.prompt_open(PromptKind::SeekTo);
+    }
+
+    /// `%`: open the prompt for a volume to set.
+    pub fn prompt_volume(&mut self) {
+        self.prompt_open(PromptKind::Volume(self.volume_max));
+    }
+
+    /// Set the volume typed into the `Volume` prompt, with or without a `%`;
+    /// values above the maximum set the maximum.
+    fn volume_input(&mut self, input: &str) {
+        let Ok(percent) = input.trim_end_matches('%').trim_end().parse::<u32>() else {
+            self.set_error(format!("Not a volume: “{input}”"));
+            return;
+        };
+        self.set_volume(u16::try_from(percent).unwrap_or(u16::MAX));
+        self.set_status(format!("Volume {}%", self.volume));
+    }
+
+    /// Set the sleep timer to the minutes typed into the `SleepTimer`
+    /// prompt. Input that is not a usable number of minutes only reports so.
+    fn sleep_timer_input(&mut self, input: &str) {
+        match input.parse::<u64>() {
+            Ok(minutes) if self.set_sleep_timer(minutes) => self.set_status(match minutes {
+                0 => "Sleep timer off".to_string(),
+                _ => format!("Sleep in {minutes} min"),
+            }),
+            _ => self.set_error(format!("Not a number of minutes: “{input}”")),
+        }
+    }
+
+    /// Seek to the time typed into the `SeekTo` prompt, within the track's
+    /// length. Input that is not a time only reports so.
+    fn seek_to_input(&mut self, input: &str) {
+        let (sign, time) = match input.as_bytes().first() {
+            Some(b'+') => (1, &input[1..]),
+            Some(b'-') => (-1, &input[1..]),
+            _ => (0, input),
+        };
+        let Some(time) = parse_time(time.trim()) else {
+            self.set_error(format!("Not a time: “{input}”"));
+            return;
+        };
+        let pos = self.current_position();
+        let target = match sign {
+            1 => pos.saturating_add(time),
+            -1 => pos.saturating_sub(time),
+            _ => time,
+        };
+        let target = self.current_duration().map_or(target, |dur| target.min(dur));
+        self.seek_within(target);
+    }
+
+    /// Elapsed time of the playing track, never past its known duration (the
+    /// sink may run slightly over before the next tick notices the end).
+    pub fn current_position(&self) -> Duration {
+        if self.now_playing.is_none() {
+            return Duration::ZERO;
+        }
+        let pos = self.player.position();
+        match self.current_duration() {
+            Some(dur) => pos.min(dur),
+            None => pos,
+        }
+    }
+
+    pub fn current_duration(&self) -> Option<Duration> {
+        self.now_playing.and_then(|idx| self.songs[idx].duration)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.now_playing.is_some() && !self.player.is_paused()
+    }
+
+    /// Nothing on screen is moving: playback is paused or stopped, no track
+    /// is loading, no key prefix is waiting and the spectrum has fallen to
+    /// zero. The main loop then redraws less often.
+    pub fn is_idle(&self) -> bool {
+        if self.is_playing() || self.player.is_loading() || self.pending_keys.is_some() {
+            return false;
+        }
+        let (left, right) = self.player.spectrum_peaks_stereo();
+        left.iter().chain(&right).all(|&peak| peak == 0)
+    }
+
+    /// The volume as shown, in percent (100% = unity gain).
+    pub fn volume_percent(&self) -> u16 {
+        self.volume
+    }
+
+    /// The highest volume the keys reach, in percent.
+    pub fn volume_max_percent(&self) -> u16 {
+        self.volume_max
+    }
+
+    pub fn now_playing_name(&self) -> &str {
+        self.now_playing
+            .map(|idx| self.songs[idx].name.as_str())
+            .unwrap_or("Nothing playing")
+    }
+
+    pub fn now_playing_album(&self) -> Option<&str> {
+        self.now_playing
+            .and_then(|idx| self.songs[idx].tags.album.as_deref())
+    }
+
+    /// Codec, sample rate and channels of the playing track, once loaded.
+    pub fn now_playing_format(&self) -> Option<Format> {
+        self.now_playing.and(self.player.format())
+    }
+
+    /// Cover art of the playing track, once loaded (`None` while loading or
+    /// when the file has none).
+    pub fn cover_art(&mut self) -> Option<&mut StatefulProtocol> {
+        let path = self.now_playing.map(|idx| self.songs[idx].path.as_path());
+        self.cover.get(path)
+    }
+
+    /// Called before each draw: take the analyser's bars if they are newer
+    /// than `spectrum_current`, which then becomes `spectrum_previous`.
+    pub fn sample_spectrum(&mut self) {
+        let at = self.player.spectrum_updated();
+        if at.is_some() && at == self.spectrum_current.at {
+            return;
+        }
+        let (left, right) = self.player.spectrum_stereo();
+        let (left_peaks, right_peaks) = self.player.spectrum_peaks_stereo();
+        let frame = SpectrumFrame { left, right, left_peaks, right_peaks, at };
+        self.spectrum_previous = std::mem::replace(&mut self.spectrum_current, frame);
+    }
+
+    /// How far to draw from `spectrum_previous` (0.0) towards
+    /// `spectrum_current` (1.0): the time since the current frame arrived,
+    /// as a fraction of the gap between the two. The chart thus runs one
+    /// analyser interval behind, but moves evenly whenever it is redrawn.
+    pub fn spectrum_blend(&self) -> f64 {
+        match (self.spectrum_previous.at, self.spectrum_current.at) {
+            (Some(previous), Some(current)) if current > previous => {
+                let gap = (current - previous).as_secs_f64();
+                (current.elapsed().as_secs_f64() / gap).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// Centre frequency of each analyser bar, for the visualizer's axis.
+    pub fn spectrum_frequencies(&self) -> Vec<f32> {
+        self.player.spectrum_frequencies()
+    }
+
+    pub fn visualizer_sensitivity(&self) -> f32 {
+        self.player.visualizer_sensitivity()
+    }
+
+    /// `}` / `{`: make the bars react more / less to the same level.
+    pub fn sensitivity_up(&mut self) {
+        let s = self.visualizer_sensitivity() * SENSITIVITY_STEP;
+        self.player.set_visualizer_sensitivity(s);
+    }
+
+    pub fn sensitivity_down(&mut self) {
+        let s = self.visualizer_sensitivity() / SENSITIVITY_STEP;
+        self.player.set_visualizer_sensitivity(s);
+    }
+
+    pub fn visualizer_stereo(&self) -> bool {
+        self.player.visualizer_stereo()
+    }
+
+    /// Called on every draw with whether the visualizer's bars are visible;
+    /// the analyser only hears about changes.
+    pub fn set_visualizer_hidden(&mut self, hidden: bool) {
+        if hidden != self.visualizer_hidden {
+            self.visualizer_hidden = hidden;
+            self.player.set_visualizer_hidden(hidden);
+        }
+    }
+
+    /// `V`: switch the visualizer between a mono mix and left/right spectra.
+    pub fn toggle_visualizer_stereo(&mut self) {
+        self.player
+            .set_visualizer_stereo(!self.player.visualizer_stereo());
+    }
+
+    // ── Help overlay ───────────────────────────────────────────────────────
+
+    pub fn help_toggle(&mut self) {
+        self.help_open = !self.help_open;
+    }
+
+    // ── Equalizer popup and band gains ─────────────────────────────────────
+
+    pub fn eq_popup_toggle(&mut self) {
+        self.eq_state.popup_open = !self.eq_state.popup_open;
+        self.eq_state.edit = None;
+    }
+
+    pub fn eq_popup_open(&self) -> bool {
+        self.eq_state.popup_open
+    }
+
+    pub fn eq_selected_band(&self) -> usize {
+        self.eq_state.selected_band
+    }
+
+    pub fn eq_select_prev_band(&mut self) {
+        self.eq_state.selected_band = self.eq_state.selected_band.saturating_sub(1);
+    }
+
+    pub fn eq_select_next_band(&mut self) {
+        self.eq_state.selected_band = (self.eq_state.selected_band + 1).min(self.eq_band_count().saturating_sub(1));
+    }
+
+    pub fn eq_band_count(&self) -> usize {
+        self.player.eq_gains().band_count()
+    }
+
+    pub fn eq_band_gain_db(&self, band: usize) -> f32 {
+        self.player.eq_gains().band_db(band)
+    }
+
+    pub fn eq_set_band_gain_db(&self, band: usize, db: f32) {
+        self.player.eq_gains().set_band_db(band, db);
+    }
+
+    /// Centre frequency of `band` in Hz.
+    pub fn eq_band_freq(&self, band: usize) -> f32 {
+        self.player.eq_gains().band_freq(band)
+    }
+
+    pub fn eq_set_band_freq(&self, band: usize, hz: f32) {
+        self.player.eq_gains().set_band_freq(band, hz);
+    }
+
+    /// Move the selected band's centre frequency up by a sixth of an octave.
+    pub fn eq_freq_up(&mut self) {
+        let band = self.eq_state.selected_band;
+        self.eq_set_band_freq(band, self.eq_band_freq(band) * EQ_FREQ_STEP);
+    }
+
+    pub fn eq_freq_down(&mut self) {
+        let band = self.eq_state.selected_band;
+        self.eq_set_band_freq(band, self.eq_band_freq(band) / EQ_FREQ_STEP);
+    }
+
+    pub fn eq_q(&self) -> f32 {
+        self.player.eq_gains().q()
+    }
+
+    pub fn eq_q_up(&mut self) {
+        self.player.eq_gains().set_q(self.eq_q() + 0.1);
+    }
+
+    pub fn eq_q_down(&mut self) {
+        self.player.eq_gains().set_q(self.eq_q() - 0.1);
+    }
+
+    /// Pre-amp gain in dB, applied before the EQ bands.
+    pub fn eq_preamp_db(&self) -> f32 {
+        self.player.eq_gains().preamp_db()
+    }
+
+    pub fn eq_preamp_up(&mut self) {
+        self.player.eq_gains().set_preamp_db(self.eq_preamp_db() + 0.5);
+    }
+
+    pub fn eq_preamp_down(&mut self) {
+        self.player.eq_gains().set_preamp_db(self.eq_preamp_db() - 0.5);
+    }
+
+    /// Raise the selected band by 1 dB, or 0.5 dB when `fine`.
+    pub fn eq_band_up(&mut self, fine: bool) {
+        let band = self.eq_state.selected_band;
+        let db = self.eq_band_gain_db(band);
+        self.eq_set_band_gain_db(band, (db + Self::eq_gain_step(fine)).min(12.0));
+        self.eq_state.preset = None;
+    }
+
+    pub fn eq_band_down(&mut self, fine: bool) {
+        let band = self.eq_state.selected_band;
+        let db = self.eq_band_gain_db(band);
+        self.eq_set_band_gain_db(band, (db - Self::eq_gain_step(fine)).max(-12.0));
+        self.eq_state.preset = None;
+    }
+
+    fn eq_gain_step(fine: bool) -> f32 {
+        if fine { EQ_GAIN_FINE_STEP } else { EQ_GAIN_STEP }
+    }
+
+    /// Type `c` (a digit, or a leading `-`, or one `.`) into the gain being
+    /// entered for the selected band, starting the entry if needed.
+    pub fn eq_edit_push(&mut self, c: char) {
+        let buf = self.eq_state.edit.get_or_insert_with(String::new);
+        let valid = match c {
+            '0'..='9' => true,
+            '-' => buf.is_empty(),
+            '.' => !buf.contains('.'),
+            _ => false,
+        };
+        if valid && buf.len() < EQ_EDIT_MAX_LEN {
+            buf.push(c);
+        }
+    }
+
+    /// Remove the last typed character; removing the last one ends the entry.
+    pub fn eq_edit_pop(&mut self) {
+        if let Some(buf) = &mut self.eq_state.edit {
+            buf.pop();
+            if buf.is_empty() {
+                self.eq_state.edit = None;
+            }
+        }
+    }
+
+    /// Apply the typed gain (clamped to ±12 dB) to the selected band. An
+    /// entry that is not a number is dropped.
+    pub fn eq_edit_commit(&mut self) {
+        let Some(buf) = self.eq_state.edit.take() else { return };
+        if let Ok(db) = buf.parse::<f32>() {
+            self.eq_set_band_gain_db(self.eq_state.selected_band, db);
+            self.eq_state.preset = None;
+        }
+    }
+
+    pub fn eq_edit_cancel(&mut self) {
+        self.eq_state.edit = None;
+    }
+
+    pub fn eq_editing(&self) -> bool {
+        self.eq_state.edit.is_some()
+    }
+
+    pub fn eq_enabled(&self) -> bool {
+        self.player.eq_gains().is_enabled()
+    }
+
+    /// Bypass the EQ (or turn it back on) without touching the band settings.
+    pub fn eq_toggle_bypass(&mut self) {
+        self.player.eq_gains().toggle_enabled();
+    }
+
+    fn eq_gains_db(&self) -> Vec<f32> {
+        (0..self.eq_band_count())
+            .map(|band| self.eq_band_gain_db(band))
+            .collect()
+    }
+
+    fn eq_apply_gains(&self, gains: &[f32]) {
+        for (band, &db) in gains.iter().enumerate() {
+            self.eq_set_band_gain_db(band, db);
+        }
+    }
+
+    /// True while the playing track's own EQ curve replaces the global EQ.
+    pub fn eq_track_curve(&self) -> bool {
+        self.global_eq.is_some()
+    }
+
+    /// Apply the EQ curve saved for track `idx`, or bring the global EQ back
+    /// for a track without one. Curves saved with a different number of
+    /// bands are ignored.
+    fn apply_track_eq(&mut self, idx: usize) {
+        let bands = self.eq_band_count();
+        let curve = self
+            .track_eq
+            .get(&self.songs[idx].path)
+            .filter(|gains| gains.len() == bands)
+            .map(<[f32]>::to_vec);
+        match curve {
+            Some(gains) => {
+                if self.global_eq.is_none() {
+                    self.global_eq = Some((self.eq_gains_db(), self.eq_state.preset));
+                }
+                self.eq_apply_gains(&gains);
+                self.eq_state.preset = None;
+            }
+            None => self.restore_global_eq(),
+        }
+    }
+
+    fn restore_global_eq(&mut self) {
+        if let Some((gains, preset)) = self.global_eq.take() {
+            self.eq_apply_gains(&gains);
+            self.eq_state.preset = preset;
+        }
+    }
+
+    /// `s` in the EQ popup: keep the current band gains as the playing
+    /// track's own curve.
+    pub fn eq_save_for_track(&mut self) {
+        let Some(idx) = self.now_playing else { return };
+        let gains = self.eq_gains_db();
+        if self.global_eq.is_none() {
+            self.global_eq = Some((gains.clone(), self.eq_state.preset));
+        }
+        self.track_eq.set(&self.songs[idx].path, gains);
+        let _ = self.track_eq.save();
+        self.set_status("EQ saved for this track");
+    }
+
+    /// `x` in the EQ popup: drop the playing track's own curve and return to
+    /// the global EQ.
+    pub fn eq_clear_for_track(&mut self) {
+        let Some(idx) = self.now_playing else { return };
+        self.track_eq.remove(&self.songs[idx].path);
+        let _ = self.track_eq.save();
+        self.restore_global_eq();
+        self.set_status("Track EQ removed");
+    }
+
+    /// Set all bands back to 0 dB.
+    pub fn eq_reset(&mut self) {
+        for band in 0..self.eq_band_count() {
+            self.eq_set_band_gain_db(band, 0.0);
+        }
+        self.eq_state.preset = Some(0);
+        self.set_status("EQ reset");
+    }
+
+    /// Apply the next preset in `eq::PRESETS` (from "Custom", start at the first).
+    pub fn eq_next_preset(&mut self) {
+        let next = self
+            .eq_state
+            .preset
+            .map_or(0, |i| (i + 1) % eq::PRESETS.len());
+        let points = &eq::PRESETS[next].1;
+        for band in 0..self.eq_band_count() {
+            self.eq_set_band_gain_db(band, eq::preset_gain(points, self.eq_band_freq(band)));
+        }
+        self.eq_state.preset = Some(next);
+        self.set_status(format!("EQ preset: {}", self.eq_preset_name()));
+    }
+
+    pub fn eq_preset_name(&self) -> &'static str {
+        self.eq_state
+            .preset
+            .map_or("Custom", |i| eq::PRESETS[i].0)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::Once;
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::eq::{EqConfig, EqGains};
+
+    /// What the mock was asked to do, shared with the test.
+    #[derive(Default)]
+    pub(crate) struct MockState {
+        played: Vec<PathBuf>,
+        paused: bool,
+        /// The current track has played out.
+        finished: bool,
+    }
+
+    struct MockPlayback {
+        state: Rc<RefCell<MockState>>,
+        eq_gains: EqGains,
+    }
+
+    impl Playback for MockPlayback {
+        fn play_file(&mut self, path: &Path) -> Result<()> {
+            let mut state = self.state.borrow_mut();
+            state.played.push(path.to_path_buf());
+            state.paused = false;
+            state.finished = false;
+            Ok(())
+        }
+
+        fn is_loading(&self) -> bool {
+            false
+        }
+
+        fn poll_load(&mut self) -> Option<Result<()>> {
+            None
+        }
+
+        fn load_file_from(&mut self, _path: &Path, _start: Duration) -> Result<()> {
+            self.state.borrow_mut().paused = true;
+            Ok(())
+        }
+
+        fn preload(&mut self, _path: &Path) -> Result<()> {
+            Err(anyhow!("no gapless playback in tests"))
+        }
+
+        fn take_advanced(&mut self) -> bool {
+            false
+        }
+
+        fn cancel_preload(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn seek(&mut self, _pos: Duration) -> Result<()> {
+            Ok(())
+        }
+
+        fn speed(&self) -> f32 {
+            1.0
+        }
+
+        fn set_speed(&mut self, _speed: f32) -> Result<()> {
+            Ok(())
+        }
+
+        fn replay_gain(&self) -> bool {
+            false
+        }
+
+        fn set_replay_gain(&mut self, _on: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn mono(&self) -> bool {
+            false
+        }
+
+        fn set_mono(&mut self, _on: bool) -> Result<()> {
+            Ok(())
+        }
+
+        fn eq_gains(&self) -> &EqGains {
+            &self.eq_gains
+        }
+
+        fn toggle_pause(&self) {
+            let mut state = self.state.borrow_mut();
+            state.paused = !state.paused;
+        }
+
+        fn pause(&self) {
+            self.state.borrow_mut().paused = true;
+        }
+
+        fn is_paused(&self) -> bool {
+            self.state.borrow().paused
+        }
+
+        fn position(&self) -> Duration {
+            Duration::ZERO
+        }
+
+        fn set_volume(&self, _vol: f32) {}
+
+        fn set_balance(&self, _balance: f32) {}
+
+        fn is_empty(&self) -> bool {
+            self.state.borrow().finished
+        }
+
+        fn format(&self) -> Option<Format> {
+            None
+        }
+
+        fn spectrum_stereo(&self) -> (Vec<u64>, Vec<u64>) {
+            (Vec::new(), Vec::new())
+        }
+
+        fn spectrum_peaks_stereo(&self) -> (Vec<u64>, Vec<u64>) {
+            (Vec::new(), Vec::new())
+        }
+
+        fn spectrum_frequencies(&self) -> Vec<f32> {
+            Vec::new()
+        }
+
+        fn spectrum_updated(&self) -> Option<Instant> {
+            None
+        }
+
+        fn visualizer_sensitivity(&self) -> f32 {
+            1.0
+        }
+
+        fn set_visualizer_sensitivity(&self, _sensitivity: f32) {}
+
+        fn visualizer_stereo(&self) -> bool {
+            false
+        }
+
+        fn set_visualizer_stereo(&self, _stereo: bool) {}
+
+        fn set_visualizer_hidden(&self, _hidden: bool) {}
+    }
+
+    /// Keep play counts, resume positions etc. written by the tests out of
+    /// the user's config directory.
+    fn isolate_config() {
+        static ONCE: Once = Once::new();
+        ONCE.call_once(|| {
+            let dir = std::env::temp_dir()
+                .join(format!("tui_music_player-tests-{}", std::process::id()));
+            // SAFETY: every test calls this before anything reads the
+            // environment, and `Once` makes the others wait until it is set.
+            unsafe { std::env::set_var("XDG_CONFIG_HOME", dir) };
+        });
+    }
+
+    /// An app with `count` three-minute songs and a mock player.
+    pub(crate) fn app_with_songs(count: usize) -> (App, Rc<RefCell<MockState>>) {
+        isolate_config();
+        let songs = (0..count)
+            .map(|i| {
+                Song::with_tags(
+                    PathBuf::from(format!("song-{i}.mp3")),
+                    format!("Song {i}"),
+                    Some(Duration::from_secs(180)),
+                    Tags::default(),
+                )
+            })
+            .collect();
+        let state = Rc::new(RefCell::new(MockState::default()));
+        let player = MockPlayback {
+            state: Rc::clone(&state),
+            eq_gains: EqGains::new(&EqConfig::default().bands),
+        };
+        let app = App::with_playback(
+            PathBuf::from("music"),
+            songs,
+            LibraryCache::default(),
+            Box::new(player),
+            &Config::default(),
+            Picker::from_fontsize((8, 16)),
+        );
+        (app, state)
+    }
+
+    /// A new, empty folder for a test's library, so scans do not depend on
+    /// what is in the working directory.
+    fn empty_music_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("tui_music_player-{test}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Let the playing track run out and give the app its tick.
+    fn finish_track(app: &mut App, state: &Rc<RefCell<MockState>>) {
+        state.borrow_mut().finished = true;
+        app.check_track_end();
+    }
+
+    fn played(state: &Rc<RefCell<MockState>>) -> Vec<String> {
+        state
+            .borrow()
+            .played
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn repeat_cycles_off_all_one() {
+        let (mut app, _) = app_with_songs(1);
+        assert!(app.repeat == RepeatMode::Off);
+        app.toggle_repeat();
+        assert!(app.repeat == RepeatMode::All);
+        app.toggle_repeat();
+        assert!(app.repeat == RepeatMode::One);
+        app.toggle_repeat();
+        assert!(app.repeat == RepeatMode::Off);
+    }
+
+    #[test]
+    fn repeat_off_stops_after_last_track() {
+        let (mut app, state) = app_with_songs(3);
+        app.play_selected();
+        for _ in 0..3 {
+            finish_track(&mut app, &state);
+        }
+        assert_eq!(played(&state), ["song-0.mp3", "song-1.mp3", "song-2.mp3"]);
+        assert_eq!(app.now_playing, None);
+    }
+
+    #[test]
+    fn repeat_all_wraps_to_first_track() {
+        let (mut app, state) = app_with_songs(2);
+        app.repeat = RepeatMode::All;
+        app.play_selected();
+        finish_track(&mut app, &state);
+        finish_track(&mut app, &state);
+        assert_eq!(played(&state), ["song-0.mp3", "song-1.mp3", "song-0.mp3"]);
+        assert_eq!(app.now_playing, Some(0));
+    }
+
+    #[test]
+    fn repeat_one_replays_current_track() {
+        let (mut app, state) = app_with_songs(2);
+        app.repeat = RepeatMode::One;
+        app.play_selected();
+        finish_track(&mut app, &state);
+        assert_eq!(played(&state), ["song-0.mp3", "song-0.mp3"]);
+        assert_eq!(app.now_playing, Some(0));
+    }
+
+    #[test]
+    fn play_folder_plays_its_tracks_in_order() {
+        let (mut app, state) = app_with_songs(3);
+        for (song, dir) in app.songs.iter_mut().zip(["a", "b", "a/c"]) {
+            song.path = Path::new("music").join(dir).join(&song.path);
+        }
+        app.play_folder(Path::new("music/a"));
+        assert_eq!(app.queue, [2]);
+        finish_track(&mut app, &state);
+        finish_track(&mut app, &state);
+        assert_eq!(played(&state), ["music/a/song-0.mp3", "music/a/c/song-2.mp3"]);
+        assert_eq!(app.now_playing, None);
+    }
+
+    #[test]
+    fn history_view_lists_recent_plays_first() {
+        let (mut app, _) = app_with_songs(3);
+        app.selected = 1;
+        app.play_selected();
+        app.selected = 0;
+        app.play_selected();
+        // Playing it again in a row adds no entry.
+        app.play_selected();
+        assert_eq!(
+            app.songs[0].last_played,
+            app.songs[1].last_played.map(|stamp| stamp + 1)
+        );
+        app.toggle_history_only();
+        assert_eq!(app.view[..2], [0, 1]);
+    }
+
+    #[test]
+    fn shrunken_library_stops_playback() {
+        let (mut app, state) = app_with_songs(3);
+        app.selected = 2;
+        app.play_selected();
+        app.queue.push_back(1);
+        app.songs.truncate(1);
+        finish_track(&mut app, &state);
+        assert_eq!(app.now_playing, None);
+
+        app.selected = 0;
+        app.play_selected();
+        app.songs.clear();
+        app.next_track();
+        app.prev_track();
+        finish_track(&mut app, &state);
+        assert_eq!(app.now_playing, None);
+    }
+
+    #[test]
+    fn long_titles_scroll_while_playing() {
+        let (mut app, _) = app_with_songs(2);
+        app.play_selected();
+        assert_eq!(app.title_scroll(4), 0);
+        app.check_title_scroll();
+        app.check_title_scroll();
+        assert_eq!(app.title_scroll(4), 2);
+        app.toggle_pause();
+        app.check_title_scroll();
+        assert_eq!(app.title_scroll(4), 2);
+        app.toggle_pause();
+        // A title that fits stays put; a new width starts over.
+        assert_eq!(app.title_scroll(20), 0);
+        app.check_title_scroll();
+        assert_eq!(app.title_scroll(20), 0);
+        app.title_scroll(4);
+        app.check_title_scroll();
+        app.next_track();
+        assert_eq!(app.title_scroll(4), 0);
+    }
+
+    #[test]
+    fn wide_titles_scroll_by_columns() {
+        let (mut app, _) = app_with_songs(1);
+        // 10 characters, but 20 columns.
+        app.songs[0].name = "日本語の歌".repeat(2);
+        app.play_selected();
+        assert_eq!(app.title_scroll(15), 0);
+        app.check_title_scroll();
+        assert_eq!(app.title_scroll(15), 1);
+    }
+
+    #[test]
+    fn next_track_at_end_of_playlist_keeps_last_track() {
+        let (mut app, state) = app_with_songs(2);
+        app.next_track();
+        app.next_track();
+        app.next_track();
+        assert_eq!(played(&state), ["song-0.mp3", "song-1.mp3"]);
+        assert_eq!(app.now_playing, Some(1));
+    }
+
+    #[test]
+    fn next_track_wraps_with_repeat_all() {
+        let (mut app, state) = app_with_songs(2);
+        app.repeat = RepeatMode::All;
+        app.next_track();
+        app.next_track();
+        app.next_track();
+        assert_eq!(played(&state), ["song-0.mp3", "song-1.mp3", "song-0.mp3"]);
+    }
+
+    #[test]
+    fn paused_track_is_not_advanced() {
+        let (mut app, state) = app_with_songs(2);
+        app.play_selected();
+        app.toggle_pause();
+        finish_track(&mut app, &state);
+        assert_eq!(played(&state), ["song-0.mp3"]);
+        assert_eq!(app.now_playing, Some(0));
+    }
+
+    #[test]
+    fn typed_volume_is_clamped() {
+        fn type_volume(app: &mut App, input: &str) {
+            app.prompt_volume();
+            input.chars().for_each(|c| app.prompt_push(c));
+            app.prompt_submit();
+        }
+
+        let (mut app, _state) = app_with_songs(1);
+        let max = app.volume_max_percent();
+        for (input, percent) in [("40 %", 40), ("9999", max), ("65", 65)] {
+            type_volume(&mut app, input);
+            assert_eq!(app.volume_percent(), percent);
+            assert_eq!(app.last_error, None);
+        }
+
+        // Invalid input leaves the volume alone and says why.
+        type_volume(&mut app, "loud");
+        assert_eq!(app.volume_percent(), 65);
+        assert!(app.last_error.is_some());
+    }
+
+    #[test]
+    fn times_parse() {
+        assert_eq!(parse_time("1:23"), Some(Duration::from_secs(83)));
+        assert_eq!(parse_time("0:05"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_time("1:02:03"), Some(Duration::from_secs(3723)));
+        assert_eq!(parse_time("90"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_time("2.5"), Some(Duration::from_millis(2500)));
+        for garbage in ["", "abc", "1:xx", "1:2:3:4", "-5", "inf", "NaN", "1.5:00"] {
+            assert_eq!(parse_time(garbage), None, "{garbage}");
+        }
+        // Too long for a `Duration`: refused rather than panicking.
+        assert_eq!(parse_time("1e30"), None);
+        assert_eq!(parse_time("1:1e30"), None);
+    }
+
+    #[test]
+    fn huge_relative_seek_does_not_overflow() {
+        let (mut app, _state) = app_with_songs(1);
+        app.play_selected();
+        // Fits in a `Duration`; the sum with the position saturates.
+        app.seek_to_input("+18000000000000000000");
+        assert_eq!(app.last_error, None);
+        app.seek_to_input("+1e30");
+        assert!(app.last_error.is_some());
+    }
+
+    #[test]
+    fn closing_a_playlist_stops_a_track_outside_the_library() {
+        let (mut app, state) = app_with_songs(2);
+        app.music_dir = empty_music_dir("close-playlist");
+        app.playlist = Some(PathBuf::from("/elsewhere/list.m3u"));
+        app.play_selected();
+        assert!(!state.borrow().paused);
+
+        app.close_playlist();
+        assert_eq!(app.now_playing, None);
+        assert!(state.borrow().paused);
+    }
+
+    #[test]
+    fn huge_sleep_timer_is_refused() {
+        let (mut app, _state) = app_with_songs(1);
+        app.sleep_timer_input("30");
+        assert!(app.sleep_remaining().is_some());
+        assert_eq!(app.last_error, None);
+        // Overflows the minutes-to-seconds conversion, or the clock.
+        for input in ["18446744073709551615", "153722867280912931", "soon"] {
+            app.last_error = None;
+            app.sleep_timer_input(input);
+            assert!(app.last_error.is_some(), "{input}");
+            assert!(app.sleep_remaining().is_some_and(|left| left <= Duration::from_secs(1800)));
+        }
+        app.sleep_timer_input("0");
+        assert_eq!(app.sleep_remaining(), None);
+    }
+
+    #[test]
+    fn scan_errors_are_reported_once() {
+        let (mut app, _state) = app_with_songs(1);
+        let missing = std::env::temp_dir().join("tui_music_player-no-such-folder");
+        let scan = || {
+            let (mut files, mut errors) = (Vec::new(), Vec::new());
+            App::collect_audio_files(&missing, &[], 0, &mut files, &mut errors);
+            errors
+        };
+        app.set_scan_errors(scan());
+        assert_eq!(app.scan_errors.len(), 1);
+        assert!(app.last_error.as_ref().is_some_and(|e| e.starts_with("Cannot read")));
+
+        // The same failure on a rescan is not shown again.
+        app.last_error = None;
+        app.set_scan_errors(scan());
+        assert_eq!(app.scan_errors.len(), 1);
+        assert_eq!(app.last_error, None);
+    }
+
+    #[test]
+    fn rescan_runs_in_the_background() {
+        let (mut app, _state) = app_with_songs(3);
+        app.music_dir = empty_music_dir("rescan");
+        let added = app.music_dir.join("added.mp3");
+        fs::write(&added, b"").unwrap();
+        app.start_rescan();
+        assert_eq!(app.songs.len(), 3);
+        for _ in 0..500 {
+            app.check_library_changes();
+            if app.rescan.is_none() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
         }
+        assert!(app.rescan.is_none());
+        let paths: Vec<_> = app.songs.iter().map(|s| &s.path).collect();
+        assert_eq!(paths, [&added]);
     }
 }
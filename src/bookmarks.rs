@@ -0,0 +1,48 @@
+//! Per-track bookmarks (positions within a file), kept as JSON next to the
+//! session in the user's config directory and keyed by canonical path.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::session::{PathStore, track_key};
+
+const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+pub struct Bookmarks {
+    store: PathStore<HashMap<PathBuf, Vec<Duration>>>,
+}
+
+impl Bookmarks {
+    /// Load saved bookmarks. Missing or unreadable files yield an empty set.
+    pub fn load() -> Self {
+        Self {
+            store: PathStore::load(BOOKMARKS_FILE),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.store.save()
+    }
+
+    /// Bookmarks of `track`, sorted by position.
+    pub fn get(&self, track: &Path) -> &[Duration] {
+        self.store.entries.get(&track_key(track)).map_or(&[], Vec::as_slice)
+    }
+
+    /// Add a bookmark at `at`, ignoring one within a second of an existing mark.
+    pub fn add(&mut self, track: &Path, at: Duration) {
+        let marks = self.store.entries.entry(track_key(track)).or_default();
+        if marks.iter().any(|&m| m.abs_diff(at) < Duration::from_secs(1)) {
+            return;
+        }
+        let pos = marks.partition_point(|&m| m < at);
+        marks.insert(pos, at);
+    }
+
+    pub fn clear(&mut self, track: &Path) {
+        self.store.entries.remove(&track_key(track));
+    }
+}
@@ -0,0 +1,365 @@
+//! User configuration read once at startup from `config.toml` in the config
+//! directory. Every key is optional; a missing or unreadable file, or one
+//! that fails to parse, falls back to the defaults.
+
+use std::collections::HashMap;
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::eq::EqConfig;
+use crate::keymap::{KeyMap, KeySpec};
+use crate::player::DECODABLE_EXTENSIONS;
+use crate::resume::ResumeMode;
+use crate::session::config_dir;
+use crate::theme::{Theme, ThemeConfig};
+use crate::visualizer::VisualizerConfig;
+
+const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub visualizer: VisualizerConfig,
+    pub playback: PlaybackConfig,
+    pub equalizer: EqConfig,
+    pub ui: UiConfig,
+    pub library: LibraryConfig,
+    #[cfg(all(feature = "socket", unix))]
+    pub socket: SocketConfig,
+    /// `[keys]`: action name to key(s), as written; applied to `keymap`.
+    keys: HashMap<String, KeySpec>,
+    #[serde(skip)]
+    pub keymap: KeyMap,
+    /// `[theme]`: base theme and colour overrides, as written; see `theme`.
+    #[serde(rename = "theme")]
+    theme_config: ThemeConfig,
+    #[serde(skip)]
+    pub theme: Theme,
+}
+
+/// Largest accepted `volume_step`, in percent.
+const MAX_VOLUME_STEP: u16 = 50;
+/// Accepted range of `volume_max`, in percent.
+const VOLUME_MAX_RANGE: std::ops::RangeInclusive<u16> = 10..=300;
+/// Accepted range of `volume_curve`.
+const VOLUME_CURVE_RANGE: std::ops::RangeInclusive<f32> = 1.0..=4.0;
+/// Accepted range of `seek_step` and `seek_step_long`, in seconds.
+const SEEK_STEP_RANGE: std::ops::RangeInclusive<u64> = 1..=600;
+/// Accepted range of `output_rate`, in Hz.
+const OUTPUT_RATE_RANGE: std::ops::RangeInclusive<u32> = 8_000..=384_000;
+
+/// What Enter on a track does while another one is playing (`[playback]
+/// enter` in the config file).
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnterMode {
+    /// Play it right away.
+    #[default]
+    Play,
+    /// Add it to the "up next" queue.
+    Enqueue,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct PlaybackConfig {
+    /// Whether a track with a saved position continues from there.
+    pub resume: ResumeMode,
+    pub enter: EnterMode,
+    /// Volume change per key press, in percent.
+    pub volume_step: u16,
+    /// Highest volume, in percent (100 = the decoded level, unamplified).
+    pub volume_max: u16,
+    /// Exponent mapping the volume up to 100% to gain (1.0 = linear).
+    pub volume_curve: f32,
+    /// Seconds skipped by Left/Right, and by Shift+Left/Right.
+    pub seek_step: u64,
+    pub seek_step_long: u64,
+    /// Sample rate to open the output device at, in Hz; every track is
+    /// resampled to it. `None` uses the device's default rate.
+    pub output_rate: Option<u32>,
+    /// Downmix every track to mono for the output.
+    pub mono: bool,
+}
+
+impl Default for PlaybackConfig {
+    fn default() -> Self {
+        Self {
+            resume: ResumeMode::default(),
+            enter: EnterMode::default(),
+            volume_step: 5,
+            volume_max: 150,
+            volume_curve: 2.0,
+            seek_step: 5,
+            seek_step_long: 30,
+            output_rate: None,
+            mono: false,
+        }
+    }
+}
+
+impl PlaybackConfig {
+    /// The config with a volume step outside 1–50%, a maximum outside
+    /// 10–300%, a curve exponent outside 1–4, a seek step outside 1–600 s or
+    /// an output rate outside 8–384 kHz replaced by its default, reporting it
+    /// on stderr.
+    pub fn validated(self) -> Self {
+        let defaults = Self::default();
+        let mut config = self;
+        if !(1..=MAX_VOLUME_STEP).contains(&config.volume_step) {
+            eprintln!(
+                "config: ignoring playback.volume_step = {}: expected 1 to {MAX_VOLUME_STEP}",
+                config.volume_step
+            );
+            config.volume_step = defaults.volume_step;
+        }
+        if !VOLUME_MAX_RANGE.contains(&config.volume_max) {
+            eprintln!(
+                "config: ignoring playback.volume_max = {}: expected {} to {}",
+                config.volume_max,
+                VOLUME_MAX_RANGE.start(),
+                VOLUME_MAX_RANGE.end()
+            );
+            config.volume_max = defaults.volume_max;
+        }
+        if !VOLUME_CURVE_RANGE.contains(&config.volume_curve) {
+            eprintln!(
+                "config: ignoring playback.volume_curve = {}: expected {} to {}",
+                config.volume_curve,
+                VOLUME_CURVE_RANGE.start(),
+                VOLUME_CURVE_RANGE.end()
+            );
+            config.volume_curve = defaults.volume_curve;
+        }
+        for (name, step, default) in [
+            ("seek_step", &mut config.seek_step, defaults.seek_step),
+            ("seek_step_long", &mut config.seek_step_long, defaults.seek_step_long),
+        ] {
+            if !SEEK_STEP_RANGE.contains(step) {
+                eprintln!(
+                    "config: ignoring playback.{name} = {step}: expected {} to {}",
+                    SEEK_STEP_RANGE.start(),
+                    SEEK_STEP_RANGE.end()
+                );
+                *step = default;
+            }
+        }
+        if let Some(rate) = config.output_rate
+            && !OUTPUT_RATE_RANGE.contains(&rate)
+        {
+            eprintln!(
+                "config: ignoring playback.output_rate = {rate}: expected {} to {} Hz",
+                OUTPUT_RATE_RANGE.start(),
+                OUTPUT_RATE_RANGE.end()
+            );
+            config.output_rate = defaults.output_rate;
+        }
+        config
+    }
+}
+
+/// Accepted range of `tick_ms`.
+const TICK_RANGE: std::ops::RangeInclusive<u64> = 10..=1000;
+/// Longest accepted `idle_tick_ms`.
+const MAX_IDLE_TICK: u64 = 5000;
+
+/// A panel under the song list (`[ui] panels` in the config file).
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Panel {
+    Visualizer,
+    NowPlaying,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Milliseconds between redraws and housekeeping while playing.
+    pub tick_ms: u64,
+    /// Milliseconds between redraws while paused or stopped, once the
+    /// spectrum has fallen still. A key press is still handled at once.
+    pub idle_tick_ms: u64,
+    /// Show the volume as a bar beside the percentage.
+    pub volume_bar: bool,
+    /// Show the time of day and how long the player has been running.
+    pub clock: bool,
+    /// Panels under the song list, top to bottom; one left out is hidden.
+    pub panels: Vec<Panel>,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            tick_ms: 100,
+            idle_tick_ms: 500,
+            volume_bar: false,
+            clock: true,
+            panels: vec![Panel::Visualizer, Panel::NowPlaying],
+        }
+    }
+}
+
+impl UiConfig {
+    /// The config with a tick outside 10–1000 ms, or an idle tick shorter
+    /// than the tick or over 5 s, replaced by its default, and panels listed
+    /// twice kept once, reporting it on stderr.
+    pub fn validated(self) -> Self {
+        let defaults = Self::default();
+        let mut config = self;
+        if !TICK_RANGE.contains(&config.tick_ms) {
+            eprintln!(
+                "config: ignoring ui.tick_ms = {}: expected {} to {}",
+                config.tick_ms,
+                TICK_RANGE.start(),
+                TICK_RANGE.end()
+            );
+            config.tick_ms = defaults.tick_ms;
+        }
+        if !(config.tick_ms..=MAX_IDLE_TICK).contains(&config.idle_tick_ms) {
+            let fallback = defaults.idle_tick_ms.max(config.tick_ms);
+            eprintln!(
+                "config: ignoring ui.idle_tick_ms = {}: expected {} to {MAX_IDLE_TICK}",
+                config.idle_tick_ms, config.tick_ms
+            );
+            config.idle_tick_ms = fallback;
+        }
+        let mut panels = Vec::with_capacity(config.panels.len());
+        for panel in config.panels {
+            if panels.contains(&panel) {
+                eprintln!("config: ignoring a repeated entry in ui.panels");
+            } else {
+                panels.push(panel);
+            }
+        }
+        config.panels = panels;
+        config
+    }
+}
+
+/// Audio file extensions listed by default.
+const DEFAULT_EXTENSIONS: [&str; 6] = ["mp3", "wav", "ogg", "flac", "m4a", "aac"];
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct LibraryConfig {
+    /// Extensions of the audio files listed; replaces the defaults.
+    pub extensions: Vec<String>,
+    /// Extensions listed on top of `extensions`.
+    pub extra_extensions: Vec<String>,
+    /// Extensions never listed, even when in `extensions`.
+    pub exclude_extensions: Vec<String>,
+    /// List a track stored in several formats only once.
+    pub skip_duplicates: bool,
+    /// File extensions from most to least preferred when skipping
+    /// duplicates; unlisted ones rank last.
+    pub prefer_formats: Vec<String>,
+}
+
+impl Default for LibraryConfig {
+    fn default() -> Self {
+        Self {
+            extensions: DEFAULT_EXTENSIONS.map(str::to_string).to_vec(),
+            extra_extensions: Vec::new(),
+            exclude_extensions: Vec::new(),
+            skip_duplicates: false,
+            prefer_formats: ["flac", "wav", "m4a", "ogg", "aac", "mp3"]
+                .map(str::to_string)
+                .to_vec(),
+        }
+    }
+}
+
+impl LibraryConfig {
+    /// The config with every extension lower-cased and without a leading
+    /// dot. Empty entries, and listed ones the decoder cannot play, are
+    /// dropped with a note on stderr.
+    pub fn validated(self) -> Self {
+        let exclude_extensions =
+            extension_list("exclude_extensions", self.exclude_extensions, false);
+        Self {
+            extensions: extension_list("extensions", self.extensions, true),
+            extra_extensions: extension_list("extra_extensions", self.extra_extensions, true),
+            exclude_extensions,
+            prefer_formats: extension_list("prefer_formats", self.prefer_formats, false),
+            ..self
+        }
+    }
+
+    /// Extensions of the files to list: `extensions` and `extra_extensions`
+    /// without `exclude_extensions`.
+    pub fn audio_extensions(&self) -> Vec<String> {
+        let mut list: Vec<String> = Vec::new();
+        for ext in self.extensions.iter().chain(&self.extra_extensions) {
+            if !list.contains(ext) && !self.exclude_extensions.contains(ext) {
+                list.push(ext.clone());
+            }
+        }
+        list
+    }
+}
+
+/// `library.<key>` normalized, keeping only what the decoder can play when
+/// `decodable` is set.
+fn extension_list(key: &str, list: Vec<String>, decodable: bool) -> Vec<String> {
+    list.into_iter()
+        .filter_map(|entry| {
+            let ext = entry.trim().trim_start_matches('.').to_lowercase();
+            if ext.is_empty() {
+                eprintln!("config: ignoring library.{key} entry {entry:?}");
+                return None;
+            }
+            if decodable && !DECODABLE_EXTENSIONS.contains(&ext.as_str()) {
+                eprintln!(
+                    "config: ignoring library.{key} entry {entry:?}: the player cannot decode it \
+                     (supported: {})",
+                    DECODABLE_EXTENSIONS.join(", ")
+                );
+                return None;
+            }
+            Some(ext)
+        })
+        .collect()
+}
+
+/// `[socket]`: the status endpoint (`socket` feature).
+#[cfg(all(feature = "socket", unix))]
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SocketConfig {
+    pub enabled: bool,
+    /// Where to listen; see `socket::default_path` when unset.
+    pub path: Option<std::path::PathBuf>,
+}
+
+impl Config {
+    /// Load and validate the config. Problems are reported on stderr, so call
+    /// this before the terminal switches to the alternate screen.
+    pub fn load() -> Self {
+        let Some(path) = config_dir().map(|dir| dir.join(CONFIG_FILE)) else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        let config: Self = match toml::from_str(&contents) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("config: ignoring {}: {e}", path.display());
+                return Self::default();
+            }
+        };
+        let keymap = KeyMap::with_overrides(&config.keys);
+        let theme = config.theme_config.resolve();
+        Self {
+            visualizer: config.visualizer.validated(),
+            playback: config.playback.validated(),
+            equalizer: config.equalizer.validated(),
+            ui: config.ui.validated(),
+            library: config.library.validated(),
+            keymap,
+            theme,
+            ..config
+        }
+    }
+}
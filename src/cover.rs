@@ -0,0 +1,70 @@
+//! Cover art of the playing track, drawn with the best image protocol the
+//! terminal supports (kitty, sixel, iTerm2) or coloured half-block characters
+//! when it supports none. Pictures are read and decoded on a background
+//! thread so a large embedded image never stalls the UI.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use image::DynamicImage;
+use ratatui_image::picker::Picker;
+use ratatui_image::protocol::StatefulProtocol;
+
+use crate::metadata;
+
+/// Font size in pixels assumed when the terminal does not report one.
+const FALLBACK_FONT_SIZE: (u16, u16) = (8, 16);
+
+/// Query the terminal for graphics support and its font size. Call after
+/// entering the alternate screen and before reading events; terminals that
+/// do not answer get half-block rendering.
+pub fn detect_picker() -> Picker {
+    Picker::from_query_stdio().unwrap_or_else(|_| Picker::from_fontsize(FALLBACK_FONT_SIZE))
+}
+
+pub struct CoverArt {
+    picker: Picker,
+    /// Track the art below belongs to (or is being loaded for).
+    path: Option<PathBuf>,
+    image: Option<StatefulProtocol>,
+    /// Result of the load in flight, if any.
+    pending: Option<Receiver<Option<DynamicImage>>>,
+}
+
+impl CoverArt {
+    pub fn new(picker: Picker) -> Self {
+        Self {
+            picker,
+            path: None,
+            image: None,
+            pending: None,
+        }
+    }
+
+    /// Art for `path` once it has loaded. A new path drops the current image
+    /// and starts loading the new one in the background.
+    pub fn get(&mut self, path: Option<&Path>) -> Option<&mut StatefulProtocol> {
+        if self.path.as_deref() != path {
+            self.path = path.map(Path::to_path_buf);
+            self.image = None;
+            self.pending = path.map(|path| {
+                let path = path.to_path_buf();
+                let (tx, rx) = mpsc::channel();
+                std::thread::spawn(move || {
+                    let image = metadata::cover_art(&path)
+                        .and_then(|bytes| image::load_from_memory(&bytes).ok());
+                    let _ = tx.send(image);
+                });
+                rx
+            });
+        }
+
+        if let Some(rx) = &self.pending
+            && let Ok(image) = rx.try_recv()
+        {
+            self.pending = None;
+            self.image = image.map(|image| self.picker.new_resize_protocol(image));
+        }
+        self.image.as_mut()
+    }
+}
@@ -0,0 +1,380 @@
+//! Decoding a track for playback. rodio's decoder reads most files; with the
+//! `symphonia` feature, local files it rejects (some `.m4a`/`.aac` and Ogg
+//! files) are tried again with Symphonia's format readers and codecs.
+
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::Result;
+use rodio::source::SeekError;
+use rodio::{Decoder, Source};
+
+use crate::stream::Input;
+#[cfg(feature = "symphonia")]
+use crate::stream::is_stream;
+
+/// Name of the fallback decoder, shown with the track's format.
+#[cfg(feature = "symphonia")]
+pub const SYMPHONIA: &str = "Symphonia";
+
+/// A track opened for playback, by whichever decoder could read it.
+pub enum TrackDecoder {
+    Rodio(Decoder<Input>),
+    #[cfg(feature = "symphonia")]
+    Symphonia(fallback::SymphoniaDecoder),
+}
+
+impl TrackDecoder {
+    /// Open `path` (a file, `-` or a URL) and probe its format: the slow
+    /// part of starting a track. A file rodio cannot read is tried with
+    /// Symphonia; streams cannot be re-read, so they get rodio only.
+    pub fn open(path: &Path) -> Result<Self> {
+        let error = match Decoder::new(Input::open(path)?) {
+            Ok(decoder) => return Ok(Self::Rodio(decoder)),
+            Err(e) => e,
+        };
+        #[cfg(feature = "symphonia")]
+        if !is_stream(path)
+            && let Ok(decoder) = fallback::SymphoniaDecoder::open(path)
+        {
+            return Ok(Self::Symphonia(decoder));
+        }
+        Err(error.into())
+    }
+
+    /// The fallback decoder's name when it is the one reading the track.
+    pub fn fallback_name(&self) -> Option<&'static str> {
+        match self {
+            Self::Rodio(_) => None,
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(_) => Some(SYMPHONIA),
+        }
+    }
+}
+
+/// Length of the file at `path` as Symphonia reads it from the headers, for
+/// files rodio's decoder rejects.
+#[cfg(feature = "symphonia")]
+pub fn fallback_duration(path: &Path) -> Option<Duration> {
+    fallback::SymphoniaDecoder::open(path).ok()?.total_duration()
+}
+
+impl Iterator for TrackDecoder {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        match self {
+            Self::Rodio(decoder) => decoder.next(),
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(decoder) => decoder.next(),
+        }
+    }
+}
+
+impl Source for TrackDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        match self {
+            Self::Rodio(decoder) => decoder.current_frame_len(),
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(decoder) => decoder.current_frame_len(),
+        }
+    }
+    fn channels(&self) -> u16 {
+        match self {
+            Self::Rodio(decoder) => decoder.channels(),
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(decoder) => decoder.channels(),
+        }
+    }
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Self::Rodio(decoder) => decoder.sample_rate(),
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(decoder) => decoder.sample_rate(),
+        }
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        match self {
+            Self::Rodio(decoder) => decoder.total_duration(),
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(decoder) => decoder.total_duration(),
+        }
+    }
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        match self {
+            Self::Rodio(decoder) => decoder.try_seek(pos),
+            #[cfg(feature = "symphonia")]
+            Self::Symphonia(decoder) => decoder.try_seek(pos),
+        }
+    }
+}
+
+#[cfg(feature = "symphonia")]
+mod fallback {
+    use std::fs::File;
+    use std::path::Path;
+    use std::time::Duration;
+
+    use anyhow::{Context, Result, bail};
+    use rodio::Source;
+    use rodio::source::SeekError;
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{CODEC_TYPE_NULL, Decoder, DecoderOptions};
+    use symphonia::core::errors::Error;
+    use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+    use symphonia::core::units::Time;
+
+    /// A local file decoded by Symphonia, handed out as interleaved `i16`
+    /// samples like rodio's own decoder.
+    pub struct SymphoniaDecoder {
+        format: Box<dyn FormatReader>,
+        decoder: Box<dyn Decoder>,
+        track_id: u32,
+        /// Samples of the last decoded packet, and how many have been handed
+        /// out.
+        buffer: Option<SampleBuffer<i16>>,
+        pos: usize,
+        /// Samples still to drop after a seek landed before its target.
+        skip: usize,
+        channels: u16,
+        sample_rate: u32,
+        total_duration: Option<Duration>,
+    }
+
+    impl SymphoniaDecoder {
+        /// Probe `path` and decode its first packet, which settles the
+        /// channel count and sample rate.
+        pub fn open(path: &Path) -> Result<Self> {
+            let file = File::open(path)?;
+            let stream = MediaSourceStream::new(Box::new(file), Default::default());
+            let mut hint = Hint::new();
+            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+                hint.with_extension(ext);
+            }
+            let probed = symphonia::default::get_probe().format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )?;
+            let track = probed
+                .format
+                .tracks()
+                .iter()
+                .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+                .context("no audio track")?;
+            let (track_id, params) = (track.id, track.codec_params.clone());
+            let decoder =
+                symphonia::default::get_codecs().make(&params, &DecoderOptions::default())?;
+            // A malformed header may claim a rate of 0, making this infinite.
+            let total_duration = params.n_frames.zip(params.sample_rate).and_then(|(frames, rate)| {
+                Duration::try_from_secs_f64(frames as f64 / f64::from(rate)).ok()
+            });
+            let mut source = Self {
+                format: probed.format,
+                decoder,
+                track_id,
+                buffer: None,
+                pos: 0,
+                skip: 0,
+                channels: 0,
+                sample_rate: 0,
+                total_duration,
+            };
+            if !source.refill() {
+                bail!("no audio decoded");
+            }
+            Ok(source)
+        }
+
+        fn buffered(&self) -> usize {
+            self.buffer.as_ref().map_or(0, |b| b.len())
+        }
+
+        /// Decode packets of the track until one holds audio. False at the
+        /// end of the track or on an error that ends it.
+        fn refill(&mut self) -> bool {
+            loop {
+                let Ok(packet) = self.format.next_packet() else {
+                    return false;
+                };
+                if packet.track_id() != self.track_id {
+                    continue;
+                }
+                let decoded = match self.decoder.decode(&packet) {
+                    Ok(decoded) => decoded,
+                    // A corrupt packet is skipped rather than ending the track.
+                    Err(Error::DecodeError(_)) => continue,
+                    Err(_) => return false,
+                };
+                let spec = *decoded.spec();
+                // Nothing downstream can play audio at 0 Hz.
+                if spec.rate == 0 {
+                    return false;
+                }
+                let needed = decoded.capacity() * spec.channels.count();
+                if self.buffer.as_ref().is_none_or(|b| b.capacity() < needed) {
+                    self.buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+                }
+                let buffer = self.buffer.as_mut().expect("buffer allocated above");
+                buffer.copy_interleaved_ref(decoded);
+                self.channels = spec.channels.count() as u16;
+                self.sample_rate = spec.rate;
+                self.pos = 0;
+                if !buffer.samples().is_empty() {
+                    return true;
+                }
+            }
+        }
+    }
+
+    impl Iterator for SymphoniaDecoder {
+        type Item = i16;
+
+        fn next(&mut self) -> Option<i16> {
+            loop {
+                let sample = *self.buffer.as_ref()?.samples().get(self.pos)?;
+                self.pos += 1;
+                // Decode ahead, so `current_frame_len` only reaches zero at
+                // the end of the track.
+                if self.pos >= self.buffered() && !self.refill() {
+                    self.buffer = None;
+                }
+                if self.skip == 0 {
+                    return Some(sample);
+                }
+                self.skip -= 1;
+            }
+        }
+    }
+
+    impl Source for SymphoniaDecoder {
+        fn current_frame_len(&self) -> Option<usize> {
+            Some(self.buffered() - self.pos)
+        }
+        fn channels(&self) -> u16 {
+            self.channels
+        }
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+        fn total_duration(&self) -> Option<Duration> {
+            self.total_duration
+        }
+        /// Seek to the packet holding `pos`, then drop the samples before it.
+        fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+            let to = SeekTo::Time {
+                time: Time::new(pos.as_secs(), f64::from(pos.subsec_nanos()) / 1e9),
+                track_id: Some(self.track_id),
+            };
+            let seeked = self
+                .format
+                .seek(SeekMode::Accurate, to)
+                .map_err(|_| SeekError::NotSupported {
+                    underlying_source: std::any::type_name::<Self>(),
+                })?;
+            self.decoder.reset();
+            if !self.refill() {
+                self.buffer = None;
+            }
+            // Timestamps count frames for the formats this decoder is used for.
+            let frames = seeked.required_ts.saturating_sub(seeked.actual_ts);
+            self.skip = frames as usize * usize::from(self.channels);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "symphonia"))]
+mod tests {
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use rodio::Source;
+
+    use super::fallback::SymphoniaDecoder;
+
+    const RATE: u32 = 8_000;
+
+    /// A 16-bit PCM WAV file of `frames` frames whose samples count up
+    /// (each channel of frame `i` holds `i`), so a sample tells where in
+    /// the file it is. Removed when dropped.
+    struct CountingWav(PathBuf);
+
+    impl CountingWav {
+        fn new(name: &str, channels: u16, frames: u32) -> Self {
+            let data_len = frames * u32::from(channels) * 2;
+            let mut bytes = Vec::new();
+            bytes.extend_from_slice(b"RIFF");
+            bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+            bytes.extend_from_slice(b"WAVEfmt ");
+            bytes.extend_from_slice(&16u32.to_le_bytes());
+            bytes.extend_from_slice(&1u16.to_le_bytes());
+            bytes.extend_from_slice(&channels.to_le_bytes());
+            bytes.extend_from_slice(&RATE.to_le_bytes());
+            bytes.extend_from_slice(&(RATE * u32::from(channels) * 2).to_le_bytes());
+            bytes.extend_from_slice(&(channels * 2).to_le_bytes());
+            bytes.extend_from_slice(&16u16.to_le_bytes());
+            bytes.extend_from_slice(b"data");
+            bytes.extend_from_slice(&data_len.to_le_bytes());
+            for i in 0..frames {
+                for _ in 0..channels {
+                    bytes.extend_from_slice(&(i as i16).to_le_bytes());
+                }
+            }
+            let path = std::env::temp_dir()
+                .join(format!("tui_music_player-{}-{name}.wav", std::process::id()));
+            std::fs::write(&path, bytes).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for CountingWav {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn decodes_every_sample_in_order() {
+        let wav = CountingWav::new("decode", 2, RATE);
+        let decoder = SymphoniaDecoder::open(&wav.0).unwrap();
+        assert_eq!(decoder.channels(), 2);
+        assert_eq!(decoder.sample_rate(), RATE);
+        assert_eq!(decoder.total_duration(), Some(Duration::from_secs(1)));
+
+        let samples: Vec<i16> = decoder.collect();
+        assert_eq!(samples.len(), 2 * RATE as usize);
+        assert!(samples.chunks(2).enumerate().all(|(i, frame)| frame == [i as i16; 2]));
+    }
+
+    #[test]
+    fn frame_len_counts_down_to_the_end() {
+        let wav = CountingWav::new("frames", 1, RATE);
+        let mut decoder = SymphoniaDecoder::open(&wav.0).unwrap();
+        let mut total = 0;
+        while let Some(len) = decoder.current_frame_len().filter(|&len| len > 0) {
+            assert_eq!(decoder.by_ref().take(len).count(), len);
+            total += len;
+        }
+        assert_eq!(total, RATE as usize);
+        assert_eq!(decoder.next(), None);
+    }
+
+    #[test]
+    fn seek_lands_on_the_target_sample() {
+        let wav = CountingWav::new("seek", 2, 2 * RATE);
+        let mut decoder = SymphoniaDecoder::open(&wav.0).unwrap();
+        decoder.try_seek(Duration::from_millis(1_250)).unwrap();
+        assert_eq!(decoder.next(), Some(10_000));
+        assert_eq!(decoder.next(), Some(10_000));
+        assert_eq!(decoder.next(), Some(10_001));
+
+        // Back to the start, and the rest of the file follows.
+        decoder.try_seek(Duration::ZERO).unwrap();
+        assert_eq!(decoder.count(), 4 * RATE as usize);
+    }
+}
@@ -0,0 +1,135 @@
+//! Leaving out copies of the same track stored in several formats (e.g. an
+//! album ripped to both FLAC and MP3), keeping the preferred format.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::app::{Song, stem_name};
+
+/// What makes two files the same track: artist, album and title when the
+/// file is tagged with all three, else its title (or file name) within its
+/// folder. Live and studio takes on different albums, and untagged tracks
+/// that merely share a title ("Intro"), stay apart.
+#[derive(PartialEq, Eq, Hash)]
+enum Key {
+    Tagged(String, String, String),
+    File(PathBuf, String),
+}
+
+fn key(song: &Song) -> Key {
+    let tags = &song.tags;
+    match (&tags.artist, &tags.album, &tags.title) {
+        (Some(artist), Some(album), Some(title)) => {
+            Key::Tagged(normalize(artist), normalize(album), normalize(title))
+        }
+        (_, _, title) => Key::File(
+            song.path.parent().unwrap_or(Path::new("")).to_path_buf(),
+            normalize(title.as_deref().unwrap_or(&stem_name(&song.path))),
+        ),
+    }
+}
+
+/// Lower-case letters and digits, every other run of characters a single
+/// space, so "Song_Title " and "song title" match.
+fn normalize(s: &str) -> String {
+    s.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Rank of `path`'s extension in `prefer` (lower is better); unlisted
+/// formats come last.
+fn rank(path: &Path, prefer: &[String]) -> usize {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    prefer.iter().position(|p| *p == ext).unwrap_or(prefer.len())
+}
+
+/// Drop every song with a copy in a format ranked higher in `prefer`.
+/// Copies in the same format are all kept, as are playlists; the order of
+/// the rest is unchanged.
+pub fn remove_duplicates(songs: &mut Vec<Song>, prefer: &[String]) {
+    let mut best: HashMap<Key, usize> = HashMap::new();
+    for song in songs.iter().filter(|s| !s.is_playlist()) {
+        let rank = rank(&song.path, prefer);
+        best.entry(key(song))
+            .and_modify(|best| *best = (*best).min(rank))
+            .or_insert(rank);
+    }
+    songs.retain(|song| song.is_playlist() || rank(&song.path, prefer) == best[&key(song)]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::Tags;
+
+    fn song(path: &str, artist: Option<&str>, title: Option<&str>) -> Song {
+        album_song(path, artist, Some("Album"), title)
+    }
+
+    fn album_song(
+        path: &str,
+        artist: Option<&str>,
+        album: Option<&str>,
+        title: Option<&str>,
+    ) -> Song {
+        let tags = Tags {
+            artist: artist.map(str::to_string),
+            album: album.map(str::to_string),
+            title: title.map(str::to_string),
+            ..Tags::default()
+        };
+        Song::with_tags(PathBuf::from(path), stem_name(Path::new(path)), None, tags)
+    }
+
+    #[test]
+    fn keeps_the_preferred_format() {
+        let prefer = ["flac".to_string(), "mp3".to_string()];
+        let mut songs = vec![
+            song("flac/a/01.flac", Some("Artist"), Some("Song")),
+            song("mp3/a/01.mp3", Some("artist"), Some("Song!")),
+            song("mp3/a/02.mp3", Some("Artist"), Some("Other")),
+            song("mp3/a/03.mp3", Some("Artist"), Some("Other")),
+            song("b/Track_One.ogg", None, None),
+            song("b/track one.MP3", None, None),
+            song("c/track one.ogg", None, None),
+        ];
+        remove_duplicates(&mut songs, &prefer);
+        let paths: Vec<_> = songs.iter().map(|s| s.path.to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            [
+                "flac/a/01.flac",
+                "mp3/a/02.mp3",
+                "mp3/a/03.mp3",
+                "b/track one.MP3",
+                "c/track one.ogg",
+            ]
+        );
+    }
+
+    #[test]
+    fn same_title_on_other_albums_is_kept() {
+        let prefer = ["flac".to_string(), "mp3".to_string()];
+        let mut songs = vec![
+            album_song("studio/song.flac", Some("Artist"), Some("Studio"), Some("Song")),
+            album_song("live/song.mp3", Some("Artist"), Some("Live"), Some("Song")),
+            // No artist: only copies in the same folder are the same track.
+            album_song("one/intro.flac", None, Some("One"), Some("Intro")),
+            album_song("two/intro.mp3", None, Some("Two"), Some("Intro")),
+            album_song("two/Intro.flac", None, Some("Two"), Some("Intro")),
+        ];
+        remove_duplicates(&mut songs, &prefer);
+        let paths: Vec<_> = songs.iter().map(|s| s.path.to_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            ["studio/song.flac", "live/song.mp3", "one/intro.flac", "two/Intro.flac"]
+        );
+    }
+}
@@ -1,120 +1,261 @@
-//! 3-band equalizer (Bass, Mid, Treble) using peaking biquad filters.
-//! Coefficients are recomputed only when the user changes gains.
+//! Graphic equalizer of peaking biquad filters applied in series, three
+//! bands (Bass, Mid, Treble) unless `config.toml` sets other centre
+//! frequencies. Coefficients are recomputed only when the user changes gains,
+//! centre frequencies or Q. The whole EQ can be bypassed without losing
+//! settings. A pre-amp gain, applied ahead of the filters, is stored
+//! alongside.
 
 use std::sync::Arc;
-use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU32, Ordering};
 use std::time::Duration;
 
 use biquad::frequency::ToHertz;
 use biquad::{Biquad, Coefficients, DirectForm1, Type};
 use rodio::Source;
+use serde::Deserialize;
 
 const MIN_CENTI_DB: i32 = -1200;
 const MAX_CENTI_DB: i32 = 1200;
-const BASS_FREQ: f32 = 120.0;
-const MID_FREQ: f32 = 1000.0;
-const TREBLE_FREQ: f32 = 8000.0;
+/// Centre frequencies of the default Bass, Mid and Treble bands.
+const DEFAULT_FREQS: [f32; 3] = [120.0, 1000.0, 8000.0];
+/// Most bands the popup has room for.
+const MAX_BANDS: usize = 10;
 const Q: f32 = 1.0;
+const MIN_FREQ: f32 = 20.0;
+const MAX_FREQ: f32 = 20_000.0;
+const MIN_Q: f32 = 0.1;
+const MAX_Q: f32 = 10.0;
+/// Pre-amp range in dB, either way.
+const MAX_PREAMP_DB: f32 = 12.0;
 const COEF_UPDATE_INTERVAL: usize = 256;
 
-/// Gain in dB per band (±12 dB). Stored as centi-dB for lock-free updates.
-#[derive(Debug)]
-pub struct EqGains {
-    bass: AtomicI32,
-    mid: AtomicI32,
-    treble: AtomicI32,
+/// Named gain presets as (Bass, Mid, Treble) in dB at the default band
+/// frequencies; other bands follow the curve through them (see
+/// `preset_gain`).
+pub const PRESETS: [(&str, [f32; 3]); 5] = [
+    ("Flat", [0.0, 0.0, 0.0]),
+    ("Rock", [4.0, -2.0, 3.0]),
+    ("Pop", [-1.0, 3.0, 2.0]),
+    ("Jazz", [3.0, 1.0, 2.0]),
+    ("Bass Boost", [8.0, 0.0, -1.0]),
+];
+
+/// Gain of a preset at `freq`: interpolated between its three points on a
+/// logarithmic frequency scale, and level beyond the outer ones.
+pub fn preset_gain(points: &[f32; 3], freq: f32) -> f32 {
+    let x = freq.log2();
+    let xs = DEFAULT_FREQS.map(f32::log2);
+    if x <= xs[0] {
+        return points[0];
+    }
+    for i in 0..2 {
+        if x <= xs[i + 1] {
+            let t = (x - xs[i]) / (xs[i + 1] - xs[i]);
+            return points[i] + (points[i + 1] - points[i]) * t;
+        }
+    }
+    points[2]
 }
 
-impl Default for EqGains {
+/// `[equalizer]` settings from the config file.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct EqConfig {
+    /// Centre frequency of each band in Hz, lowest first.
+    pub bands: Vec<f32>,
+}
+
+impl Default for EqConfig {
     fn default() -> Self {
         Self {
-            bass: AtomicI32::new(0),
-            mid: AtomicI32::new(0),
-            treble: AtomicI32::new(0),
+            bands: DEFAULT_FREQS.to_vec(),
+        }
+    }
+}
+
+impl EqConfig {
+    /// The config with an unusable band list (empty, more than `MAX_BANDS`,
+    /// outside 20 Hz – 20 kHz or not ascending) replaced by the default,
+    /// reporting it on stderr.
+    pub fn validated(self) -> Self {
+        let bands = &self.bands;
+        let problem = if bands.is_empty() || bands.len() > MAX_BANDS {
+            Some(format!("expected 1 to {MAX_BANDS} bands"))
+        } else if bands.iter().any(|hz| !(MIN_FREQ..=MAX_FREQ).contains(hz)) {
+            Some("frequencies must be between 20 and 20000 Hz".to_string())
+        } else if bands.windows(2).any(|pair| pair[0] >= pair[1]) {
+            Some("frequencies must be in ascending order".to_string())
+        } else {
+            None
+        };
+        match problem {
+            Some(problem) => {
+                eprintln!("config: ignoring equalizer.bands = {bands:?}: {problem}");
+                Self::default()
+            }
+            None => self,
         }
     }
 }
 
+/// Gain in dB per band (±12 dB), stored as centi-dB, plus each band's centre
+/// frequency and the shared Q, stored as `f32` bits, all for lock-free updates.
+#[derive(Debug)]
+pub struct EqGains {
+    gains: Vec<AtomicI32>,
+    freqs: Vec<AtomicU32>,
+    q: AtomicU32,
+    /// When false the EQ is bypassed and samples pass through unfiltered.
+    enabled: AtomicBool,
+    /// Pre-amp gain in centi-dB; not affected by bypass.
+    preamp: AtomicI32,
+}
+
+/// Everything the filter coefficients depend on; compared to detect changes.
+#[derive(Debug, Clone, PartialEq)]
+struct EqParams {
+    centi: Vec<i32>,
+    freqs: Vec<f32>,
+    q: f32,
+}
+
+fn load_f32(a: &AtomicU32) -> f32 {
+    f32::from_bits(a.load(Ordering::Relaxed))
+}
+
 impl EqGains {
-    pub fn new() -> Self {
-        Self::default()
+    /// Flat bands centred on `freqs`.
+    pub fn new(freqs: &[f32]) -> Self {
+        Self {
+            gains: freqs.iter().map(|_| AtomicI32::new(0)).collect(),
+            freqs: freqs
+                .iter()
+                .map(|hz| AtomicU32::new(hz.clamp(MIN_FREQ, MAX_FREQ).to_bits()))
+                .collect(),
+            q: AtomicU32::new(Q.to_bits()),
+            enabled: AtomicBool::new(true),
+            preamp: AtomicI32::new(0),
+        }
     }
 
-    pub fn bass_db(&self) -> f32 {
-        self.bass.load(Ordering::Relaxed) as f32 * 0.01
+    pub fn band_count(&self) -> usize {
+        self.gains.len()
     }
-    pub fn set_bass_db(&self, db: f32) {
-        let c = (db.clamp(-12.0, 12.0) * 100.0).round() as i32;
-        self.bass
-            .store(c.clamp(MIN_CENTI_DB, MAX_CENTI_DB), Ordering::Relaxed);
+
+    /// Gain of `band` in dB (0 for a band that does not exist).
+    pub fn band_db(&self, band: usize) -> f32 {
+        self.gains
+            .get(band)
+            .map_or(0.0, |c| c.load(Ordering::Relaxed) as f32 * 0.01)
+    }
+    pub fn set_band_db(&self, band: usize, db: f32) {
+        if let Some(gain) = self.gains.get(band) {
+            let c = (db.clamp(-12.0, 12.0) * 100.0).round() as i32;
+            gain.store(c.clamp(MIN_CENTI_DB, MAX_CENTI_DB), Ordering::Relaxed);
+        }
     }
 
-    pub fn mid_db(&self) -> f32 {
-        self.mid.load(Ordering::Relaxed) as f32 * 0.01
+    /// Centre frequency of `band` in Hz (0 for a band that does not exist).
+    pub fn band_freq(&self, band: usize) -> f32 {
+        self.freqs.get(band).map_or(0.0, load_f32)
     }
-    pub fn set_mid_db(&self, db: f32) {
-        let c = (db.clamp(-12.0, 12.0) * 100.0).round() as i32;
-        self.mid
-            .store(c.clamp(MIN_CENTI_DB, MAX_CENTI_DB), Ordering::Relaxed);
+    pub fn set_band_freq(&self, band: usize, hz: f32) {
+        if let Some(freq) = self.freqs.get(band) {
+            freq.store(hz.clamp(MIN_FREQ, MAX_FREQ).to_bits(), Ordering::Relaxed);
+        }
     }
 
-    pub fn treble_db(&self) -> f32 {
-        self.treble.load(Ordering::Relaxed) as f32 * 0.01
+    /// Q (bandwidth) shared by all bands; higher is narrower.
+    pub fn q(&self) -> f32 {
+        load_f32(&self.q)
     }
-    pub fn set_treble_db(&self, db: f32) {
-        let c = (db.clamp(-12.0, 12.0) * 100.0).round() as i32;
-        self.treble
-            .store(c.clamp(MIN_CENTI_DB, MAX_CENTI_DB), Ordering::Relaxed);
+    pub fn set_q(&self, q: f32) {
+        self.q
+            .store(q.clamp(MIN_Q, MAX_Q).to_bits(), Ordering::Relaxed);
     }
 
-    fn load_centi(&self) -> (i32, i32, i32) {
-        (
-            self.bass.load(Ordering::Relaxed),
-            self.mid.load(Ordering::Relaxed),
-            self.treble.load(Ordering::Relaxed),
-        )
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+    pub fn toggle_enabled(&self) {
+        self.enabled.fetch_xor(true, Ordering::Relaxed);
+    }
+
+    /// Pre-amp gain in dB (±12), applied before the bands.
+    pub fn preamp_db(&self) -> f32 {
+        self.preamp.load(Ordering::Relaxed) as f32 * 0.01
+    }
+    pub fn set_preamp_db(&self, db: f32) {
+        let c = (db.clamp(-MAX_PREAMP_DB, MAX_PREAMP_DB) * 100.0).round() as i32;
+        self.preamp.store(c, Ordering::Relaxed);
+    }
+    /// Pre-amp as a linear amplitude factor.
+    pub fn preamp_factor(&self) -> f32 {
+        10f32.powf(self.preamp_db() / 20.0)
     }
-}
 
-fn make_peaking(sr: f32, freq: f32, gain_db: f32) -> Option<Coefficients<f32>> {
-    Coefficients::<f32>::from_params(Type::PeakingEQ(gain_db), (sr as i32).hz(), freq.hz(), Q).ok()
+    fn load_params(&self) -> EqParams {
+        EqParams {
+            centi: self.gains.iter().map(|c| c.load(Ordering::Relaxed)).collect(),
+            freqs: self.freqs.iter().map(load_f32).collect(),
+            q: self.q(),
+        }
+    }
+
+    /// Bring `params` up to date; true if anything changed. Does not
+    /// allocate, as the audio thread calls it continuously.
+    fn refresh_params(&self, params: &mut EqParams) -> bool {
+        let mut changed = false;
+        for (centi, gain) in params.centi.iter_mut().zip(&self.gains) {
+            let now = gain.load(Ordering::Relaxed);
+            changed |= *centi != now;
+            *centi = now;
+        }
+        for (hz, freq) in params.freqs.iter_mut().zip(&self.freqs) {
+            let now = load_f32(freq);
+            changed |= *hz != now;
+            *hz = now;
+        }
+        let q = self.q();
+        changed |= params.q != q;
+        params.q = q;
+        changed
+    }
 }
 
-fn flat_coeffs(sr: f32, freq: f32) -> Coefficients<f32> {
-    make_peaking(sr, freq, 0.0).unwrap_or_else(|| {
-        Coefficients::<f32>::from_params(Type::PeakingEQ(0.0), (sr as i32).hz(), freq.hz(), Q)
-            .unwrap()
-    })
+fn make_peaking(sr: f32, freq: f32, gain_db: f32, q: f32) -> Option<Coefficients<f32>> {
+    Coefficients::<f32>::from_params(Type::PeakingEQ(gain_db), (sr as i32).hz(), freq.hz(), q).ok()
 }
 
-fn update_coeffs(
-    sr: f32,
-    b: i32,
-    m: i32,
-    t: i32,
-    bass: &mut DirectForm1<f32>,
-    mid: &mut DirectForm1<f32>,
-    treble: &mut DirectForm1<f32>,
-) {
-    if let Some(c) = make_peaking(sr, BASS_FREQ, b as f32 * 0.01) {
-        bass.update_coefficients(c);
-    }
-    if let Some(c) = make_peaking(sr, MID_FREQ, m as f32 * 0.01) {
-        mid.update_coefficients(c);
+/// Pass-through coefficients, used when a band's frequency is above Nyquist.
+fn identity_coeffs() -> Coefficients<f32> {
+    Coefficients {
+        a1: 0.0,
+        a2: 0.0,
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
     }
-    if let Some(c) = make_peaking(sr, TREBLE_FREQ, t as f32 * 0.01) {
-        treble.update_coefficients(c);
+}
+
+fn update_coeffs(sr: f32, p: &EqParams, filters: &mut [DirectForm1<f32>]) {
+    for ((filter, &freq), &centi) in filters.iter_mut().zip(&p.freqs).zip(&p.centi) {
+        let coeffs =
+            make_peaking(sr, freq, centi as f32 * 0.01, p.q).unwrap_or_else(identity_coeffs);
+        filter.update_coefficients(coeffs);
     }
 }
 
 pub struct EqSource<S> {
     inner: S,
     gains: Arc<EqGains>,
-    bass: DirectForm1<f32>,
-    mid: DirectForm1<f32>,
-    treble: DirectForm1<f32>,
+    /// One filter per band, applied lowest band first.
+    filters: Vec<DirectForm1<f32>>,
     sample_rate: u32,
-    last_gains: (i32, i32, i32),
+    last_params: EqParams,
     n: usize,
 }
 
@@ -122,19 +263,15 @@ impl<S: Source<Item = f32>> EqSource<S> {
     pub fn new(inner: S, gains: Arc<EqGains>) -> Self {
         let sr_f = inner.sample_rate() as f32;
         let sr_u = inner.sample_rate();
-        let (b, m, t) = gains.load_centi();
-        let mut bass = DirectForm1::new(flat_coeffs(sr_f, BASS_FREQ));
-        let mut mid = DirectForm1::new(flat_coeffs(sr_f, MID_FREQ));
-        let mut treble = DirectForm1::new(flat_coeffs(sr_f, TREBLE_FREQ));
-        update_coeffs(sr_f, b, m, t, &mut bass, &mut mid, &mut treble);
+        let params = gains.load_params();
+        let mut filters = vec![DirectForm1::new(identity_coeffs()); gains.band_count()];
+        update_coeffs(sr_f, &params, &mut filters);
         Self {
             inner,
             gains,
-            bass,
-            mid,
-            treble,
+            filters,
             sample_rate: sr_u,
-            last_gains: (b, m, t),
+            last_params: params,
             n: 0,
         }
     }
@@ -145,21 +282,11 @@ impl<S: Source<Item = f32>> EqSource<S> {
             return;
         }
         self.n = 0;
-        let cur = self.gains.load_centi();
-        if cur == self.last_gains {
+        if !self.gains.refresh_params(&mut self.last_params) {
             return;
         }
-        self.last_gains = cur;
         let sr = self.sample_rate as f32;
-        update_coeffs(
-            sr,
-            cur.0,
-            cur.1,
-            cur.2,
-            &mut self.bass,
-            &mut self.mid,
-            &mut self.treble,
-        );
+        update_coeffs(sr, &self.last_params, &mut self.filters);
     }
 }
 
@@ -167,8 +294,12 @@ impl<S: Source<Item = f32>> Iterator for EqSource<S> {
     type Item = f32;
     fn next(&mut self) -> Option<f32> {
         let s = self.inner.next()?;
+        // Keep coefficients current while bypassed so re-enabling is seamless.
         self.maybe_update();
-        Some(self.treble.run(self.mid.run(self.bass.run(s))))
+        if !self.gains.is_enabled() {
+            return Some(s);
+        }
+        Some(self.filters.iter_mut().fold(s, |s, filter| filter.run(s)))
     }
 }
 
@@ -185,10 +316,9 @@ impl<S: Source<Item = f32>> Source for EqSource<S> {
     fn total_duration(&self) -> Option<Duration> {
         self.inner.total_duration()
     }
+    /// The filter state is kept: zeroing it mid-stream is what makes a
+    /// seek click, while the old history fades out within a few samples.
     fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
-        self.bass.reset_state();
-        self.mid.reset_state();
-        self.treble.reset_state();
         self.inner.try_seek(pos)
     }
 }
@@ -0,0 +1,45 @@
+//! Starred tracks, kept as JSON next to the session in the user's config
+//! directory and keyed by canonical path, so stars survive rescans.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::session::{PathStore, track_key};
+
+const FAVORITES_FILE: &str = "favorites.json";
+
+pub struct Favorites {
+    store: PathStore<HashSet<PathBuf>>,
+}
+
+impl Favorites {
+    /// Load saved stars. Missing or unreadable files yield none.
+    pub fn load() -> Self {
+        Self {
+            store: PathStore::load(FAVORITES_FILE),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.store.save()
+    }
+
+    /// Whether the track whose `track_key` is `key` is starred.
+    pub fn contains(&self, key: &Path) -> bool {
+        self.store.entries.contains(key)
+    }
+
+    /// Star `track`, or unstar it if it already is. Returns whether it is
+    /// starred now.
+    pub fn toggle(&mut self, track: &Path) -> bool {
+        let key = track_key(track);
+        if self.store.entries.remove(&key) {
+            false
+        } else {
+            self.store.entries.insert(key);
+            true
+        }
+    }
+}
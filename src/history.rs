@@ -0,0 +1,55 @@
+//! Recently played tracks, most recent first, kept as JSON next to the
+//! session in the user's config directory and keyed by canonical path.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::session::{PathStore, track_key};
+
+const HISTORY_FILE: &str = "history.json";
+
+/// Plays remembered; older ones are dropped.
+pub const HISTORY_LEN: usize = 100;
+
+pub struct History {
+    store: PathStore<VecDeque<PathBuf>>,
+    /// Stamp the next new entry gets; see `stamp`.
+    next: u64,
+}
+
+impl History {
+    /// Load the saved history. Missing or unreadable files yield none.
+    pub fn load() -> Self {
+        let store: PathStore<VecDeque<PathBuf>> = PathStore::load(HISTORY_FILE);
+        let next = store.entries.len() as u64 + 1;
+        Self { store, next }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.store.save()
+    }
+
+    /// When the track whose `track_key` is `key` was last played, as a
+    /// number that grows with every play, so a larger stamp is more recent.
+    /// `None` if it is not among the remembered plays.
+    pub fn stamp(&self, key: &Path) -> Option<u64> {
+        let pos = self.store.entries.iter().position(|path| path == key)?;
+        Some(self.next - 1 - pos as u64)
+    }
+
+    /// Record that `track` started playing and return its new stamp.
+    /// Playing the same track again right away (e.g. on Repeat One) adds no
+    /// entry.
+    pub fn push(&mut self, track: &Path) -> u64 {
+        let key = track_key(track);
+        let paths = &mut self.store.entries;
+        if paths.front() != Some(&key) {
+            paths.push_front(key);
+            paths.truncate(HISTORY_LEN);
+            self.next += 1;
+        }
+        self.next - 1
+    }
+}
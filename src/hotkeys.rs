@@ -0,0 +1,102 @@
+//! Global media keys (`global-hotkeys` feature, macOS and Windows), so play
+//! and pause, next and previous work while the terminal is not focused.
+//! Linux desktops get the same through MPRIS.
+//!
+//! The keys are registered with the system from the main thread, which
+//! must then keep its event loop going: `GlobalHotkeys::actions` pumps it
+//! once per tick and turns the presses it delivered into the actions the
+//! keys run.
+
+use global_hotkey::hotkey::{Code, HotKey};
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+
+use crate::keymap::Action;
+
+const MEDIA_KEYS: [(Code, Action); 3] = [
+    (Code::MediaPlayPause, Action::PlayPause),
+    (Code::MediaTrackNext, Action::Next),
+    (Code::MediaTrackPrevious, Action::Previous),
+];
+
+pub struct GlobalHotkeys {
+    /// Releases the keys again when dropped.
+    _manager: GlobalHotKeyManager,
+    /// Id of each registered key and the action it runs.
+    actions: Vec<(u32, Action)>,
+}
+
+impl GlobalHotkeys {
+    /// Register the media keys; call on the main thread before the terminal
+    /// switches to the alternate screen. A key that cannot be registered
+    /// (e.g. because another program holds it) is reported on stderr and
+    /// left out. `None` when no key could be registered.
+    pub fn register() -> Option<Self> {
+        let manager = match GlobalHotKeyManager::new() {
+            Ok(manager) => manager,
+            Err(e) => {
+                eprintln!("hotkeys: global media keys are unavailable: {e}");
+                return None;
+            }
+        };
+        let mut actions = Vec::new();
+        for (code, action) in MEDIA_KEYS {
+            let hotkey = HotKey::new(None, code);
+            match manager.register(hotkey) {
+                Ok(()) => actions.push((hotkey.id(), action)),
+                Err(e) => eprintln!("hotkeys: cannot register {code:?}: {e}"),
+            }
+        }
+        (!actions.is_empty()).then_some(Self {
+            _manager: manager,
+            actions,
+        })
+    }
+
+    /// The actions of the media keys pressed since the last call, in order.
+    /// Called every tick.
+    pub fn actions(&self) -> Vec<Action> {
+        pump_events();
+        let mut actions = Vec::new();
+        while let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
+            if event.state() != HotKeyState::Pressed {
+                continue;
+            }
+            if let Some(&(_, action)) = self.actions.iter().find(|(id, _)| *id == event.id()) {
+                actions.push(action);
+            }
+        }
+        actions
+    }
+}
+
+/// Dispatch the window messages waiting for this thread, the hot key
+/// presses among them, without waiting for more.
+#[cfg(target_os = "windows")]
+fn pump_events() {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, MSG, PM_REMOVE, PeekMessageW, TranslateMessage,
+    };
+
+    // SAFETY: `msg` is plain data, written by `PeekMessageW` before it is
+    // read, and only passed back to the same thread's message functions.
+    unsafe {
+        let mut msg: MSG = std::mem::zeroed();
+        while PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+/// Run the main run loop once without waiting, so it hands over the hot
+/// key presses that have arrived.
+#[cfg(target_os = "macos")]
+fn pump_events() {
+    use std::time::Duration;
+
+    use core_foundation::runloop::{CFRunLoop, kCFRunLoopDefaultMode};
+
+    // SAFETY: an immutable constant exported by CoreFoundation.
+    let mode = unsafe { kCFRunLoopDefaultMode };
+    CFRunLoop::run_in_mode(mode, Duration::ZERO, false);
+}
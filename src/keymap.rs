@@ -0,0 +1,317 @@
+//! Key bindings for the main screen: which key runs which action. Defaults
+//! are built in; the `[keys]` section of `config.toml` can rebind any action
+//! by name, e.g. `select_down = ["Down", "t"]`.
+//!
+//! Keys are written as a single character (`j`, `F`, `+`, `Space`) or a key
+//! name (`Enter`, `Esc`, `Backspace`, `Tab`, `Up`, `Down`, `Left`, `Right`,
+//! `PageUp`, `PageDown`, `Home`, `End`, `Delete`, `Insert`, `F1` – `F12`),
+//! optionally prefixed with `Ctrl+`, `Alt+` or `Shift+`.
+
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Help,
+    PlayPause,
+    Play,
+    ClosePlaylist,
+    Search,
+    ClearSearch,
+    Next,
+    Previous,
+    Restart,
+    SelectUp,
+    SelectDown,
+    PageUp,
+    PageDown,
+    SelectFirst,
+    SelectLast,
+    SelectPlaying,
+    SeekBackward,
+    SeekForward,
+    SeekBackwardLong,
+    SeekForwardLong,
+    SeekTo,
+    VolumeUp,
+    VolumeDown,
+    VolumeSet,
+    BalanceLeft,
+    BalanceRight,
+    SpeedDown,
+    SpeedUp,
+    ReplayGain,
+    Mono,
+    Sort,
+    RemainingTime,
+    PlayCounts,
+    Star,
+    StarredOnly,
+    VisualizerPalette,
+    VisualizerStereo,
+    SensitivityUp,
+    SensitivityDown,
+    AbLoop,
+    Bookmark,
+    ClearBookmarks,
+    NextBookmark,
+    PrevBookmark,
+    Repeat,
+    Shuffle,
+    Enqueue,
+    ClearQueue,
+    PlayFolder,
+    History,
+    SleepTimer,
+    SleepFinishTrack,
+    EqToggle,
+    Browse,
+    BrowseFocus,
+}
+
+/// Every action with its name in the config file and its default keys.
+const ACTIONS: &[(Action, &str, &[&str])] = &[
+    (Action::Quit, "quit", &["q"]),
+    (Action::Help, "help", &["?"]),
+    (Action::PlayPause, "play_pause", &["Space"]),
+    (Action::Play, "play", &["Enter"]),
+    (Action::ClosePlaylist, "close_playlist", &["Backspace"]),
+    (Action::Search, "search", &["/"]),
+    (Action::ClearSearch, "clear_search", &["Esc"]),
+    (Action::Next, "next", &["n"]),
+    (Action::Previous, "previous", &["p"]),
+    (Action::Restart, "restart", &["R"]),
+    (Action::SelectUp, "select_up", &["Up", "k"]),
+    (Action::SelectDown, "select_down", &["Down", "j"]),
+    (Action::PageUp, "page_up", &["PageUp"]),
+    (Action::PageDown, "page_down", &["PageDown"]),
+    (Action::SelectFirst, "select_first", &["Home"]),
+    (Action::SelectLast, "select_last", &["End", "G"]),
+    (Action::SelectPlaying, "select_playing", &["i"]),
+    (Action::SeekBackward, "seek_backward", &["Left"]),
+    (Action::SeekForward, "seek_forward", &["Right"]),
+    (Action::SeekBackwardLong, "seek_backward_long", &["Shift+Left"]),
+    (Action::SeekForwardLong, "seek_forward_long", &["Shift+Right"]),
+    (Action::SeekTo, "seek_to", &[":"]),
+    (Action::VolumeUp, "volume_up", &["+", "="]),
+    (Action::VolumeDown, "volume_down", &["-"]),
+    (Action::VolumeSet, "volume_set", &["%"]),
+    (Action::BalanceLeft, "balance_left", &["<"]),
+    (Action::BalanceRight, "balance_right", &[">"]),
+    (Action::SpeedDown, "speed_down", &["["]),
+    (Action::SpeedUp, "speed_up", &["]"]),
+    (Action::ReplayGain, "replay_gain", &["g"]),
+    (Action::Mono, "mono", &["D"]),
+    (Action::Sort, "sort", &["o"]),
+    (Action::RemainingTime, "remaining_time", &["d"]),
+    (Action::PlayCounts, "play_counts", &["c"]),
+    (Action::Star, "star", &["f"]),
+    (Action::StarredOnly, "starred_only", &["F"]),
+    (Action::History, "history", &["H"]),
+    (Action::VisualizerPalette, "visualizer_palette", &["v"]),
+    (Action::VisualizerStereo, "visualizer_stereo", &["V"]),
+    (Action::SensitivityUp, "sensitivity_up", &["}"]),
+    (Action::SensitivityDown, "sensitivity_down", &["{"]),
+    (Action::AbLoop, "ab_loop", &["l"]),
+    (Action::Bookmark, "bookmark", &["m"]),
+    (Action::ClearBookmarks, "clear_bookmarks", &["M"]),
+    (Action::NextBookmark, "next_bookmark", &["."]),
+    (Action::PrevBookmark, "prev_bookmark", &[","]),
+    (Action::Repeat, "repeat", &["r"]),
+    (Action::Shuffle, "shuffle", &["s"]),
+    (Action::Enqueue, "enqueue", &["a"]),
+    (Action::ClearQueue, "clear_queue", &["A"]),
+    (Action::PlayFolder, "play_folder", &["P"]),
+    (Action::SleepTimer, "sleep_timer", &["t"]),
+    (Action::SleepFinishTrack, "sleep_finish_track", &["T"]),
+    (Action::EqToggle, "eq_toggle", &["Ctrl+e"]),
+    (Action::Browse, "browse", &["b"]),
+    (Action::BrowseFocus, "browse_focus", &["Tab"]),
+];
+
+/// Keys for one action in the config file: a single key or a list. An empty
+/// list leaves the action unbound.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum KeySpec {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeySpec {
+    fn keys(&self) -> &[String] {
+        match self {
+            KeySpec::One(key) => std::slice::from_ref(key),
+            KeySpec::Many(keys) => keys,
+        }
+    }
+}
+
+pub struct KeyMap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Action>,
+    /// Every bound key in the order it was first given, for listing them.
+    order: Vec<(KeyCode, KeyModifiers)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::with_overrides(&HashMap::new())
+    }
+}
+
+impl KeyMap {
+    /// Default bindings, with the actions named in `overrides` bound to the
+    /// given keys instead. Unknown action or key names are reported on stderr
+    /// and skipped.
+    pub fn with_overrides(overrides: &HashMap<String, KeySpec>) -> Self {
+        let mut unknown: Vec<&String> = overrides
+            .keys()
+            .filter(|name| !ACTIONS.iter().any(|(_, n, _)| n == name))
+            .collect();
+        unknown.sort();
+        for name in unknown {
+            eprintln!("config: unknown action \"{name}\" in [keys]");
+        }
+
+        let mut map = Self {
+            bindings: HashMap::new(),
+            order: Vec::new(),
+        };
+        // Defaults first, so a key the user assigned elsewhere wins over them.
+        for &(action, name, defaults) in ACTIONS {
+            if !overrides.contains_key(name) {
+                for key in defaults.iter().filter_map(|key| parse_key(key)) {
+                    map.bind(key, action);
+                }
+            }
+        }
+        for &(action, name, _) in ACTIONS {
+            let Some(spec) = overrides.get(name) else {
+                continue;
+            };
+            for key in spec.keys() {
+                match parse_key(key) {
+                    Some(key) => map.bind(key, action),
+                    None => eprintln!("config: unknown key \"{key}\" for {name} in [keys]"),
+                }
+            }
+        }
+        map
+    }
+
+    fn bind(&mut self, key: (KeyCode, KeyModifiers), action: Action) {
+        if self.bindings.insert(key, action).is_none() {
+            self.order.push(key);
+        }
+    }
+
+    /// The action bound to a key press, if any.
+    pub fn action(&self, code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        self.bindings.get(&normalize(code, modifiers)).copied()
+    }
+
+    /// Names of the keys bound to `action`, in the order they were given,
+    /// e.g. `["↑", "k"]`. Empty if the action is unbound.
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        self.order
+            .iter()
+            .filter(|key| self.bindings.get(key) == Some(&action))
+            .map(|&(code, modifiers)| key_name(code, modifiers))
+            .collect()
+    }
+}
+
+/// Parse `Ctrl+Alt+x`-style key text into a normalized key.
+fn parse_key(text: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = text;
+    loop {
+        let (prefix, tail) = match rest.split_once('+') {
+            Some((prefix, tail)) if !tail.is_empty() => (prefix, tail),
+            _ => break,
+        };
+        modifiers |= match prefix.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" | "meta" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+        rest = tail;
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => {
+            KeyCode::Char(c.to_ascii_uppercase())
+        }
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "tab" => KeyCode::Tab,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            name => {
+                let n = name.strip_prefix('f')?.parse().ok()?;
+                (1..=12).contains(&n).then_some(KeyCode::F(n))?
+            }
+        },
+    };
+    Some(normalize(code, modifiers))
+}
+
+/// How a key is shown on screen: arrows as symbols, Ctrl/Alt letters in
+/// upper case, other keys by the names `parse_key` reads.
+fn key_name(code: KeyCode, modifiers: KeyModifiers) -> String {
+    let mut name = String::new();
+    for (modifier, prefix) in [
+        (KeyModifiers::CONTROL, "Ctrl+"),
+        (KeyModifiers::ALT, "Alt+"),
+        (KeyModifiers::SHIFT, "Shift+"),
+    ] {
+        if modifiers.contains(modifier) {
+            name.push_str(prefix);
+        }
+    }
+    match code {
+        KeyCode::Char(' ') => name.push_str("Space"),
+        KeyCode::Char(c) if modifiers.is_empty() => name.push(c),
+        KeyCode::Char(c) => name.push(c.to_ascii_uppercase()),
+        KeyCode::Up => name.push('↑'),
+        KeyCode::Down => name.push('↓'),
+        KeyCode::Left => name.push('←'),
+        KeyCode::Right => name.push('→'),
+        KeyCode::PageUp => name.push_str("PgUp"),
+        KeyCode::PageDown => name.push_str("PgDn"),
+        KeyCode::Delete => name.push_str("Del"),
+        KeyCode::Insert => name.push_str("Ins"),
+        KeyCode::F(n) => name.push_str(&format!("F{n}")),
+        other => name.push_str(&format!("{other:?}")),
+    }
+    name
+}
+
+/// Terminals disagree on whether Shift accompanies characters it produced
+/// (`F`, `?`), so it is ignored for characters; other modifiers beyond
+/// Ctrl, Alt and Shift are ignored everywhere.
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    let mut modifiers =
+        modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+    if matches!(code, KeyCode::Char(_)) {
+        modifiers.remove(KeyModifiers::SHIFT);
+    }
+    (code, modifiers)
+}
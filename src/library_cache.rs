@@ -0,0 +1,118 @@
+//! Cache of scanned track metadata (tags and duration), kept as JSON in the
+//! user's config directory so a restart does not re-read every file. Entries
+//! are keyed by path and only trusted while the file's size and modification
+//! time are unchanged.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::metadata::Tags;
+use crate::session::config_dir;
+
+const CACHE_FILE: &str = "library.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    size: u64,
+    modified: SystemTime,
+    tags: Tags,
+    /// `None` until the background scan has measured the file.
+    duration: Option<Duration>,
+}
+
+#[derive(Clone, Default)]
+pub struct LibraryCache {
+    entries: HashMap<PathBuf, Entry>,
+    /// Changed since loading, so worth writing back.
+    dirty: bool,
+}
+
+impl LibraryCache {
+    /// Load the cache. Missing or unreadable files yield an empty cache.
+    pub fn load() -> Self {
+        let entries = config_dir()
+            .and_then(|dir| fs::read_to_string(dir.join(CACHE_FILE)).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// Write the cache if it changed, first pruning entries whose file no
+    /// longer exists.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.entries.retain(|path, _| path.is_file());
+        let dir = config_dir().context("no config directory (HOME is not set)")?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("creating {}", dir.display()))?;
+        let path = dir.join(CACHE_FILE);
+        fs::write(&path, serde_json::to_string(&self.entries)?)
+            .with_context(|| format!("writing {}", path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Tags and duration of `path`, or `None` when it is not cached or the
+    /// file has changed since.
+    pub fn get(&self, path: &Path) -> Option<(Tags, Option<Duration>)> {
+        let entry = self.entries.get(path)?;
+        let (size, modified) = stamp(path)?;
+        (entry.size == size && entry.modified == modified)
+            .then(|| (entry.tags.clone(), entry.duration))
+    }
+
+    /// Remember freshly read tags of `path` (its duration is not known yet).
+    pub fn insert(&mut self, path: &Path, tags: &Tags) {
+        let Some((size, modified)) = stamp(path) else {
+            return;
+        };
+        let entry = Entry {
+            size,
+            modified,
+            tags: tags.clone(),
+            duration: None,
+        };
+        self.entries.insert(path.to_path_buf(), entry);
+        self.dirty = true;
+    }
+
+    /// Take over the entries a background scan added to its copy of the
+    /// cache. Entries for unchanged files stay as they are here, keeping
+    /// durations measured while the scan ran.
+    pub fn merge(&mut self, scanned: LibraryCache) {
+        for (path, entry) in scanned.entries {
+            let unchanged = self
+                .entries
+                .get(&path)
+                .is_some_and(|ours| ours.size == entry.size && ours.modified == entry.modified);
+            if !unchanged {
+                self.entries.insert(path, entry);
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Record the measured duration of a cached file.
+    pub fn set_duration(&mut self, path: &Path, duration: Duration) {
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.duration = Some(duration);
+            self.dirty = true;
+        }
+    }
+}
+
+/// Size and modification time of `path`, which identify its contents.
+fn stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.len(), meta.modified().ok()?))
+}
@@ -1,18 +1,52 @@
 mod app;
+mod bookmarks;
+mod config;
+mod cover;
+mod decoder;
+mod dedupe;
 mod eq;
+mod favorites;
+mod history;
+#[cfg(all(feature = "global-hotkeys", any(target_os = "macos", target_os = "windows")))]
+mod hotkeys;
+mod keymap;
+mod library_cache;
+mod metadata;
+#[cfg(all(feature = "mpris", target_os = "linux"))]
+mod mpris;
+mod play_counts;
 mod player;
+mod playlist;
+mod resume;
+mod session;
+#[cfg(all(feature = "socket", unix))]
+mod socket;
+mod stream;
+mod theme;
+mod track_eq;
 mod ui;
 mod visualizer;
+mod watcher;
 
 use std::io::{self, Stdout};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use anyhow::{Context, Result, bail};
+use app::PromptKind;
+use config::Config;
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+    MouseButton, MouseEvent, MouseEventKind,
+};
 use crossterm::execute;
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use keymap::{Action, KeyMap};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
+use ratatui_image::picker::Picker;
+
+use player::Player;
 
 fn main() -> Result<()> {
     // Set panic hook to restore terminal on panic
@@ -22,16 +56,80 @@ fn main() -> Result<()> {
         original_hook(info);
     }));
 
+    let args = parse_args()?;
+    if args.list_devices {
+        for name in Player::list_devices()? {
+            println!("{name}");
+        }
+        return Ok(());
+    }
+    let config = Config::load();
+    // Registered from the main thread, warning here rather than over the UI.
+    #[cfg(all(feature = "global-hotkeys", any(target_os = "macos", target_os = "windows")))]
+    let hotkeys = hotkeys::GlobalHotkeys::register();
+    // Optional, so a socket that cannot be opened (e.g. one held by another
+    // player) is only warned about, before the UI takes over the terminal.
+    // Stopped and its socket file removed when dropped on the way out.
+    #[cfg(all(feature = "socket", unix))]
+    let status_socket = if config.socket.enabled {
+        let path = config.socket.path.clone().unwrap_or_else(socket::default_path);
+        socket::StatusSocket::start(&path)
+            .inspect_err(|e| eprintln!("socket: status socket is unavailable: {e:#}"))
+            .ok()
+    } else {
+        None
+    };
+
     let mut terminal = setup_terminal()?;
-    let result = run(&mut terminal);
+    let picker = cover::detect_picker();
+    let result = run(
+        &mut terminal,
+        args,
+        &config,
+        picker,
+        #[cfg(all(feature = "global-hotkeys", any(target_os = "macos", target_os = "windows")))]
+        hotkeys.as_ref(),
+        #[cfg(all(feature = "socket", unix))]
+        status_socket.as_ref(),
+    );
     restore_terminal()?;
     result
 }
 
+#[derive(Default)]
+struct Args {
+    /// Library directory, or `-` (standard input) or a URL to play a stream.
+    music_dir: Option<PathBuf>,
+    /// Output device name (see `--list-devices`).
+    device: Option<String>,
+    list_devices: bool,
+}
+
+/// Usage: `tui_music_player [--device NAME] [--list-devices] [MUSIC_DIR | - | URL]`.
+fn parse_args() -> Result<Args> {
+    let mut args = Args::default();
+    let mut iter = std::env::args_os().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.to_str() {
+            Some("--list-devices") => args.list_devices = true,
+            Some("--device") => {
+                let name = iter.next().context("--device needs a device name")?;
+                args.device = Some(name.to_string_lossy().into_owned());
+            }
+            Some(s) if s.starts_with("--device=") => {
+                args.device = Some(s["--device=".len()..].to_string());
+            }
+            _ if args.music_dir.is_none() => args.music_dir = Some(PathBuf::from(arg)),
+            _ => bail!("unexpected argument {:?}", arg),
+        }
+    }
+    Ok(args)
+}
+
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
     terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let terminal = Terminal::new(backend)?;
     Ok(terminal)
@@ -39,29 +137,80 @@ fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
 
 fn restore_terminal() -> Result<()> {
     terminal::disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen)?;
+    execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen)?;
     Ok(())
 }
 
-fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
-    let mut app = app::App::new()?;
-    let tick_rate = Duration::from_millis(100);
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    args: Args,
+    config: &Config,
+    picker: Picker,
+    #[cfg(all(feature = "global-hotkeys", any(target_os = "macos", target_os = "windows")))]
+    hotkeys: Option<&hotkeys::GlobalHotkeys>,
+    #[cfg(all(feature = "socket", unix))]
+    status_socket: Option<&socket::StatusSocket>,
+) -> Result<()> {
+    let mut app = app::App::new(args.music_dir, args.device.as_deref(), config, picker)?;
+    // Without a session bus the player simply runs without media keys.
+    #[cfg(all(feature = "mpris", target_os = "linux"))]
+    let mpris = mpris::Mpris::start().ok();
     let mut last_tick = Instant::now();
 
     loop {
-        terminal.draw(|frame| ui::draw(frame, &mut app))?;
+        terminal.draw(|frame| ui::draw(frame, &mut app, &config.keymap))?;
 
+        // Back off while nothing moves on screen; any event still wakes the
+        // loop at once, and starting playback brings the fast tick back.
+        let tick_rate = Duration::from_millis(if app.is_idle() {
+            config.ui.idle_tick_ms
+        } else {
+            config.ui.tick_ms
+        });
         let timeout = tick_rate.saturating_sub(last_tick.elapsed());
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    handle_key(&mut app, key.code, key.modifiers);
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    handle_key(&mut app, &config.keymap, key.code, key.modifiers);
                 }
+                Event::Mouse(mouse) => handle_mouse(&mut app, mouse),
+                _ => {}
+            }
+        }
+        #[cfg(all(feature = "socket", unix))]
+        if let Some(status_socket) = status_socket {
+            while let Some(command) = status_socket.try_command() {
+                socket::apply(&mut app, command);
+            }
+        }
+        #[cfg(all(feature = "global-hotkeys", any(target_os = "macos", target_os = "windows")))]
+        if let Some(hotkeys) = hotkeys {
+            for action in hotkeys.actions() {
+                run_action(&mut app, action);
             }
         }
 
         if last_tick.elapsed() >= tick_rate {
+            app.poll_durations();
+            app.check_library_changes();
+            app.check_ab_loop();
+            app.check_load();
             app.check_track_end();
+            app.check_sleep_timer();
+            app.check_error_timeout();
+            app.check_status_timeout();
+            app.check_title_scroll();
+            if let Some(pending) = app.take_expired_keys() {
+                flush_pending_keys(&mut app, &config.keymap, pending);
+            }
+            #[cfg(all(feature = "mpris", target_os = "linux"))]
+            if let Some(mpris) = &mpris {
+                mpris.update(&mut app);
+            }
+            #[cfg(all(feature = "socket", unix))]
+            if let Some(status_socket) = status_socket {
+                status_socket.update(&app);
+            }
             last_tick = Instant::now();
         }
 
@@ -70,18 +219,152 @@ fn run(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
         }
     }
 
-    Ok(())
+    app.save_library_cache();
+    app.save_session()
 }
 
-fn handle_key(app: &mut app::App, code: KeyCode, modifiers: KeyModifiers) {
+/// Handle a key that may continue the `pending` prefix: another digit or
+/// `g`, or the motion it counts. Returns false if the key is unrelated.
+fn complete_pending_keys(
+    app: &mut app::App,
+    keymap: &KeyMap,
+    pending: app::PendingKeys,
+    code: KeyCode,
+    modifiers: KeyModifiers,
+) -> bool {
+    let plain = !modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+    match code {
+        KeyCode::Char('g') if plain && pending.g => {
+            match pending.count {
+                Some(n) => select_row(app, n),
+                None => run_action(app, Action::SelectFirst),
+            }
+            true
+        }
+        KeyCode::Char('g') if plain => {
+            app.pending_keys = Some(pending);
+            app.push_g();
+            true
+        }
+        KeyCode::Char(c @ '0'..='9')
+            if plain && !pending.g && keymap.action(code, modifiers).is_none() =>
+        {
+            app.pending_keys = Some(pending);
+            app.push_count(c.to_digit(10).unwrap_or(0));
+            true
+        }
+        _ => {
+            let Some(count) = pending.count.filter(|_| !pending.g) else {
+                return false;
+            };
+            match keymap.action(code, modifiers) {
+                Some(
+                    action @ (Action::SelectUp
+                    | Action::SelectDown
+                    | Action::PageUp
+                    | Action::PageDown),
+                ) => {
+                    for _ in 0..count.max(1) {
+                        run_action(app, action);
+                    }
+                    true
+                }
+                Some(Action::SelectFirst | Action::SelectLast) => {
+                    select_row(app, count);
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Row `n` (1-based) of whichever pane has the focus.
+fn select_row(app: &mut app::App, n: usize) {
+    if app.folders_focused() {
+        app.browse_select_at(|_| n.saturating_sub(1));
+    } else {
+        app.select_row(n);
+    }
+}
+
+/// A prefix that was not completed does what its keys do alone: the last
+/// digit seeks to that tenth of the track, and `g` runs its bound action.
+fn flush_pending_keys(app: &mut app::App, keymap: &KeyMap, pending: app::PendingKeys) {
+    if let Some(count) = pending.count {
+        let tenths = (count % 10) as u32;
+        app.seek_to_ratio(f64::from(tenths) / 10.0);
+    }
+    if pending.g
+        && let Some(action) = keymap.action(KeyCode::Char('g'), KeyModifiers::NONE)
+    {
+        run_action(app, action);
+    }
+}
+
+/// Clicks select/play list rows and seek on the progress bar; the wheel moves
+/// the selection. Ignored while a popup or prompt is open.
+fn handle_mouse(app: &mut app::App, mouse: MouseEvent) {
+    if app.prompt.is_some() || app.help_open || app.eq_popup_open() {
+        return;
+    }
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => app.click(mouse.column, mouse.row),
+        MouseEventKind::ScrollUp => app.select_prev(),
+        MouseEventKind::ScrollDown => app.select_next(),
+        _ => {}
+    }
+}
+
+fn handle_key(app: &mut app::App, keymap: &KeyMap, code: KeyCode, modifiers: KeyModifiers) {
     let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+    let alt = modifiers.contains(KeyModifiers::ALT);
+    let shift = modifiers.contains(KeyModifiers::SHIFT);
 
-    // Ctrl+E (or Ctrl+Meta+E where Meta is Alt): toggle Equalizer popup
-    if ctrl && code == KeyCode::Char('e') {
+    // An open text prompt captures every key until submitted or cancelled.
+    if app.prompt.is_some() {
+        match code {
+            KeyCode::Esc => app.prompt_cancel(),
+            KeyCode::Enter => app.prompt_submit(),
+            KeyCode::Backspace => app.prompt_pop(),
+            KeyCode::Char('c') if ctrl => app.should_quit = true,
+            KeyCode::Char(c) if !ctrl => app.prompt_push(c),
+            _ => {}
+        }
+        return;
+    }
+
+    // The help overlay swallows every key except the ones that close it.
+    if app.help_open {
+        match code {
+            KeyCode::Esc => app.help_toggle(),
+            _ if keymap.action(code, modifiers) == Some(Action::Help) => app.help_toggle(),
+            KeyCode::Char('c') if ctrl => app.should_quit = true,
+            _ => {}
+        }
+        return;
+    }
+
+    // Ctrl+E (or whatever `eq_toggle` is bound to) toggles the Equalizer
+    // popup, also from inside it.
+    if keymap.action(code, modifiers) == Some(Action::EqToggle) {
         app.eq_popup_toggle();
         return;
     }
 
+    // A gain being typed in the EQ popup takes every key until Enter or Esc.
+    if app.eq_popup_open() && app.eq_editing() {
+        match code {
+            KeyCode::Esc => app.eq_edit_cancel(),
+            KeyCode::Enter => app.eq_edit_commit(),
+            KeyCode::Backspace => app.eq_edit_pop(),
+            KeyCode::Char('c') if ctrl => app.should_quit = true,
+            KeyCode::Char(c) if !ctrl => app.eq_edit_push(c),
+            _ => {}
+        }
+        return;
+    }
+
     // When EQ popup is open, handle popup-specific keys first
     if app.eq_popup_open() {
         match code {
@@ -97,32 +380,205 @@ fn handle_key(app: &mut app::App, code: KeyCode, modifiers: KeyModifiers) {
                 app.eq_select_next_band();
                 return;
             }
+            // Shift (Shift+↑/↓, or K/J) steps by half a dB.
             KeyCode::Up | KeyCode::Char('k') => {
-                app.eq_band_up();
+                app.eq_band_up(shift);
+                return;
+            }
+            KeyCode::Char('K') => {
+                app.eq_band_up(true);
                 return;
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                app.eq_band_down();
+                app.eq_band_down(shift);
+                return;
+            }
+            KeyCode::Char('J') => {
+                app.eq_band_down(true);
+                return;
+            }
+            KeyCode::Tab => {
+                app.eq_next_preset();
+                return;
+            }
+            KeyCode::Backspace => {
+                app.eq_reset();
+                return;
+            }
+            KeyCode::Char(c @ ('0'..='9' | '-' | '.')) => {
+                app.eq_edit_push(c);
+                return;
+            }
+            KeyCode::Char('b') => {
+                app.eq_toggle_bypass();
+                return;
+            }
+            KeyCode::Char('f') => {
+                app.eq_freq_down();
+                return;
+            }
+            KeyCode::Char('F') => {
+                app.eq_freq_up();
+                return;
+            }
+            KeyCode::Char('s') => {
+                app.eq_save_for_track();
+                return;
+            }
+            KeyCode::Char('x') => {
+                app.eq_clear_for_track();
+                return;
+            }
+            KeyCode::Char('p') => {
+                app.eq_preamp_down();
+                return;
+            }
+            KeyCode::Char('P') => {
+                app.eq_preamp_up();
+                return;
+            }
+            KeyCode::Char('[') => {
+                app.eq_q_down();
+                return;
+            }
+            KeyCode::Char(']') => {
+                app.eq_q_up();
                 return;
             }
             _ => {}
         }
     }
 
+    // While typing a search query, keys edit the query instead of acting.
+    if app.search_mode {
+        match code {
+            KeyCode::Esc => app.search_clear(),
+            KeyCode::Enter => app.search_play(),
+            KeyCode::Backspace => app.search_pop(),
+            KeyCode::Up => app.select_prev(),
+            KeyCode::Down => app.select_next(),
+            KeyCode::Char('c') if ctrl => app.should_quit = true,
+            KeyCode::Char(c) if !ctrl => app.search_push(c),
+            _ => {}
+        }
+        return;
+    }
+
+    // Vim-style prefixes: a count before a motion (`5j`, `12G`) or `gg`.
+    if let Some(pending) = app.pending_keys.take() {
+        if complete_pending_keys(app, keymap, pending, code, modifiers) {
+            return;
+        }
+        // Not a continuation: the prefix keys act on their own first.
+        flush_pending_keys(app, keymap, pending);
+    }
+    if code == KeyCode::Char('g') && !ctrl && !alt {
+        app.push_g();
+        return;
+    }
+
+    if let Some(action) = keymap.action(code, modifiers) {
+        run_action(app, action);
+        return;
+    }
     match code {
-        KeyCode::Char('q') => app.should_quit = true,
         KeyCode::Char('c') if ctrl => app.should_quit = true,
-        KeyCode::Char(' ') => app.toggle_pause(),
-        KeyCode::Enter => app.play_selected(),
-        KeyCode::Char('n') => app.next_track(),
-        KeyCode::Char('p') => app.prev_track(),
-        KeyCode::Up | KeyCode::Char('k') => app.select_prev(),
-        KeyCode::Down | KeyCode::Char('j') => app.select_next(),
-        KeyCode::Left => app.seek_backward(),
-        KeyCode::Right => app.seek_forward(),
-        KeyCode::Char('+') | KeyCode::Char('=') => app.volume_up(),
-        KeyCode::Char('-') => app.volume_down(),
-        KeyCode::Char('r') => app.toggle_repeat(),
+        KeyCode::Char(c) if alt && c.is_alphanumeric() => app.jump_to_letter(c),
+        // A digit on its own seeks to tenths of the track; see
+        // `flush_pending_keys`.
+        KeyCode::Char(c @ '0'..='9') => app.push_count(c.to_digit(10).unwrap_or(0)),
         _ => {}
     }
 }
+
+fn run_action(app: &mut app::App, action: Action) {
+    // With the folder pane focused, the list keys move in it instead.
+    if app.folders_focused() {
+        match action {
+            Action::Play => return app.browse_open_folder(),
+            Action::Enqueue => {
+                if let Some(dir) = app.target_folder() {
+                    app.enqueue_folder(&dir);
+                }
+                return;
+            }
+            Action::SelectUp => return app.browse_select_at(|i| i.saturating_sub(1)),
+            Action::SelectDown => return app.browse_select_at(|i| i + 1),
+            Action::PageUp => {
+                let page = app.page_size();
+                return app.browse_select_at(|i| i.saturating_sub(page));
+            }
+            Action::PageDown => {
+                let page = app.page_size();
+                return app.browse_select_at(|i| i + page);
+            }
+            Action::SelectFirst => return app.browse_select_at(|_| 0),
+            Action::SelectLast => return app.browse_select_at(|_| usize::MAX),
+            _ => {}
+        }
+    }
+    match action {
+        Action::Quit => app.should_quit = true,
+        Action::Help => app.help_toggle(),
+        Action::PlayPause => app.toggle_pause(),
+        Action::Play if app.songs.is_empty() => app.create_music_dir(),
+        Action::Play => app.activate_selected(),
+        Action::ClosePlaylist if app.browsing() => app.browse_up(),
+        Action::ClosePlaylist => app.close_playlist(),
+        Action::Search => app.search_start(),
+        Action::ClearSearch => app.search_clear(),
+        Action::Next => app.next_track(),
+        Action::Previous => app.prev_track(),
+        Action::Restart => app.restart_track(),
+        Action::SelectUp => app.select_prev(),
+        Action::SelectDown => app.select_next(),
+        Action::PageUp => app.select_page_up(),
+        Action::PageDown => app.select_page_down(),
+        Action::SelectFirst => app.select_first(),
+        Action::SelectLast => app.select_last(),
+        Action::SelectPlaying => app.select_playing(),
+        Action::SeekBackward => app.seek_backward(false),
+        Action::SeekForward => app.seek_forward(false),
+        Action::SeekBackwardLong => app.seek_backward(true),
+        Action::SeekForwardLong => app.seek_forward(true),
+        Action::SeekTo => app.prompt_seek(),
+        Action::VolumeUp => app.volume_up(),
+        Action::VolumeDown => app.volume_down(),
+        Action::VolumeSet => app.prompt_volume(),
+        Action::BalanceLeft => app.balance_left(),
+        Action::BalanceRight => app.balance_right(),
+        Action::SpeedDown => app.speed_down(),
+        Action::SpeedUp => app.speed_up(),
+        Action::ReplayGain => app.toggle_replay_gain(),
+        Action::Mono => app.toggle_mono(),
+        Action::Sort => app.cycle_sort(),
+        Action::RemainingTime => app.toggle_remaining(),
+        Action::PlayCounts => app.toggle_play_counts(),
+        Action::Star => app.toggle_star(),
+        Action::StarredOnly => app.toggle_starred_only(),
+        Action::History => app.toggle_history_only(),
+        Action::VisualizerPalette => app.cycle_palette(),
+        Action::VisualizerStereo => app.toggle_visualizer_stereo(),
+        Action::SensitivityUp => app.sensitivity_up(),
+        Action::SensitivityDown => app.sensitivity_down(),
+        Action::AbLoop => app.cycle_ab_loop(),
+        Action::Bookmark => app.add_bookmark(),
+        Action::ClearBookmarks => app.clear_bookmarks(),
+        Action::NextBookmark => app.next_bookmark(),
+        Action::PrevBookmark => app.prev_bookmark(),
+        Action::Repeat => app.toggle_repeat(),
+        Action::Shuffle => app.toggle_shuffle(),
+        Action::Enqueue => app.enqueue_selected(),
+        Action::ClearQueue => app.clear_queue(),
+        Action::PlayFolder => {
+            if let Some(dir) = app.target_folder() {
+                app.play_folder(&dir);
+            }
+        }
+        Action::SleepTimer => app.prompt_open(PromptKind::SleepTimer),
+        Action::SleepFinishTrack => app.toggle_sleep_finish_track(),
+        Action::EqToggle => app.eq_popup_toggle(),
+        Action::Browse => app.toggle_browse(),
+        Action::BrowseFocus => app.toggle_browse_focus(),
+    }
+}
@@ -0,0 +1,143 @@
+//! Tag metadata (title, artist, album, ReplayGain, cover art) read with lofty.
+//! Untagged or unreadable files simply yield empty tags.
+
+use std::path::Path;
+use std::time::Duration;
+
+use lofty::file::{AudioFile, FileType, TaggedFileExt};
+use lofty::picture::PictureType;
+use lofty::tag::{Accessor, ItemKey};
+use serde::{Deserialize, Serialize};
+
+/// Largest ReplayGain adjustment applied either way, in dB; tagged gains
+/// beyond it are taken as this.
+const MAX_GAIN_DB: f32 = 24.0;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    /// `REPLAYGAIN_TRACK_GAIN` in dB.
+    pub track_gain_db: Option<f32>,
+    /// `REPLAYGAIN_TRACK_PEAK` as a linear sample peak (1.0 = full scale).
+    pub track_peak: Option<f32>,
+}
+
+impl Tags {
+    /// Read the primary tag of `path` (or the first tag present).
+    pub fn read(path: &Path) -> Self {
+        let Ok(tagged) = lofty::read_from_path(path) else {
+            return Self::default();
+        };
+        let Some(tag) = tagged.primary_tag().or_else(|| tagged.first_tag()) else {
+            return Self::default();
+        };
+        let clean = |s: Option<std::borrow::Cow<'_, str>>| {
+            s.map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+        };
+        Self {
+            title: clean(tag.title()),
+            artist: clean(tag.artist()),
+            album: clean(tag.album()),
+            track_gain_db: tag
+                .get_string(&ItemKey::ReplayGainTrackGain)
+                .and_then(parse_gain),
+            track_peak: tag
+                .get_string(&ItemKey::ReplayGainTrackPeak)
+                .and_then(|s| s.trim().parse().ok())
+                .filter(|peak: &f32| peak.is_finite()),
+        }
+    }
+
+    /// Amplitude factor for the tagged track gain, lowered where needed so
+    /// the tagged peak does not clip. `None` without a gain tag.
+    pub fn replay_gain_factor(&self) -> Option<f32> {
+        let factor = 10f32.powf(self.track_gain_db? / 20.0);
+        Some(match self.track_peak {
+            Some(peak) if peak > 0.0 => factor.min(1.0 / peak),
+            _ => factor,
+        })
+    }
+
+    /// "Artist — Title" when both are tagged, the title alone when only it is.
+    pub fn display_name(&self) -> Option<String> {
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => Some(format!("{artist} — {title}")),
+            (None, Some(title)) => Some(title.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a ReplayGain value such as `-6.48 dB`, limited to `MAX_GAIN_DB`.
+/// Infinite and NaN values are ignored.
+fn parse_gain(s: &str) -> Option<f32> {
+    let s = s.trim();
+    let s = s
+        .strip_suffix("dB")
+        .or_else(|| s.strip_suffix("db"))
+        .or_else(|| s.strip_suffix("DB"))
+        .unwrap_or(s);
+    s.trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|gain| gain.is_finite())
+        .map(|gain| gain.clamp(-MAX_GAIN_DB, MAX_GAIN_DB))
+}
+
+/// Short name of the codec (or container) of `path`, e.g. "FLAC" or "MP3".
+pub fn codec_name(path: &Path) -> Option<&'static str> {
+    let tagged = lofty::read_from_path(path).ok()?;
+    Some(match tagged.file_type() {
+        FileType::Aac => "AAC",
+        FileType::Aiff => "AIFF",
+        FileType::Ape => "APE",
+        FileType::Flac => "FLAC",
+        FileType::Mpeg => "MP3",
+        FileType::Mp4 => "MP4",
+        FileType::Mpc => "MPC",
+        FileType::Opus => "Opus",
+        FileType::Vorbis => "Vorbis",
+        FileType::Speex => "Speex",
+        FileType::Wav => "WAV",
+        FileType::WavPack => "WavPack",
+        _ => return None,
+    })
+}
+
+/// Length of `path` from its headers, estimated from the file size and
+/// bitrate when it has no length field (e.g. a VBR MP3 without a Xing
+/// header). `None` when unreadable or zero.
+pub fn duration(path: &Path) -> Option<Duration> {
+    let tagged = lofty::read_from_path(path).ok()?;
+    Some(tagged.properties().duration()).filter(|d| !d.is_zero())
+}
+
+/// Encoded bytes (JPEG, PNG, …) of the embedded cover picture of `path`:
+/// the front cover when tagged as such, else the first picture of any tag.
+pub fn cover_art(path: &Path) -> Option<Vec<u8>> {
+    let tagged = lofty::read_from_path(path).ok()?;
+    let pictures = || tagged.tags().iter().flat_map(|tag| tag.pictures());
+    pictures()
+        .find(|pic| pic.pic_type() == PictureType::CoverFront)
+        .or_else(|| pictures().next())
+        .map(|pic| pic.data().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gains_parse_within_limits() {
+        assert_eq!(parse_gain("-6.48 dB"), Some(-6.48));
+        assert_eq!(parse_gain(" +2.5db "), Some(2.5));
+        assert_eq!(parse_gain("400 dB"), Some(MAX_GAIN_DB));
+        assert_eq!(parse_gain("-1e9 dB"), Some(-MAX_GAIN_DB));
+        for bad in ["inf dB", "-inf", "NaN dB", "loud"] {
+            assert_eq!(parse_gain(bad), None, "{bad}");
+        }
+    }
+}
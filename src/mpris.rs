@@ -0,0 +1,368 @@
+//! MPRIS service on the D-Bus session bus (Linux, `mpris` feature), so media
+//! keys and desktop widgets can control playback and show the current track.
+//!
+//! zbus answers D-Bus calls on its own thread. Method calls are queued as
+//! `Command`s and applied to the `App` on the UI thread by `Mpris::update`,
+//! which also publishes the state the properties report.
+
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use zbus::blocking::{Connection, connection};
+use zbus::interface;
+use zbus::zvariant::{ObjectPath, Value};
+
+use crate::app::App;
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.tui_music_player";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+
+/// A request from the desktop, applied on the next tick.
+enum Command {
+    Quit,
+    PlayPause,
+    Play,
+    Pause,
+    Next,
+    Previous,
+    /// Relative seek in microseconds (may be negative).
+    Seek(i64),
+    /// Absolute position in microseconds within the given track.
+    SetPosition(String, i64),
+}
+
+/// Snapshot of the player state reported over D-Bus.
+#[derive(Clone, Default, PartialEq)]
+struct Status {
+    /// Index of the playing song, used for its `mpris:trackid`.
+    track: Option<usize>,
+    title: String,
+    artist: Option<String>,
+    album: Option<String>,
+    length: Option<Duration>,
+    playing: bool,
+    volume: f64,
+    rate: f64,
+}
+
+/// State shared with the D-Bus thread. The position is kept apart from
+/// `Status` because it changes every tick without a change signal.
+#[derive(Default)]
+struct Shared {
+    status: Status,
+    position: Duration,
+}
+
+fn track_id(track: Option<usize>) -> ObjectPath<'static> {
+    track
+        .and_then(|idx| ObjectPath::try_from(format!("/org/tui_music_player/track/{idx}")).ok())
+        .unwrap_or_else(|| ObjectPath::from_static_str_unchecked(NO_TRACK))
+}
+
+fn micros(d: Duration) -> i64 {
+    i64::try_from(d.as_micros()).unwrap_or(i64::MAX)
+}
+
+// ── org.mpris.MediaPlayer2 ─────────────────────────────────────────────────
+
+struct Root {
+    commands: Sender<Command>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {
+        let _ = self.commands.send(Command::Quit);
+    }
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        "TUI Music Player".to_string()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+// ── org.mpris.MediaPlayer2.Player ──────────────────────────────────────────
+
+struct PlayerInterface {
+    commands: Sender<Command>,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl PlayerInterface {
+    fn status(&self) -> Status {
+        self.shared
+            .lock()
+            .map(|s| s.status.clone())
+            .unwrap_or_default()
+    }
+
+    fn send(&self, command: Command) {
+        let _ = self.commands.send(command);
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl PlayerInterface {
+    fn next(&self) {
+        self.send(Command::Next);
+    }
+
+    fn previous(&self) {
+        self.send(Command::Previous);
+    }
+
+    fn pause(&self) {
+        self.send(Command::Pause);
+    }
+
+    fn play_pause(&self) {
+        self.send(Command::PlayPause);
+    }
+
+    /// There is no stopped state; stopping pauses.
+    fn stop(&self) {
+        self.send(Command::Pause);
+    }
+
+    fn play(&self) {
+        self.send(Command::Play);
+    }
+
+    fn seek(&self, offset: i64) {
+        self.send(Command::Seek(offset));
+    }
+
+    fn set_position(&self, track_id: ObjectPath<'_>, position: i64) {
+        self.send(Command::SetPosition(track_id.as_str().to_string(), position));
+    }
+
+    /// Opening URIs is not supported (`SupportedUriSchemes` is empty).
+    fn open_uri(&self, _uri: String) {}
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        let status = self.status();
+        match (status.track, status.playing) {
+            (None, _) => "Stopped",
+            (Some(_), true) => "Playing",
+            (Some(_), false) => "Paused",
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, Value<'static>> {
+        let status = self.status();
+        let mut map = HashMap::new();
+        map.insert("mpris:trackid".to_string(), Value::from(track_id(status.track)));
+        if status.track.is_none() {
+            return map;
+        }
+        map.insert("xesam:title".to_string(), Value::from(status.title));
+        if let Some(artist) = status.artist {
+            map.insert("xesam:artist".to_string(), Value::from(vec![artist]));
+        }
+        if let Some(album) = status.album {
+            map.insert("xesam:album".to_string(), Value::from(album));
+        }
+        if let Some(length) = status.length {
+            map.insert("mpris:length".to_string(), Value::from(micros(length)));
+        }
+        map
+    }
+
+    #[zbus(property)]
+    fn position(&self) -> i64 {
+        self.shared.lock().map(|s| micros(s.position)).unwrap_or(0)
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.status().volume
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        self.status().rate
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        0.5
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        2.0
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+// ── Service handle ─────────────────────────────────────────────────────────
+
+/// Registered MPRIS service; dropping it leaves the bus.
+pub struct Mpris {
+    connection: Connection,
+    commands: Receiver<Command>,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Mpris {
+    /// Claim the bus name and serve both interfaces. Fails when there is no
+    /// session bus (e.g. over SSH without D-Bus).
+    pub fn start() -> Result<Self> {
+        let (tx, commands) = mpsc::channel();
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let connection = connection::Builder::session()?
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, Root { commands: tx.clone() })?
+            .serve_at(
+                OBJECT_PATH,
+                PlayerInterface {
+                    commands: tx,
+                    shared: Arc::clone(&shared),
+                },
+            )?
+            .build()?;
+        Ok(Self {
+            connection,
+            commands,
+            shared,
+        })
+    }
+
+    /// Apply the commands received since the last call, then publish the
+    /// app's state, signalling the properties that changed. Called every tick.
+    pub fn update(&self, app: &mut App) {
+        while let Ok(command) = self.commands.try_recv() {
+            apply(app, command);
+        }
+
+        let song = app.now_playing.map(|idx| &app.songs[idx]);
+        let status = Status {
+            track: app.now_playing,
+            title: song
+                .and_then(|s| s.tags.title.clone())
+                .unwrap_or_else(|| app.now_playing_name().to_string()),
+            artist: song.and_then(|s| s.tags.artist.clone()),
+            album: app.now_playing_album().map(str::to_string),
+            length: app.current_duration(),
+            playing: app.is_playing(),
+            volume: f64::from(app.volume_percent()) / 100.0,
+            rate: f64::from(app.speed()),
+        };
+
+        let changed = match self.shared.lock() {
+            Ok(mut shared) => {
+                shared.position = app.current_position();
+                let changed = shared.status != status;
+                shared.status = status;
+                changed
+            }
+            Err(_) => false,
+        };
+        if changed {
+            let _ = self.notify();
+        }
+    }
+
+    /// Emit `PropertiesChanged` for the properties derived from `Status`.
+    fn notify(&self) -> zbus::Result<()> {
+        let iface = self
+            .connection
+            .object_server()
+            .interface::<_, PlayerInterface>(OBJECT_PATH)?;
+        let ctxt = iface.signal_context();
+        let player = iface.get();
+        zbus::block_on(async {
+            player.playback_status_changed(ctxt).await?;
+            player.metadata_changed(ctxt).await?;
+            player.volume_changed(ctxt).await?;
+            player.rate_changed(ctxt).await
+        })
+    }
+}
+
+fn apply(app: &mut App, command: Command) {
+    match command {
+        Command::Quit => app.should_quit = true,
+        Command::PlayPause if app.now_playing.is_none() => app.play_selected(),
+        Command::PlayPause => app.toggle_pause(),
+        Command::Play if app.now_playing.is_none() => app.play_selected(),
+        Command::Play if !app.is_playing() => app.toggle_pause(),
+        Command::Pause if app.is_playing() => app.toggle_pause(),
+        Command::Play | Command::Pause => {}
+        Command::Next => app.next_track(),
+        Command::Previous => app.prev_track(),
+        Command::Seek(offset) => {
+            let pos = micros(app.current_position()).saturating_add(offset).max(0);
+            app.seek_to(Duration::from_micros(pos as u64));
+        }
+        Command::SetPosition(track, pos) => {
+            // Ignored when the track has changed since the client read it.
+            if track == track_id(app.now_playing).as_str() && pos >= 0 {
+                app.seek_to(Duration::from_micros(pos as u64));
+            }
+        }
+    }
+}
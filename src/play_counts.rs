@@ -0,0 +1,40 @@
+//! How many times each track was played to the end, kept as JSON next to
+//! the session in the user's config directory and keyed by canonical path.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::session::{PathStore, track_key};
+
+const PLAY_COUNTS_FILE: &str = "play_counts.json";
+
+pub struct PlayCounts {
+    store: PathStore<HashMap<PathBuf, u32>>,
+}
+
+impl PlayCounts {
+    /// Load saved counts. Missing or unreadable files yield no counts.
+    pub fn load() -> Self {
+        Self {
+            store: PathStore::load(PLAY_COUNTS_FILE),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.store.save()
+    }
+
+    /// Full plays of the track whose `track_key` is `key`.
+    pub fn get(&self, key: &Path) -> u32 {
+        self.store.entries.get(key).copied().unwrap_or(0)
+    }
+
+    /// Count one more full play of `track` and return the new total.
+    pub fn increment(&mut self, track: &Path) -> u32 {
+        let count = self.store.entries.entry(track_key(track)).or_default();
+        *count += 1;
+        *count
+    }
+}
@@ -1,14 +1,88 @@
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
-use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use anyhow::{Context, Result, anyhow, bail};
+use rodio::cpal::traits::HostTrait;
+use rodio::cpal::{Device, SampleRate};
+use rodio::source::SeekError;
+use rodio::{DeviceTrait, Decoder, OutputStream, OutputStreamHandle, Sink, Source};
 
-use crate::eq::{EqGains, EqSource};
-use crate::visualizer::{SpectrumAnalyzer, VisualizerSource};
+use crate::decoder::TrackDecoder;
+use crate::eq::{EqConfig, EqGains, EqSource};
+use crate::metadata::{self, Tags};
+use crate::stream::is_stream;
+use crate::visualizer::{SpectrumAnalyzer, VisualizerConfig, VisualizerSource};
+
+/// How often a playing source picks up a changed pre-amp gain.
+const PREAMP_UPDATE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// File extensions of the formats the decoder can play. rodio's default
+/// decoders have no AAC support, so `.m4a`/`.aac` need Symphonia.
+#[cfg(not(feature = "symphonia"))]
+pub const DECODABLE_EXTENSIONS: &[&str] = &["mp3", "wav", "wave", "ogg", "oga", "flac"];
+#[cfg(feature = "symphonia")]
+pub const DECODABLE_EXTENSIONS: &[&str] =
+    &["mp3", "wav", "wave", "ogg", "oga", "flac", "m4a", "aac"];
+
+/// Audio output as `App` drives it. `Player` plays through the sound card;
+/// tests use a mock so `App` can run without audio hardware.
+pub trait Playback {
+    /// Start playing `path` from the beginning. The file may still be
+    /// loading when this returns; `poll_load` reports when it is ready.
+    fn play_file(&mut self, path: &Path) -> Result<()>;
+    /// True while the file passed to `play_file` is being opened.
+    fn is_loading(&self) -> bool;
+    /// The outcome of the load started by `play_file`, once it has finished.
+    fn poll_load(&mut self) -> Option<Result<()>>;
+    /// Load `path` at `start`, paused.
+    fn load_file_from(&mut self, path: &Path, start: Duration) -> Result<()>;
+    /// Queue `path` to follow the current track without a gap.
+    fn preload(&mut self, path: &Path) -> Result<()>;
+    /// True once playback has moved on to the preloaded track.
+    fn take_advanced(&mut self) -> bool;
+    /// Forget the preloaded track, keeping the current one playing.
+    fn cancel_preload(&mut self) -> Result<()>;
+    fn seek(&mut self, pos: Duration) -> Result<()>;
+    fn speed(&self) -> f32;
+    fn set_speed(&mut self, speed: f32) -> Result<()>;
+    fn replay_gain(&self) -> bool;
+    fn set_replay_gain(&mut self, on: bool) -> Result<()>;
+    fn mono(&self) -> bool;
+    fn set_mono(&mut self, on: bool) -> Result<()>;
+    fn eq_gains(&self) -> &EqGains;
+    fn toggle_pause(&self);
+    fn pause(&self);
+    fn is_paused(&self) -> bool;
+    /// Position in the current track.
+    fn position(&self) -> Duration;
+    fn set_volume(&self, vol: f32);
+    /// Left/right balance from -1.0 (left only) to 1.0 (right only); takes
+    /// effect on the playing track right away.
+    fn set_balance(&self, balance: f32);
+    /// True when nothing is loaded or the current track has played out.
+    fn is_empty(&self) -> bool;
+    /// Format of the current track, once it has loaded.
+    fn format(&self) -> Option<Format>;
+    /// Latest bars, left and right; outside stereo mode the left is the
+    /// mono mix.
+    fn spectrum_stereo(&self) -> (Vec<u64>, Vec<u64>);
+    fn spectrum_peaks_stereo(&self) -> (Vec<u64>, Vec<u64>);
+    fn spectrum_frequencies(&self) -> Vec<f32>;
+    /// When the analyser last published bars, to tell new ones apart.
+    fn spectrum_updated(&self) -> Option<Instant>;
+    fn visualizer_sensitivity(&self) -> f32;
+    fn set_visualizer_sensitivity(&self, sensitivity: f32);
+    fn visualizer_stereo(&self) -> bool;
+    fn set_visualizer_stereo(&self, stereo: bool);
+    /// Stop (or resume) the spectrum analysis while the visualizer is not
+    /// on screen.
+    fn set_visualizer_hidden(&self, hidden: bool);
+}
 
 pub struct Player {
     _stream: OutputStream,
@@ -18,15 +92,61 @@ pub struct Player {
     eq_gains: Arc<EqGains>,
     /// Start offset when playback was started with play_file_from (so position display is correct).
     playback_start: Duration,
+    /// File currently loaded into the sink, re-opened when a seek needs a restart.
+    current: Option<PathBuf>,
+    /// Playback rate applied with `Source::speed` (1.0 = normal; also shifts pitch).
+    speed: f32,
+    /// Scale each track by its ReplayGain track gain (`Source::amplify`).
+    replay_gain: bool,
+    /// Average the channels into one before the output.
+    mono: bool,
+    /// Balance as `f32` bits, shared with the playing sources.
+    balance: Arc<AtomicU32>,
+    /// Track appended to the sink behind `current` for gapless playback, with
+    /// its format.
+    queued: Option<(PathBuf, Format)>,
+    /// `current` being opened on a loader thread by `play_file`.
+    loading: Option<Receiver<Result<TrackDecoder>>>,
+    /// Format of the track playing now; `None` while it is loading.
+    format: Option<Format>,
+    /// Sample rate the output stream is opened at; `None` for the device's
+    /// default.
+    output_rate: Option<u32>,
+}
+
+/// Channel count and sample rate of a decoded file, as seen by the analyzer
+/// (before the speed change), and its codec when the file's headers name it.
+#[derive(Clone, Copy)]
+pub struct Format {
+    pub channels: u16,
+    /// The file's channel count when it is downmixed to mono for the output.
+    pub downmixed_from: Option<u16>,
+    pub sample_rate: u32,
+    pub codec: Option<&'static str>,
+    /// The fallback decoder reading the file when rodio's could not.
+    pub decoder: Option<&'static str>,
 }
 
 impl Player {
-    pub fn new() -> Result<Self> {
-        let (stream, handle) = OutputStream::try_default()?;
+    /// Open the default output device, at `output_rate` Hz when set.
+    pub fn new(
+        visualizer: VisualizerConfig,
+        equalizer: &EqConfig,
+        output_rate: Option<u32>,
+    ) -> Result<Self> {
+        let (stream, handle) = match output_rate {
+            None => OutputStream::try_default()?,
+            Some(_) => {
+                let device = rodio::cpal::default_host()
+                    .default_output_device()
+                    .context("no output device")?;
+                open_stream(&device, output_rate)?
+            }
+        };
         let sink = Sink::try_new(&handle)?;
         sink.pause();
-        let analyzer = SpectrumAnalyzer::new();
-        let eq_gains = Arc::new(EqGains::new());
+        let analyzer = SpectrumAnalyzer::new(visualizer);
+        let eq_gains = Arc::new(EqGains::new(&equalizer.bands));
         Ok(Self {
             _stream: stream,
             handle,
@@ -34,79 +154,622 @@ impl Player {
             analyzer,
             eq_gains,
             playback_start: Duration::ZERO,
+            current: None,
+            speed: 1.0,
+            replay_gain: false,
+            mono: false,
+            balance: Arc::new(AtomicU32::new(0f32.to_bits())),
+            queued: None,
+            loading: None,
+            format: None,
+            output_rate,
         })
     }
 
-    pub fn play_file(&mut self, path: &Path) -> Result<()> {
-        self.play_file_from(path, Duration::ZERO)
+    /// Names of the available output devices.
+    pub fn list_devices() -> Result<Vec<String>> {
+        let devices = rodio::cpal::default_host().output_devices()?;
+        Ok(devices.filter_map(|d| d.name().ok()).collect())
     }
 
-    /// Start playback from a given position (e.g. after seek). Uses skip_duration
-    /// so seeking works even when Sink::try_seek is not applied to the source chain.
-    pub fn play_file_from(&mut self, path: &Path, start: Duration) -> Result<()> {
+    /// Move output to the device called `name`: rebuilds the stream, handle
+    /// and sink, and re-opens the current track at its position so playback
+    /// carries on (or stays paused) on the new device.
+    pub fn switch_device(&mut self, name: &str) -> Result<()> {
+        let device = rodio::cpal::default_host()
+            .output_devices()?
+            .find(|d| d.name().is_ok_and(|n| n == name))
+            .with_context(|| format!("no output device named {name:?}"))?;
+        let (stream, handle) = open_stream(&device, self.output_rate)?;
+
+        let position = self.position();
+        let paused = self.sink.is_paused();
+        let loaded = !self.sink.empty();
+        let volume = self.sink.volume();
         self.sink.stop();
+        self._stream = stream;
+        self.handle = handle;
         self.sink = Sink::try_new(&self.handle)?;
-        self.analyzer.clear();
+        self.pause_sink();
+        self.sink.set_volume(volume);
+
+        if loaded && let Some(path) = self.current.clone() {
+            self.load_file_from(&path, position)?;
+            if !paused {
+                self.play_sink();
+            }
+        }
+        Ok(())
+    }
+
+    /// Empty the sink for a new track at `start`, keeping the volume. The
+    /// sink is left paused.
+    fn reset_sink(&mut self, path: &Path, start: Duration) -> Result<()> {
+        let volume = self.sink.volume();
+        self.sink.stop();
+        self.sink = Sink::try_new(&self.handle)?;
+        self.pause_sink();
+        self.sink.set_volume(volume);
+        // Re-opening the same track (seek fallback, speed change) keeps the
+        // spectrum; only a different track starts from a blank one.
+        if self.current.as_deref() != Some(path) {
+            self.analyzer.clear();
+        }
         self.playback_start = start;
+        self.current = Some(path.to_path_buf());
+        self.queued = None;
+        // A load still running for another track is abandoned.
+        self.loading = None;
+        self.format = None;
+        Ok(())
+    }
 
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let source = Decoder::new(reader)?;
+    /// Decode `path` from `start` through the mono downmix, ReplayGain and
+    /// pre-amp, EQ, visualizer, balance and speed chain. Returns the source and its format.
+    fn open_source(
+        &self,
+        path: &Path,
+        start: Duration,
+    ) -> Result<(impl Source<Item = f32> + Send + 'static, Format)> {
+        Ok(self.build_source(TrackDecoder::open(path)?, path, start))
+    }
 
+    /// The chain of `open_source` around an already opened decoder.
+    fn build_source(
+        &self,
+        source: TrackDecoder,
+        path: &Path,
+        start: Duration,
+    ) -> (impl Source<Item = f32> + Send + 'static, Format) {
         let channels = source.channels();
-        self.analyzer.set_channels(channels);
+        let downmix = self.mono && channels > 1;
+        let format = Format {
+            channels: if downmix { 1 } else { channels },
+            downmixed_from: downmix.then_some(channels),
+            sample_rate: source.sample_rate(),
+            codec: metadata::codec_name(path),
+            decoder: source.fallback_name(),
+        };
+
+        // Files without a gain tag play unchanged.
+        let gain = if self.replay_gain {
+            Tags::read(path).replay_gain_factor().unwrap_or(1.0)
+        } else {
+            1.0
+        };
 
         let source = source.skip_duration(start);
-        let converted = source.convert_samples::<f32>();
+        // The pre-amp follows changes from the EQ popup while playing.
+        let gains = Arc::clone(&self.eq_gains);
+        let converted = Downmix::new(source.convert_samples::<f32>(), downmix)
+            .amplify(gain * gains.preamp_factor())
+            .periodic_access(PREAMP_UPDATE_INTERVAL, move |amplified| {
+                amplified.set_factor(gain * gains.preamp_factor());
+            });
         let eq_source = EqSource::new(converted, Arc::clone(&self.eq_gains));
-        let visualized = VisualizerSource::new(eq_source, self.analyzer.buffer());
+        let visualized = VisualizerSource::new(
+            eq_source,
+            self.analyzer.buffer(),
+            self.analyzer.buffer_capacity(),
+        );
+        let balanced = Balance::new(visualized, Arc::clone(&self.balance));
+        (balanced.speed(self.speed), format)
+    }
+
+    /// Make `format` the playing track's, for the analyser and `format()`.
+    fn set_format(&mut self, format: Format) {
+        self.analyzer.set_channels(format.channels);
+        self.analyzer.set_sample_rate(format.sample_rate);
+        self.format = Some(format);
+    }
 
-        self.sink.append(visualized);
+    /// Start the sink; the analyser picks up the samples again.
+    fn play_sink(&self) {
         self.sink.play();
+        self.analyzer.set_paused(false);
+    }
+
+    /// Pause the sink and let the spectrum fall instead of freezing.
+    fn pause_sink(&self) {
+        self.sink.pause();
+        self.analyzer.set_paused(true);
+    }
+
+    /// Re-open the current track at `pos`, keeping the pause state.
+    /// Streams are read only once, so they cannot be re-opened.
+    fn reload_at(&mut self, pos: Duration) -> Result<()> {
+        let path = self.current.clone().context("no track loaded")?;
+        if is_stream(&path) {
+            bail!("a stream cannot be re-opened");
+        }
+        let paused = self.sink.is_paused();
+        self.load_file_from(&path, pos)?;
+        if !paused {
+            self.play_sink();
+        }
+        Ok(())
+    }
+
+    /// Length of `path` as the decoder reports it or, for files it cannot
+    /// tell (e.g. MP3s without a length header), as estimated from the
+    /// headers. Files rodio cannot open are measured by the fallback decoder.
+    pub fn get_duration(path: &Path) -> Option<Duration> {
+        let file = File::open(path).ok()?;
+        let reader = BufReader::new(file);
+        let Ok(source) = Decoder::new(reader) else {
+            #[cfg(feature = "symphonia")]
+            return crate::decoder::fallback_duration(path).or_else(|| metadata::duration(path));
+            #[cfg(not(feature = "symphonia"))]
+            return None;
+        };
+        source.total_duration().or_else(|| metadata::duration(path))
+    }
+}
+
+impl Playback for Player {
+    /// Opening and probing a file can take a while (a large FLAC on a network
+    /// mount), so it happens on a loader thread; `poll_load` appends the
+    /// source once it is ready. Playing another file before then abandons
+    /// this one.
+    fn play_file(&mut self, path: &Path) -> Result<()> {
+        self.reset_sink(path, Duration::ZERO)?;
+        self.play_sink();
+        let (tx, rx) = mpsc::channel();
+        let file = path.to_path_buf();
+        std::thread::spawn(move || {
+            // The receiver is gone if the load was abandoned.
+            let _ = tx.send(TrackDecoder::open(&file));
+        });
+        self.loading = Some(rx);
+        Ok(())
+    }
+
+    fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    fn poll_load(&mut self) -> Option<Result<()>> {
+        let decoder = match self.loading.as_ref()?.try_recv() {
+            Ok(decoder) => decoder,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(anyhow!("loader thread stopped")),
+        };
+        self.loading = None;
+        let result = decoder.map(|decoder| {
+            let path = self.current.clone().unwrap_or_default();
+            let (source, format) = self.build_source(decoder, &path, self.playback_start);
+            self.set_format(format);
+            self.sink.append(source);
+        });
+        Some(result)
+    }
+
+    /// Like `play_file`, but from `start`, opening the file right away, and
+    /// leaving the sink paused so nothing is heard until `toggle_pause` (used
+    /// when restoring a saved session and when re-opening the current track).
+    fn load_file_from(&mut self, path: &Path, start: Duration) -> Result<()> {
+        self.reset_sink(path, start)?;
+        let (source, format) = self.open_source(path, start)?;
+        self.set_format(format);
+        self.sink.append(source);
+        Ok(())
+    }
+
+    /// Append `path` behind the current track so the sink moves on to it
+    /// with no gap. `take_advanced` reports when that happens.
+    fn preload(&mut self, path: &Path) -> Result<()> {
+        let (source, format) = self.open_source(path, Duration::ZERO)?;
+        self.sink.append(source);
+        self.queued = Some((path.to_path_buf(), format));
+        Ok(())
+    }
+
+    /// True once the sink has finished the current track and started the
+    /// preloaded one; position and `current` then refer to the new track.
+    fn take_advanced(&mut self) -> bool {
+        if self.sink.len() > 1 {
+            return false;
+        }
+        let Some((path, format)) = self.queued.take() else {
+            return false;
+        };
+        self.current = Some(path);
+        self.playback_start = Duration::ZERO;
+        self.set_format(format);
+        true
+    }
+
+    /// Drop a preloaded track by re-opening the current one at its position
+    /// (rodio cannot remove a queued source from the sink).
+    fn cancel_preload(&mut self) -> Result<()> {
+        if self.queued.is_none() {
+            return Ok(());
+        }
+        self.reload_at(self.position())
+    }
+
+    /// Seek within the current track. Uses the sink's in-place `try_seek`,
+    /// which keeps the decoder, EQ and visualizer chain; only sources that
+    /// cannot seek (e.g. FLAC) are re-opened from `pos` via `load_file_from`.
+    fn seek(&mut self, pos: Duration) -> Result<()> {
+        if self.current.as_deref().is_some_and(is_stream) {
+            bail!("cannot seek in a stream");
+        }
+        // Still loading: start from `pos` once the file is open.
+        if self.loading.is_some() {
+            self.playback_start = pos;
+            return Ok(());
+        }
+        match self.sink.try_seek(sink_time(pos, self.speed)) {
+            Ok(()) => {
+                // The sink's position now counts from the start of the file.
+                self.playback_start = Duration::ZERO;
+                Ok(())
+            }
+            Err(SeekError::NotSupported { .. }) => self.reload_at(pos),
+            Err(e) => Err(anyhow!("seek failed: {e}")),
+        }
+    }
+
+    fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Change the playback rate (clamped to 0.5–2.0). The speed is part of the
+    /// source chain, so a loaded track is re-opened at the current position.
+    fn set_speed(&mut self, speed: f32) -> Result<()> {
+        let speed = speed.clamp(0.5, 2.0);
+        if (speed - self.speed).abs() < f32::EPSILON {
+            return Ok(());
+        }
+        // The position is read at the old speed, before the change.
+        let position = self.position();
+        self.speed = speed;
+        if self.current.is_some() && !self.sink.empty() {
+            self.reload_at(position)?;
+        }
+        Ok(())
+    }
+
+    fn replay_gain(&self) -> bool {
+        self.replay_gain
+    }
+
+    /// Turn ReplayGain normalisation on or off. Like the speed it is part of
+    /// the source chain, so a loaded track is re-opened at the current position.
+    fn set_replay_gain(&mut self, on: bool) -> Result<()> {
+        if on == self.replay_gain {
+            return Ok(());
+        }
+        self.replay_gain = on;
+        if self.current.is_some() && !self.sink.empty() {
+            self.reload_at(self.position())?;
+        }
+        Ok(())
+    }
+
+    fn mono(&self) -> bool {
+        self.mono
+    }
+
+    /// Turn the mono downmix on or off, re-opening a loaded track at the
+    /// current position like `set_replay_gain`.
+    fn set_mono(&mut self, on: bool) -> Result<()> {
+        if on == self.mono {
+            return Ok(());
+        }
+        self.mono = on;
+        if self.current.is_some() && !self.sink.empty() {
+            self.reload_at(self.position())?;
+        }
         Ok(())
     }
 
-    pub fn eq_gains(&self) -> &EqGains {
+    fn eq_gains(&self) -> &EqGains {
         &self.eq_gains
     }
 
-    pub fn toggle_pause(&self) {
+    fn toggle_pause(&self) {
         if self.sink.is_paused() {
-            self.sink.play();
+            self.play_sink();
         } else {
-            self.sink.pause();
+            self.pause_sink();
         }
     }
 
-    pub fn is_paused(&self) -> bool {
+    fn pause(&self) {
+        self.pause_sink();
+    }
+
+    fn is_paused(&self) -> bool {
         self.sink.is_paused()
     }
 
-    pub fn position(&self) -> Duration {
-        self.playback_start + self.sink.get_pos()
+    fn position(&self) -> Duration {
+        self.playback_start + media_time(self.sink.get_pos(), self.speed)
     }
 
-    pub fn volume(&self) -> f32 {
-        self.sink.volume()
+    /// Set the sink volume (1.0 = unity gain). The upper limit is the
+    /// caller's; see `App::volume_up`.
+    fn set_volume(&self, vol: f32) {
+        self.sink.set_volume(vol.max(0.0));
     }
 
-    pub fn set_volume(&self, vol: f32) {
-        self.sink.set_volume(vol.clamp(0.0, 1.5));
+    fn set_balance(&self, balance: f32) {
+        self.balance
+            .store(balance.clamp(-1.0, 1.0).to_bits(), Ordering::Relaxed);
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.sink.empty()
+    fn is_empty(&self) -> bool {
+        self.loading.is_none() && self.sink.empty()
     }
 
+    fn format(&self) -> Option<Format> {
+        self.format
+    }
 
-    pub fn spectrum(&self) -> Vec<u64> {
-        self.analyzer.spectrum()
+    fn spectrum_stereo(&self) -> (Vec<u64>, Vec<u64>) {
+        self.analyzer.spectrum_stereo()
     }
 
-    pub fn get_duration(path: &Path) -> Option<Duration> {
-        let file = File::open(path).ok()?;
-        let reader = BufReader::new(file);
-        let source = Decoder::new(reader).ok()?;
-        source.total_duration()
+    fn spectrum_peaks_stereo(&self) -> (Vec<u64>, Vec<u64>) {
+        self.analyzer.peaks_stereo()
+    }
+
+    fn spectrum_frequencies(&self) -> Vec<f32> {
+        self.analyzer.bar_frequencies()
+    }
+
+    fn spectrum_updated(&self) -> Option<Instant> {
+        self.analyzer.updated()
+    }
+
+    fn visualizer_sensitivity(&self) -> f32 {
+        self.analyzer.sensitivity()
+    }
+
+    fn set_visualizer_sensitivity(&self, sensitivity: f32) {
+        self.analyzer.set_sensitivity(sensitivity);
+    }
+
+    fn visualizer_stereo(&self) -> bool {
+        self.analyzer.is_stereo()
+    }
+
+    fn set_visualizer_stereo(&self, stereo: bool) {
+        self.analyzer.set_stereo(stereo);
+    }
+
+    fn set_visualizer_hidden(&self, hidden: bool) {
+        self.analyzer.set_hidden(hidden);
+    }
+}
+
+/// Open an output stream on `device` at `rate` Hz, or at its default rate
+/// when `None`. The mixer resamples every source to the stream's rate.
+fn open_stream(device: &Device, rate: Option<u32>) -> Result<(OutputStream, OutputStreamHandle)> {
+    let Some(rate) = rate else {
+        return Ok(OutputStream::try_from_device(device)?);
+    };
+    // Keep the default channel count, and its sample format where possible.
+    let default = device.default_output_config()?;
+    let config = device
+        .supported_output_configs()?
+        .filter(|range| {
+            range.channels() == default.channels()
+                && (range.min_sample_rate().0..=range.max_sample_rate().0).contains(&rate)
+        })
+        .max_by_key(|range| range.sample_format() == default.sample_format())
+        .with_context(|| format!("the output device does not support {rate} Hz"))?
+        .with_sample_rate(SampleRate(rate));
+    Ok(OutputStream::try_from_device_config(device, config)?)
+}
+
+/// Time into the track for `sink_pos`, the sink's position. The speed stage
+/// sits inside the source the sink counts, so the sink's clock runs at
+/// wall-clock pace: at 2× it shows half the media time played.
+fn media_time(sink_pos: Duration, speed: f32) -> Duration {
+    sink_pos.mul_f32(speed)
+}
+
+/// The sink position to seek to for `pos` into the track; `Speed` scales
+/// the sink's target back up by the speed.
+fn sink_time(pos: Duration, speed: f32) -> Duration {
+    pos.div_f32(speed)
+}
+
+/// Averages each frame of `inner` into a single sample, so every speaker
+/// plays the same mix; passes the samples through unchanged when off. The
+/// mixer spreads the mono signal over the output's channels again.
+struct Downmix<S> {
+    inner: S,
+    enabled: bool,
+}
+
+impl<S: Source<Item = f32>> Downmix<S> {
+    fn new(inner: S, enabled: bool) -> Self {
+        Self { inner, enabled }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Downmix<S> {
+    type Item = f32;
+    fn next(&mut self) -> Option<f32> {
+        if !self.enabled {
+            return self.inner.next();
+        }
+        // Read at a frame boundary, so it holds for the whole frame.
+        let channels = self.inner.channels().max(1);
+        let mut sum = 0.0;
+        for _ in 0..channels {
+            sum += self.inner.next()?;
+        }
+        Some(sum / f32::from(channels))
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Downmix<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        let len = self.inner.current_frame_len()?;
+        Some(if self.enabled { len / usize::from(self.inner.channels().max(1)) } else { len })
+    }
+    fn channels(&self) -> u16 {
+        if self.enabled { 1 } else { self.inner.channels() }
+    }
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.inner.try_seek(pos)
+    }
+}
+
+/// Scales the left and right channels by the shared balance: moving it to
+/// one side turns the other down, the centre plays both unchanged. Mono
+/// tracks are spread over two channels here so they can be balanced too;
+/// channels beyond the first two pass unchanged.
+struct Balance<S> {
+    inner: S,
+    balance: Arc<AtomicU32>,
+    /// Gains of the frame being played, read at its first sample.
+    gains: (f32, f32),
+    /// Position within the inner source's frame.
+    channel: u16,
+    /// Right channel of a mono sample whose left was just returned.
+    pending: Option<f32>,
+}
+
+impl<S: Source<Item = f32>> Balance<S> {
+    fn new(inner: S, balance: Arc<AtomicU32>) -> Self {
+        Self {
+            inner,
+            balance,
+            gains: (1.0, 1.0),
+            channel: 0,
+            pending: None,
+        }
+    }
+
+    fn load_gains(&mut self) {
+        let balance = f32::from_bits(self.balance.load(Ordering::Relaxed));
+        self.gains = ((1.0 - balance).min(1.0), (1.0 + balance).min(1.0));
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for Balance<S> {
+    type Item = f32;
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.pending.take() {
+            return Some(right);
+        }
+        let channels = self.inner.channels().max(1);
+        if self.channel == 0 {
+            self.load_gains();
+        }
+        let s = self.inner.next()?;
+        if channels == 1 {
+            self.pending = Some(s * self.gains.1);
+            return Some(s * self.gains.0);
+        }
+        let gain = match self.channel {
+            0 => self.gains.0,
+            1 => self.gains.1,
+            _ => 1.0,
+        };
+        self.channel = (self.channel + 1) % channels;
+        Some(s * gain)
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Balance<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        let len = self.inner.current_frame_len()?;
+        Some(if self.inner.channels() == 1 {
+            len * 2 + usize::from(self.pending.is_some())
+        } else {
+            len
+        })
+    }
+    fn channels(&self) -> u16 {
+        self.inner.channels().max(2)
+    }
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.channel = 0;
+        self.pending = None;
+        self.inner.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rodio::buffer::SamplesBuffer;
+
+    use super::*;
+
+    fn balanced(channels: u16, samples: Vec<f32>, balance: f32) -> Vec<f32> {
+        let shared = Arc::new(AtomicU32::new(balance.to_bits()));
+        Balance::new(SamplesBuffer::new(channels, 44_100, samples), shared).collect()
+    }
+
+    #[test]
+    fn balance_turns_the_other_side_down() {
+        assert_eq!(balanced(2, vec![1.0, 1.0, 0.5, 0.5], 0.0), [1.0, 1.0, 0.5, 0.5]);
+        assert_eq!(balanced(2, vec![1.0, 1.0, 0.5, 0.5], -0.25), [1.0, 0.75, 0.5, 0.375]);
+        // Mono is spread over two channels first.
+        assert_eq!(balanced(1, vec![1.0, 0.5], 1.0), [0.0, 1.0, 0.0, 0.5]);
+    }
+
+    /// Two seconds of mono audio at 1 kHz whose samples count up, so a
+    /// sample tells where in the track it is.
+    fn counting_source() -> SamplesBuffer<f32> {
+        SamplesBuffer::new(1, 1_000, (0..2_000).map(|i| i as f32).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn position_counts_media_time_at_double_speed() {
+        // A second of the track plays in half a second at 2×; the sink's
+        // clock counts the samples at the rate `Speed` reports.
+        let mut sped = counting_source().speed(2.0);
+        let played = sped.by_ref().take(1_000).count();
+        let sink_pos = Duration::from_secs_f64(played as f64 / f64::from(sped.sample_rate()));
+        assert_eq!(sink_pos, Duration::from_millis(500));
+        assert_eq!(media_time(sink_pos, 2.0), Duration::from_secs(1));
+        assert_eq!(media_time(sink_pos, 1.0), sink_pos);
+    }
+
+    #[test]
+    fn seek_lands_on_media_time_at_double_speed() {
+        let mut sped = counting_source().speed(2.0);
+        sped.try_seek(sink_time(Duration::from_millis(1_500), 2.0)).unwrap();
+        assert_eq!(sped.next(), Some(1_500.0));
     }
 }
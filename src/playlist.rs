@@ -0,0 +1,101 @@
+//! M3U / M3U8 playlist parsing.
+//! `#EXTINF` hints supply the duration and title of the entry that follows.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::app::{Song, stem_name};
+
+/// Playlist file extensions (matched case-insensitively).
+pub const PLAYLIST_EXTENSIONS: [&str; 2] = ["m3u", "m3u8"];
+
+pub fn is_playlist(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| PLAYLIST_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Load the entries of an M3U/M3U8 playlist. Relative paths are resolved
+/// against the playlist's directory; entries whose file is missing are skipped.
+pub fn load_m3u(path: &Path) -> Vec<Song> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    let mut songs = Vec::new();
+    let mut hint: Option<(Option<Duration>, Option<String>)> = None;
+
+    for line in contents.lines() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            hint = Some(parse_extinf(info));
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (hint_duration, hint_title) = hint.take().unwrap_or_default();
+        let entry = PathBuf::from(line);
+        let entry = if entry.is_absolute() {
+            entry
+        } else {
+            base.join(entry)
+        };
+        if !entry.is_file() {
+            continue;
+        }
+
+        // Entries without an #EXTINF duration are measured in the background.
+        let stem = stem_name(&entry);
+        let mut song = Song::new(entry, stem, hint_duration);
+        if let Some(title) = hint_title {
+            song.name = title;
+        }
+        songs.push(song);
+    }
+
+    songs
+}
+
+/// Parse the `123,Artist - Title` part of an `#EXTINF:` line. A duration of
+/// `-1` (or anything non-positive, or too long for a `Duration`) means
+/// unknown.
+fn parse_extinf(info: &str) -> (Option<Duration>, Option<String>) {
+    let (secs, title) = info.split_once(',').unwrap_or((info, ""));
+    // Extended players put `key="value"` attributes after the duration.
+    let duration = secs
+        .split_whitespace()
+        .next()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|&s| s > 0.0)
+        .and_then(|s| Duration::try_from_secs_f64(s).ok());
+    let title = Some(title.trim())
+        .filter(|t| !t.is_empty())
+        .map(str::to_string);
+    (duration, title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extinf_durations_parse() {
+        assert_eq!(
+            parse_extinf("215,Artist - Title"),
+            (Some(Duration::from_secs(215)), Some("Artist - Title".to_string()))
+        );
+        assert_eq!(parse_extinf("-1,Stream"), (None, Some("Stream".to_string())));
+        assert_eq!(parse_extinf("12.5 tvg-id=\"x\","), (Some(Duration::from_secs_f64(12.5)), None));
+        // Too long for a `Duration`: unknown rather than a panic.
+        assert_eq!(parse_extinf("inf,Title").0, None);
+        assert_eq!(parse_extinf("1e30,Title").0, None);
+        assert_eq!(parse_extinf("NaN,Title").0, None);
+    }
+}
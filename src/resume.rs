@@ -0,0 +1,68 @@
+//! Where each track was left off, kept as JSON next to the session in the
+//! user's config directory and keyed by canonical path, so starting a long
+//! track again can continue from there.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::session::{PathStore, track_key};
+
+const RESUME_FILE: &str = "resume.json";
+
+/// Positions closer than this to either end of a track are not worth
+/// resuming from.
+pub const RESUME_MIN: Duration = Duration::from_secs(30);
+
+/// What to do when a track with a saved position starts (`[playback]
+/// resume` in the config file).
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResumeMode {
+    /// Ask whether to continue from the saved position.
+    #[default]
+    Ask,
+    /// Continue from the saved position without asking.
+    Always,
+    /// Always start from the beginning.
+    Never,
+}
+
+pub struct ResumePositions {
+    store: PathStore<HashMap<PathBuf, Duration>>,
+}
+
+impl ResumePositions {
+    /// Load saved positions. Missing or unreadable files yield none.
+    pub fn load() -> Self {
+        Self {
+            store: PathStore::load(RESUME_FILE),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.store.save()
+    }
+
+    pub fn get(&self, track: &Path) -> Option<Duration> {
+        self.store.entries.get(&track_key(track)).copied()
+    }
+
+    /// Remember `position` in `track` (of length `duration`, if known), or
+    /// forget the track when the position is too close to either end.
+    pub fn set(&mut self, track: &Path, position: Duration, duration: Option<Duration>) {
+        let near_end = duration.is_some_and(|dur| position + RESUME_MIN >= dur);
+        if position < RESUME_MIN || near_end {
+            self.remove(track);
+        } else {
+            self.store.entries.insert(track_key(track), position);
+        }
+    }
+
+    pub fn remove(&mut self, track: &Path) {
+        self.store.entries.remove(&track_key(track));
+    }
+}
@@ -0,0 +1,120 @@
+//! Playback session (track, position, volume and balance, repeat, EQ and
+//! pre-amp, ReplayGain) saved on quit and restored on the next start, as JSON in the
+//! user's config directory. Also home to `PathStore`, which the per-track
+//! stores (bookmarks, play counts, stars, …) keep their JSON in.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::app::RepeatMode;
+
+const SESSION_FILE: &str = "session.json";
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    /// Canonical path of the track that was playing, if any.
+    pub track: Option<PathBuf>,
+    pub position: Duration,
+    /// Volume as shown (1.0 = 100%), before the loudness curve.
+    pub volume: f32,
+    /// Left/right balance in percent (negative: left).
+    pub balance: i16,
+    pub repeat: RepeatMode,
+    /// Gain per EQ band in dB (Bass, Mid, Treble).
+    pub eq_db: Vec<f32>,
+    /// Centre frequency per EQ band in Hz.
+    pub eq_freqs: Vec<f32>,
+    pub eq_q: Option<f32>,
+    /// Pre-amp gain in dB, applied before the EQ bands.
+    pub eq_preamp_db: f32,
+    /// False when the EQ was bypassed.
+    pub eq_enabled: bool,
+    pub visualizer_sensitivity: f32,
+    pub replay_gain: bool,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            track: None,
+            position: Duration::ZERO,
+            volume: 1.0,
+            balance: 0,
+            repeat: RepeatMode::Off,
+            eq_db: Vec::new(),
+            eq_freqs: Vec::new(),
+            eq_q: None,
+            eq_preamp_db: 0.0,
+            eq_enabled: true,
+            visualizer_sensitivity: 1.0,
+            replay_gain: false,
+        }
+    }
+}
+
+impl Session {
+    /// Load the saved session. Missing or unreadable files yield `None`.
+    pub fn load() -> Option<Self> {
+        let contents = fs::read_to_string(config_dir()?.join(SESSION_FILE)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        write_config_file(SESSION_FILE, &serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Data saved per track as JSON in the config directory, keyed by
+/// `track_key`. `T` is the whole collection as saved, e.g. a map from path
+/// to play count.
+pub struct PathStore<T> {
+    file: &'static str,
+    pub entries: T,
+}
+
+impl<T: Default + Serialize + DeserializeOwned> PathStore<T> {
+    /// Load `file`. Missing or unreadable files yield an empty collection.
+    pub fn load(file: &'static str) -> Self {
+        let entries = config_dir()
+            .and_then(|dir| fs::read_to_string(dir.join(file)).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self { file, entries }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        write_config_file(self.file, &serde_json::to_string_pretty(&self.entries)?)
+    }
+}
+
+/// Canonical path of a track, so the same file reached via different paths
+/// shares what is saved for it.
+pub fn track_key(track: &Path) -> PathBuf {
+    fs::canonicalize(track).unwrap_or_else(|_| track.to_path_buf())
+}
+
+/// Write `contents` to `file` in the config directory, creating it first.
+fn write_config_file(file: &str, contents: &str) -> Result<()> {
+    let dir = config_dir().context("no config directory (HOME is not set)")?;
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("creating {}", dir.display()))?;
+    let path = dir.join(file);
+    fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))
+}
+
+/// `$XDG_CONFIG_HOME/tui_music_player`, falling back to `~/.config/tui_music_player`
+/// (or `%APPDATA%` on Windows).
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(base.join("tui_music_player"))
+}
@@ -0,0 +1,312 @@
+//! Status and control endpoint on a Unix socket (`socket` feature, enabled
+//! with `[socket]` in the config), for status bars and scripts.
+//!
+//! A client sends at most one line and gets one line back before the
+//! connection is closed: a command such as `next` or `seek 30` is answered
+//! with `ok` or `error: …`, while `status`, an empty line or nothing at all
+//! (e.g. `socat - UNIX-CONNECT:<path>`) gets the player's state as JSON.
+//!
+//! The listener runs on its own thread. Commands are parsed there and sent
+//! over a channel the main loop drains with `StatusSocket::try_command`;
+//! `StatusSocket::update` publishes the app's state to it every tick, and
+//! dropping the `StatusSocket` stops the thread and removes the socket file.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+
+use crate::app::App;
+
+/// How often the listener looks for connections and for the stop flag.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(50);
+/// Longest a slow client may hold up the listener while being written to.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+/// How long a client has to send its line; one that sends nothing by then
+/// gets the status.
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+/// Longest request read; the rest of a longer line is ignored.
+const MAX_LINE: u64 = 256;
+
+/// A request from a script, applied to the app on the UI thread.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// Resume, or start the selected track when nothing is playing.
+    Play,
+    Pause,
+    Next,
+    Previous,
+    /// Seek to a position in the playing track.
+    SeekTo(Duration),
+    /// Seek by this many seconds (negative: back).
+    SeekBy(f64),
+    /// Volume in percent.
+    Volume(u16),
+}
+
+/// Parse a request line: `Ok(None)` asks for the status, `Err` says what
+/// is wrong with it.
+fn parse(line: &str) -> Result<Option<Command>, String> {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return Ok(None);
+    };
+    let arg = words.next();
+    if words.next().is_some() {
+        return Err(format!("too many arguments to `{name}`"));
+    }
+    let command = match (name, arg) {
+        ("status", None) => return Ok(None),
+        ("play", None) => Command::Play,
+        ("pause", None) => Command::Pause,
+        ("next", None) => Command::Next,
+        ("prev", None) => Command::Previous,
+        ("status" | "play" | "pause" | "next" | "prev", Some(_)) => {
+            return Err(format!("`{name}` takes no argument"));
+        }
+        ("seek", Some(arg)) => {
+            let secs = arg
+                .parse::<f64>()
+                .ok()
+                .filter(|secs| secs.is_finite())
+                .ok_or_else(|| format!("`{arg}` is not a number of seconds"))?;
+            // Both forms must fit in a `Duration`, or converting panics.
+            let time = Duration::try_from_secs_f64(secs.abs())
+                .map_err(|_| format!("`{arg}` is out of range"))?;
+            if arg.starts_with(['+', '-']) {
+                Command::SeekBy(secs)
+            } else {
+                Command::SeekTo(time)
+            }
+        }
+        ("seek", None) => return Err("`seek` needs seconds, e.g. `seek 30` or `seek -10`".into()),
+        ("volume", Some(arg)) => Command::Volume(
+            arg.parse()
+                .map_err(|_| format!("`{arg}` is not a whole percentage"))?,
+        ),
+        ("volume", None) => return Err("`volume` needs a percentage, e.g. `volume 80`".into()),
+        _ => return Err(format!("unknown command `{name}`")),
+    };
+    Ok(Some(command))
+}
+
+/// Carry out `command` as the matching key would.
+pub fn apply(app: &mut App, command: Command) {
+    match command {
+        Command::Play if app.now_playing.is_none() => app.play_selected(),
+        Command::Play if !app.is_playing() => app.toggle_pause(),
+        Command::Pause if app.is_playing() => app.toggle_pause(),
+        Command::Play | Command::Pause => {}
+        Command::Next => app.next_track(),
+        Command::Previous => app.prev_track(),
+        Command::SeekTo(pos) => app.seek_to(pos),
+        Command::SeekBy(secs) => {
+            // `parse` made sure the offset fits in a `Duration`.
+            let offset = Duration::try_from_secs_f64(secs.abs()).unwrap_or(Duration::MAX);
+            let pos = app.current_position();
+            app.seek_to(if secs < 0.0 {
+                pos.saturating_sub(offset)
+            } else {
+                pos.saturating_add(offset)
+            });
+        }
+        Command::Volume(percent) => app.set_volume(percent),
+    }
+}
+
+/// What a client reads; times are in seconds.
+#[derive(Serialize)]
+struct Status<'a> {
+    /// `playing`, `paused` or `stopped`.
+    state: &'a str,
+    track: Option<Track<'a>>,
+    position: f64,
+    duration: Option<f64>,
+    /// In percent; 100 plays the file unchanged.
+    volume: u16,
+    /// Names of the songs up next, in order.
+    queue: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Track<'a> {
+    name: &'a str,
+    title: Option<&'a str>,
+    artist: Option<&'a str>,
+    album: Option<&'a str>,
+    path: &'a Path,
+}
+
+pub struct StatusSocket {
+    path: PathBuf,
+    /// Latest status as a line of JSON, handed to each client.
+    status: Arc<Mutex<String>>,
+    commands: Receiver<Command>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StatusSocket {
+    /// Listen on `path`. A socket file left behind by a player that is no
+    /// longer running is replaced; one that still answers is an error.
+    pub fn start(path: &Path) -> Result<Self> {
+        if path.exists() {
+            if UnixStream::connect(path).is_ok() {
+                bail!("{} is in use by another player", path.display());
+            }
+            std::fs::remove_file(path)
+                .with_context(|| format!("removing stale socket {}", path.display()))?;
+        }
+        let listener =
+            UnixListener::bind(path).with_context(|| format!("listening on {}", path.display()))?;
+        listener.set_nonblocking(true)?;
+
+        let status = Arc::new(Mutex::new(String::from("{}\n")));
+        let (tx, commands) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let status = Arc::clone(&status);
+            let stop = Arc::clone(&stop);
+            thread::spawn(move || serve(&listener, &status, &tx, &stop))
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            status,
+            commands,
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// The next command received and not yet applied, if any.
+    pub fn try_command(&self) -> Option<Command> {
+        self.commands.try_recv().ok()
+    }
+
+    /// Publish the app's current state. Called every tick.
+    pub fn update(&self, app: &App) {
+        let song = app.now_playing.map(|idx| &app.songs[idx]);
+        let state = if app.is_playing() {
+            "playing"
+        } else if song.is_some() {
+            "paused"
+        } else {
+            "stopped"
+        };
+        let status = Status {
+            state,
+            track: song.map(|song| Track {
+                name: &song.name,
+                title: song.tags.title.as_deref(),
+                artist: song.tags.artist.as_deref(),
+                album: song.tags.album.as_deref(),
+                path: &song.path,
+            }),
+            position: app.current_position().as_secs_f64(),
+            duration: app.current_duration().map(|d| d.as_secs_f64()),
+            volume: app.volume_percent(),
+            queue: app.queue.iter().map(|&idx| app.songs[idx].name.as_str()).collect(),
+        };
+        let Ok(mut line) = serde_json::to_string(&status) else {
+            return;
+        };
+        line.push('\n');
+        if let Ok(mut shared) = self.status.lock() {
+            *shared = line;
+        }
+    }
+}
+
+impl Drop for StatusSocket {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Answer connections until `stop` is set, passing commands on to
+/// `commands`.
+fn serve(
+    listener: &UnixListener,
+    status: &Mutex<String>,
+    commands: &Sender<Command>,
+    stop: &AtomicBool,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((mut stream, _)) => {
+                // Some platforms pass the listener's non-blocking mode on.
+                let _ = stream.set_nonblocking(false);
+                let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                // Nothing sent in time (or unreadable) counts as a status
+                // request.
+                let mut line = String::new();
+                let _ = BufReader::new((&stream).take(MAX_LINE)).read_line(&mut line);
+                let reply = match parse(&line) {
+                    Ok(None) => match status.lock() {
+                        Ok(status) => status.clone(),
+                        Err(_) => return,
+                    },
+                    Ok(Some(command)) => {
+                        if commands.send(command).is_err() {
+                            return;
+                        }
+                        "ok\n".to_string()
+                    }
+                    Err(e) => format!("error: {e}\n"),
+                };
+                let _ = stream.write_all(reply.as_bytes());
+            }
+            // Usually nobody is waiting; look again shortly.
+            Err(_) => thread::sleep(ACCEPT_INTERVAL),
+        }
+    }
+}
+
+/// Default socket path: `tui_music_player.sock` in `$XDG_RUNTIME_DIR`, or
+/// in the temporary directory without one.
+pub fn default_path() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map_or_else(std::env::temp_dir, PathBuf::from)
+        .join("tui_music_player.sock")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse(""), Ok(None));
+        assert_eq!(parse("status\n"), Ok(None));
+        assert_eq!(parse(" next \n"), Ok(Some(Command::Next)));
+        assert_eq!(parse("seek 30"), Ok(Some(Command::SeekTo(Duration::from_secs(30)))));
+        assert_eq!(parse("seek -7.5"), Ok(Some(Command::SeekBy(-7.5))));
+        assert_eq!(parse("volume 80"), Ok(Some(Command::Volume(80))));
+        assert!(parse("volume loud").is_err());
+        assert!(parse("seek").is_err());
+        assert!(parse("pause now").is_err());
+        assert!(parse("rewind").is_err());
+    }
+
+    #[test]
+    fn huge_seeks_are_refused() {
+        assert_eq!(parse("seek 1e30"), Err("`1e30` is out of range".to_string()));
+        assert_eq!(parse("seek +1e30"), Err("`+1e30` is out of range".to_string()));
+        assert_eq!(parse("seek -1e30"), Err("`-1e30` is out of range".to_string()));
+        assert!(parse("seek inf").is_err());
+        assert_eq!(parse("seek +60"), Ok(Some(Command::SeekBy(60.0))));
+    }
+}
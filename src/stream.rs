@@ -0,0 +1,163 @@
+//! Sources other than local files: standard input (`-`) and HTTP(S) URLs.
+//! Both are read once, front to back, so they are wrapped in a reader that
+//! keeps a window of recent bytes for the decoder's format probing.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::{Context, Result, bail};
+
+/// Bytes kept behind the read position of a stream; the decoder can seek
+/// back this far (it rewinds to the start while probing the format).
+const STREAM_WINDOW: usize = 1 << 20;
+
+/// Standard input has been handed to a decoder; it cannot be read again.
+static STDIN_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Whether `path` names standard input or a URL rather than a file.
+pub fn is_stream(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|s| s == "-" || s.starts_with("http://") || s.starts_with("https://"))
+}
+
+/// Display name of a stream: the URL, or "Standard input".
+pub fn stream_name(path: &Path) -> String {
+    match path.to_str() {
+        Some("-") => "Standard input".to_string(),
+        _ => path.display().to_string(),
+    }
+}
+
+/// What a decoder reads from: a local file, or a stream.
+pub enum Input {
+    File(BufReader<File>),
+    Stream(StreamReader),
+}
+
+impl Input {
+    /// Open `path`: `-` reads standard input, `http://` and `https://`
+    /// fetch the URL, anything else is a file.
+    pub fn open(path: &Path) -> Result<Self> {
+        if !is_stream(path) {
+            return Ok(Self::File(BufReader::new(File::open(path)?)));
+        }
+        let reader: Box<dyn Read + Send + Sync> = match path.to_str() {
+            Some("-") => {
+                if STDIN_TAKEN.swap(true, Ordering::Relaxed) {
+                    bail!("standard input can only be played once");
+                }
+                Box::new(io::stdin())
+            }
+            Some(url) => ureq::get(url)
+                .call()
+                .with_context(|| format!("fetching {url}"))?
+                .into_reader(),
+            None => bail!("not a valid URL"),
+        };
+        Ok(Self::Stream(StreamReader::new(reader)))
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::File(file) => file.read(buf),
+            Self::Stream(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Seek for Input {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::File(file) => file.seek(pos),
+            Self::Stream(stream) => stream.seek(pos),
+        }
+    }
+}
+
+/// A forward-only reader made seekable within the last `STREAM_WINDOW`
+/// bytes read. Seeking ahead reads up to the target; seeking from the end
+/// is not supported.
+pub struct StreamReader {
+    inner: Box<dyn Read + Send + Sync>,
+    /// Bytes read so far, from stream offset `start` on.
+    window: Vec<u8>,
+    start: u64,
+    /// Read position as a stream offset.
+    pos: u64,
+}
+
+impl StreamReader {
+    fn new(inner: Box<dyn Read + Send + Sync>) -> Self {
+        Self {
+            inner,
+            window: Vec::new(),
+            start: 0,
+            pos: 0,
+        }
+    }
+
+    fn end(&self) -> u64 {
+        self.start + self.window.len() as u64
+    }
+
+    /// Read more of the stream into the window. Returns the bytes added (0
+    /// at the end of the stream).
+    fn fill(&mut self) -> io::Result<usize> {
+        let mut chunk = [0; 8192];
+        let n = self.inner.read(&mut chunk)?;
+        self.window.extend_from_slice(&chunk[..n]);
+        // Drop what lies well behind the read position.
+        let behind = (self.pos - self.start) as usize;
+        if behind > 2 * STREAM_WINDOW {
+            let drop = behind - STREAM_WINDOW;
+            self.window.drain(..drop);
+            self.start += drop as u64;
+        }
+        Ok(n)
+    }
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.end() && self.fill()? == 0 {
+            return Ok(0);
+        }
+        let offset = (self.pos - self.start) as usize;
+        let n = buf.len().min(self.window.len() - offset);
+        buf[..n].copy_from_slice(&self.window[offset..offset + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for StreamReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "a stream has no known end",
+                ));
+            }
+        };
+        let Some(target) = target.filter(|&t| t >= self.start) else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek back that far in a stream",
+            ));
+        };
+        while self.end() < target {
+            if self.fill()? == 0 {
+                break;
+            }
+        }
+        self.pos = target.min(self.end());
+        Ok(self.pos)
+    }
+}
@@ -0,0 +1,147 @@
+//! Interface colours. A built-in theme is picked by name in the `[theme]`
+//! section of `config.toml`, and single colours can be overridden there with
+//! hex values such as `accent = "#88c0d0"`.
+
+use ratatui::style::Color;
+use serde::Deserialize;
+
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// Titles, borders of focused popups, the playing track's name.
+    pub accent: Color,
+    /// Selected rows and key labels.
+    pub text: Color,
+    /// Ordinary list rows and descriptions.
+    pub subtext: Color,
+    /// Borders, hints and secondary details.
+    pub dim: Color,
+    /// Background of the selected row.
+    pub highlight_bg: Color,
+    /// Background of popups.
+    pub popup_bg: Color,
+    /// Playing indicator.
+    pub playing: Color,
+    /// Pause state, A-B loop points, stars and timers.
+    pub marker: Color,
+    /// Error messages.
+    pub error: Color,
+}
+
+impl Theme {
+    /// The terminal's own palette, plus two dark backgrounds.
+    pub const DEFAULT: Theme = Theme {
+        accent: Color::Cyan,
+        text: Color::White,
+        subtext: Color::Gray,
+        dim: Color::DarkGray,
+        highlight_bg: Color::Rgb(35, 35, 55),
+        popup_bg: Color::Rgb(18, 18, 24),
+        playing: Color::Green,
+        marker: Color::Yellow,
+        error: Color::Red,
+    };
+
+    pub const NORD: Theme = Theme {
+        accent: Color::Rgb(0x88, 0xc0, 0xd0),
+        text: Color::Rgb(0xec, 0xef, 0xf4),
+        subtext: Color::Rgb(0xd8, 0xde, 0xe9),
+        dim: Color::Rgb(0x61, 0x6e, 0x88),
+        highlight_bg: Color::Rgb(0x3b, 0x42, 0x52),
+        popup_bg: Color::Rgb(0x2e, 0x34, 0x40),
+        playing: Color::Rgb(0xa3, 0xbe, 0x8c),
+        marker: Color::Rgb(0xeb, 0xcb, 0x8b),
+        error: Color::Rgb(0xbf, 0x61, 0x6a),
+    };
+
+    pub const GRUVBOX: Theme = Theme {
+        accent: Color::Rgb(0x83, 0xa5, 0x98),
+        text: Color::Rgb(0xeb, 0xdb, 0xb2),
+        subtext: Color::Rgb(0xd5, 0xc4, 0xa1),
+        dim: Color::Rgb(0x92, 0x83, 0x74),
+        highlight_bg: Color::Rgb(0x3c, 0x38, 0x36),
+        popup_bg: Color::Rgb(0x28, 0x28, 0x28),
+        playing: Color::Rgb(0xb8, 0xbb, 0x26),
+        marker: Color::Rgb(0xfa, 0xbd, 0x2f),
+        error: Color::Rgb(0xfb, 0x49, 0x34),
+    };
+
+    /// Built-in theme by name (`default`, `nord`, `gruvbox`).
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name.to_ascii_lowercase().as_str() {
+            "default" => Some(Self::DEFAULT),
+            "nord" => Some(Self::NORD),
+            "gruvbox" => Some(Self::GRUVBOX),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// `[theme]` as written in the config file.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub name: Option<String>,
+    pub accent: Option<String>,
+    pub text: Option<String>,
+    pub subtext: Option<String>,
+    pub dim: Option<String>,
+    pub highlight_bg: Option<String>,
+    pub popup_bg: Option<String>,
+    pub playing: Option<String>,
+    pub marker: Option<String>,
+    pub error: Option<String>,
+}
+
+impl ThemeConfig {
+    /// The named theme (default if none) with the given colours replaced.
+    /// An unknown name or a colour that is not `#rrggbb` is reported on
+    /// stderr; the default theme, or the base theme's colour, is used instead.
+    pub fn resolve(&self) -> Theme {
+        let mut theme = match self.name.as_deref() {
+            None => Theme::DEFAULT,
+            Some(name) => Theme::builtin(name).unwrap_or_else(|| {
+                eprintln!("config: unknown theme \"{name}\", using the default theme");
+                Theme::DEFAULT
+            }),
+        };
+        let overrides = [
+            ("accent", &self.accent, &mut theme.accent),
+            ("text", &self.text, &mut theme.text),
+            ("subtext", &self.subtext, &mut theme.subtext),
+            ("dim", &self.dim, &mut theme.dim),
+            ("highlight_bg", &self.highlight_bg, &mut theme.highlight_bg),
+            ("popup_bg", &self.popup_bg, &mut theme.popup_bg),
+            ("playing", &self.playing, &mut theme.playing),
+            ("marker", &self.marker, &mut theme.marker),
+            ("error", &self.error, &mut theme.error),
+        ];
+        for (key, value, color) in overrides {
+            let Some(value) = value else {
+                continue;
+            };
+            match parse_hex(value) {
+                Some(parsed) => *color = parsed,
+                None => eprintln!(
+                    "config: ignoring theme.{key} = \"{value}\": expected a colour like \"#88c0d0\""
+                ),
+            }
+        }
+        theme
+    }
+}
+
+/// `#rrggbb` (the `#` is optional) as an RGB colour.
+fn parse_hex(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
@@ -0,0 +1,42 @@
+//! EQ band gains saved for individual tracks, applied instead of the global
+//! EQ while those tracks play. Kept as JSON next to the session in the
+//! user's config directory and keyed by canonical path.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::session::{PathStore, track_key};
+
+const TRACK_EQ_FILE: &str = "track_eq.json";
+
+pub struct TrackEq {
+    /// Gain per band in dB.
+    store: PathStore<HashMap<PathBuf, Vec<f32>>>,
+}
+
+impl TrackEq {
+    /// Load saved curves. Missing or unreadable files yield none.
+    pub fn load() -> Self {
+        Self {
+            store: PathStore::load(TRACK_EQ_FILE),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.store.save()
+    }
+
+    pub fn get(&self, track: &Path) -> Option<&[f32]> {
+        self.store.entries.get(&track_key(track)).map(Vec::as_slice)
+    }
+
+    pub fn set(&mut self, track: &Path, gains: Vec<f32>) {
+        self.store.entries.insert(track_key(track), gains);
+    }
+
+    pub fn remove(&mut self, track: &Path) {
+        self.store.entries.remove(&track_key(track));
+    }
+}
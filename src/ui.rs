@@ -6,21 +6,30 @@ use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{
-        Bar, BarChart, BarGroup, Block, BorderType, Borders, Clear, Gauge, List, ListItem, Padding,
-        Paragraph,
+        Block, BorderType, Borders, Clear, Gauge, List, ListItem, Padding,
+        Paragraph, Scrollbar, ScrollbarOrientation,
     },
 };
 
-use crate::app::{App, EqState, RepeatMode};
+use ratatui_image::StatefulImage;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-const CYAN: Color = Color::Cyan;
-const WHITE: Color = Color::White;
-const GRAY: Color = Color::Gray;
-const DARK_GRAY: Color = Color::DarkGray;
-const GREEN: Color = Color::Green;
-const YELLOW: Color = Color::Yellow;
-const HIGHLIGHT_BG: Color = Color::Rgb(35, 35, 55);
-const EQ_POPUP_BG: Color = Color::Rgb(18, 18, 24);
+use crate::app::{App, EqState, Palette, Prompt, RepeatMode, SortOrder, TITLE_SCROLL_GAP};
+use crate::config::Panel;
+use crate::keymap::{Action, KeyMap};
+use crate::player::Format;
+use crate::theme::Theme;
+
+/// Width of the play-count column in the song list.
+const PLAYS_WIDTH: usize = 5;
+/// Columns for cover art; about square beside the five text rows.
+const COVER_WIDTH: u16 = 10;
+/// Narrower now-playing boxes leave the art out to keep room for the text.
+const COVER_MIN_WIDTH: u16 = 60;
+/// Cells of the volume bar (`[ui] volume_bar`).
+const VOLUME_BAR_WIDTH: usize = 10;
+/// Frames of the icon shown while a track is loading.
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
     let popup_x = area.width.saturating_sub(width) / 2;
@@ -35,156 +44,707 @@ fn format_duration(d: Duration) -> String {
     format!("{mins}:{secs:02}")
 }
 
-fn truncate_name(s: &str, max_chars: usize) -> String {
-    if s.chars().count() <= max_chars {
-        s.to_string()
+/// Length of a whole list: "42:07", or "3:42:07" from an hour up.
+fn format_total(d: Duration) -> String {
+    let total_secs = d.as_secs();
+    let (hours, mins, secs) = (total_secs / 3600, total_secs / 60 % 60, total_secs % 60);
+    if hours > 0 {
+        format!("{hours}:{mins:02}:{secs:02}")
     } else {
-        let truncated: String = s.chars().take(max_chars.saturating_sub(1)).collect();
-        format!("{truncated}…")
+        format!("{mins}:{secs:02}")
     }
 }
 
-pub fn draw(frame: &mut Frame, app: &mut App) {
-    let chunks = Layout::vertical([
-        Constraint::Min(5),
-        Constraint::Length(6), // Visualizer
-        Constraint::Length(8), // Now playing
-    ])
-    .split(frame.area());
+/// Listening time beside the clock: "0:42" (hours and minutes).
+fn format_session(d: Duration) -> String {
+    let total_mins = d.as_secs() / 60;
+    format!("{}:{:02}", total_mins / 60, total_mins % 60)
+}
+
+/// Compact frequency label: "120Hz", "1.0k", "12.5k".
+fn format_freq(hz: f32) -> String {
+    if hz < 1000.0 {
+        format!("{hz:.0}Hz")
+    } else {
+        format!("{:.1}k", hz / 1000.0)
+    }
+}
+
+/// "FLAC · 44.1 kHz · 2 ch", without the codec when it is unknown, and
+/// naming the fallback decoder when one plays the file.
+fn format_audio(format: Format) -> String {
+    let rate = format!("{} kHz", format.sample_rate as f64 / 1000.0);
+    let channels = match format.downmixed_from {
+        Some(from) => format!("{from} → {} ch (mono)", format.channels),
+        None => format!("{} ch", format.channels),
+    };
+    let audio = match format.codec {
+        Some(codec) => format!("{codec} · {rate} · {channels}"),
+        None => format!("{rate} · {channels}"),
+    };
+    match format.decoder {
+        Some(decoder) => format!("{audio} · via {decoder}"),
+        None => audio,
+    }
+}
+
+/// `width` columns of `s` from character `start` on, wrapping round to the
+/// start again after `TITLE_SCROLL_GAP`. A wide character that would cross
+/// the right edge is left out and its column padded.
+fn marquee(s: &str, start: usize, width: usize) -> String {
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars().chain(TITLE_SCROLL_GAP.chars()).cycle().skip(start) {
+        let w = c.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+    out.extend(std::iter::repeat_n(' ', width - used));
+    out
+}
+
+/// `s` cut to at most `max_width` columns, ending in "…" when cut. Wide
+/// characters (CJK, most emoji) take two columns.
+fn truncate_name(s: &str, max_width: usize) -> String {
+    if s.width() <= max_width {
+        return s.to_string();
+    }
+    let room = max_width.saturating_sub(1);
+    let mut width = 0;
+    let truncated: String = s
+        .chars()
+        .take_while(|c| {
+            width += c.width().unwrap_or(0);
+            width <= room
+        })
+        .collect();
+    format!("{truncated}…")
+}
+
+pub fn draw(frame: &mut Frame, app: &mut App, keymap: &KeyMap) {
+    // With nothing playing the bars are all empty, so the visualizer folds
+    // away and the song list gets its rows.
+    let visualizer_height = if app.now_playing.is_some() { 6 } else { 0 };
+    let mut constraints = vec![Constraint::Min(5)];
+    constraints.extend(app.panels.iter().map(|panel| match panel {
+        Panel::Visualizer => Constraint::Length(visualizer_height),
+        Panel::NowPlaying => Constraint::Length(8),
+    }));
+    let chunks = Layout::vertical(constraints).split(frame.area());
+    // Panels left out of the config get an empty area.
+    let panel_area = |panel| {
+        app.panels
+            .iter()
+            .position(|&p| p == panel)
+            .map_or_else(Rect::default, |i| chunks[i + 1])
+    };
+    let visualizer_area = panel_area(Panel::Visualizer);
+    let now_playing_area = panel_area(Panel::NowPlaying);
+
+    app.sample_spectrum();
+
+    // Bars need a row and a column inside the border, and the help covers
+    // them entirely.
+    app.set_visualizer_hidden(
+        visualizer_area.height < 3 || visualizer_area.width < 3 || app.help_open,
+    );
 
     draw_song_list(frame, app, chunks[0]);
-    draw_visualizer(frame, app, chunks[1]);
-    draw_now_playing(frame, app, chunks[2]);
+    if !visualizer_area.is_empty() {
+        draw_visualizer(frame, app, visualizer_area);
+    }
+    if now_playing_area.is_empty() {
+        app.progress_area = Rect::default();
+    } else {
+        draw_now_playing(frame, app, keymap, now_playing_area);
+    }
 
     if app.eq_state.popup_open {
         draw_eq_popup(frame, app);
     }
+
+    if app.help_open {
+        draw_help_popup(frame, app, keymap);
+    }
+
+    if let Some(prompt) = &app.prompt {
+        draw_prompt(frame, prompt, &app.theme);
+    }
+}
+
+/// Text prompt: a small centered box titled with what is being asked for.
+fn draw_prompt(frame: &mut Frame, prompt: &Prompt, theme: &Theme) {
+    const PROMPT_W: u16 = 44;
+    const PROMPT_H: u16 = 3;
+    let area = frame.area();
+    let rect = centered_rect(area, PROMPT_W.min(area.width), PROMPT_H.min(area.height));
+
+    frame.render_widget(Clear, rect);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.popup_bg))
+        .title(Line::from(Span::styled(
+            format!(" {} ", prompt.kind.label()),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        )));
+
+    let input = if prompt.kind.takes_input() {
+        Line::from(vec![
+            Span::raw(" "),
+            Span::styled(prompt.input.as_str(), Style::default().fg(theme.text)),
+            Span::styled("▏", Style::default().fg(theme.accent)),
+        ])
+    } else {
+        Line::from(Span::styled(
+            " y / Enter: resume   n / Esc: start over",
+            Style::default().fg(theme.dim),
+        ))
+    };
+    frame.render_widget(Paragraph::new(input).block(block), rect);
+}
+
+/// Left pane of the folder browser: ".." and the subfolders of the browsed
+/// folder.
+fn draw_folders(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.theme;
+    let focused = app.folders_focused();
+    let Some(browse) = app.browse.as_mut() else {
+        return;
+    };
+    let border = if focused { theme.accent } else { theme.dim };
+    let block = Block::default()
+        .title(Span::styled(
+            " Folders ",
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        ))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(border))
+        .padding(Padding::horizontal(1));
+
+    if browse.folders.is_empty() {
+        let msg = Paragraph::new(Span::styled("No subfolders", Style::default().fg(theme.dim)))
+            .block(block)
+            .alignment(Alignment::Center);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let width = block.inner(area).width as usize;
+    let items: Vec<ListItem> = browse
+        .folders
+        .iter()
+        .map(|(label, _)| {
+            ListItem::new(Span::styled(
+                truncate_name(label, width),
+                Style::default().fg(theme.subtext),
+            ))
+        })
+        .collect();
+    let highlight = if focused {
+        Style::default()
+            .bg(theme.highlight_bg)
+            .fg(theme.text)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().bg(theme.highlight_bg)
+    };
+    let list = List::new(items).block(block).highlight_style(highlight);
+    frame.render_stateful_widget(list, area, &mut browse.folder_state);
 }
 
 fn draw_song_list(frame: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.theme;
+    let area = if app.browsing() {
+        let [folders_area, tracks_area] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Min(0)]).areas(area);
+        draw_folders(frame, app, folders_area);
+        tracks_area
+    } else {
+        area
+    };
+    let mut title = vec![
+        Span::styled(" ♫ ", Style::default().fg(theme.accent)),
+        Span::styled(
+            "Music Player ",
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        ),
+    ];
+    if let Some(playlist) = &app.playlist {
+        let name = playlist
+            .file_stem()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        title.push(Span::styled(
+            format!("≡ {name} "),
+            Style::default().fg(theme.text),
+        ));
+        title.push(Span::styled("⌫ library ", Style::default().fg(theme.dim)));
+    }
+    if let Some(browse) = app.browse.as_ref().filter(|_| app.browsing()) {
+        let folder = browse
+            .dir
+            .strip_prefix(&app.music_dir)
+            .ok()
+            .filter(|rel| !rel.as_os_str().is_empty())
+            .map_or_else(|| "/".to_string(), |rel| format!("/{}", rel.display()));
+        title.push(Span::styled(
+            format!("{folder} "),
+            Style::default().fg(theme.text),
+        ));
+    }
+    // Folders the scan could not read; the first one was named on the error
+    // line.
+    if app.playlist.is_none() && !app.scan_errors.is_empty() {
+        title.push(Span::styled(
+            format!("⚠ {} unreadable ", app.scan_errors.len()),
+            Style::default().fg(theme.marker),
+        ));
+    }
+    if app.starred_only {
+        title.push(Span::styled("★ starred ", Style::default().fg(theme.marker)));
+    }
+    if app.history_only {
+        title.push(Span::styled("↺ recent ", Style::default().fg(theme.marker)));
+    }
+    if app.search_active() {
+        let cursor = if app.search_mode { "▏" } else { "" };
+        title.push(Span::styled("/", Style::default().fg(theme.accent)));
+        title.push(Span::styled(
+            format!("{}{cursor} ", app.search_query),
+            Style::default().fg(theme.text),
+        ));
+    }
+    if app.search_active() || app.starred_only || app.history_only {
+        title.push(Span::styled(
+            format!("{} of {} ", app.view.len(), app.songs.len()),
+            Style::default().fg(theme.dim),
+        ));
+    }
+
+    // Track count and total length on the right; "≥" while some lengths are
+    // still unknown.
+    let stats = app.stats;
+    let songs_label = if stats.songs == 1 { "song" } else { "songs" };
+    let at_least = if stats.unknown > 0 { "≥" } else { "" };
+    let mut stats_title = vec![Span::styled(
+        format!(
+            " {} {songs_label} · {at_least}{} ",
+            stats.songs,
+            format_total(stats.total)
+        ),
+        Style::default().fg(theme.dim),
+    )];
+    if app.clock {
+        stats_title.push(Span::styled(
+            format!("{} ", chrono::Local::now().format("%H:%M")),
+            Style::default().fg(theme.text),
+        ));
+        stats_title.push(Span::styled(
+            format!("· listening {} ", format_session(app.session_elapsed())),
+            Style::default().fg(theme.dim),
+        ));
+    }
+    let stats_title = Line::from(stats_title).right_aligned();
+
+    // While browsing, the pane the list keys act on has an accent border.
+    let border = if app.browsing() && !app.folders_focused() {
+        theme.accent
+    } else {
+        theme.dim
+    };
     let block = Block::default()
-        .title(Line::from(vec![
-            Span::styled(" ♫ ", Style::default().fg(CYAN)),
-            Span::styled(
-                "Music Player ",
-                Style::default().fg(CYAN).add_modifier(Modifier::BOLD),
-            ),
-        ]))
+        .title(Line::from(title))
+        .title(stats_title)
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(DARK_GRAY))
+        .border_style(Style::default().fg(border))
         .padding(Padding::horizontal(1));
 
+    app.list_area = Rect::default();
     if app.songs.is_empty() {
-        let msg = Paragraph::new(Line::from(vec![
-            Span::styled("No music files found in ", Style::default().fg(DARK_GRAY)),
-            Span::styled("./music/", Style::default().fg(WHITE)),
-        ]))
+        let dir = app.music_dir_absolute();
+        let hint = if dir.exists() {
+            "Copy audio files into it — they appear here as soon as they are added"
+        } else {
+            "This folder does not exist yet — press Enter to create it"
+        };
+        let msg = Paragraph::new(vec![
+            Line::from(vec![
+                Span::styled("No music files found in ", Style::default().fg(theme.dim)),
+                Span::styled(format!("{}/", dir.display()), Style::default().fg(theme.text)),
+            ]),
+            Line::from(Span::styled(hint, Style::default().fg(theme.dim))),
+        ])
         .block(block)
         .alignment(Alignment::Center);
         frame.render_widget(msg, area);
         return;
     }
 
-    let inner_width = block.inner(area).width as usize;
+    if app.view.is_empty() {
+        let line = if !app.search_query.is_empty() {
+            Line::from(vec![
+                Span::styled("No matches for ", Style::default().fg(theme.dim)),
+                Span::styled(
+                    format!("“{}”", app.search_query),
+                    Style::default().fg(theme.text),
+                ),
+            ])
+        } else if app.starred_only {
+            Line::from(Span::styled(
+                "No starred songs — press f to star one",
+                Style::default().fg(theme.dim),
+            ))
+        } else if app.history_only {
+            Line::from(Span::styled(
+                "Nothing played yet — press H for all songs",
+                Style::default().fg(theme.dim),
+            ))
+        } else {
+            Line::from(Span::styled(
+                "No tracks in this folder — Tab to pick a subfolder",
+                Style::default().fg(theme.dim),
+            ))
+        };
+        let msg = Paragraph::new(line)
+        .block(block)
+        .alignment(Alignment::Center);
+        frame.render_widget(msg, area);
+        return;
+    }
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [header_area, rows_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+    app.list_area = rows_area;
+    let inner_width = inner.width as usize;
+
+    // ── Column headers, with an arrow on the sorted column ──
+    let (name_arrow, dur_arrow) = match app.sort {
+        SortOrder::Path => ("", ""),
+        SortOrder::NameAsc => (" ▲", ""),
+        SortOrder::NameDesc => (" ▼", ""),
+        SortOrder::DurationAsc => ("", " ▲"),
+        SortOrder::DurationDesc => ("", " ▼"),
+    };
+    let name_header = format!("  Title{name_arrow}");
+    let plays_header = if app.show_play_counts {
+        format!("{:>PLAYS_WIDTH$}  ", "Plays")
+    } else {
+        String::new()
+    };
+    let dur_header = format!("Length{dur_arrow}");
+    let header_pad = inner_width.saturating_sub(
+        name_header.width() + plays_header.width() + dur_header.width(),
+    );
+    let header_style = Style::default().fg(theme.dim).add_modifier(Modifier::BOLD);
+    let header = Line::from(vec![
+        Span::styled(name_header, header_style),
+        Span::raw(" ".repeat(header_pad)),
+        Span::styled(plays_header, header_style),
+        Span::styled(dur_header, header_style),
+    ]);
+    frame.render_widget(Paragraph::new(header), header_area);
 
     let items: Vec<ListItem> = app
-        .songs
+        .view
         .iter()
-        .enumerate()
-        .map(|(i, song)| {
+        .map(|&i| {
+            let song = &app.songs[i];
             let is_selected = i == app.selected;
             let is_playing = app.now_playing == Some(i);
 
-            let indicator = if is_playing { "▸ " } else { "  " };
+            let indicator = if is_playing {
+                "▸ "
+            } else if song.is_playlist() {
+                "≡ "
+            } else {
+                "  "
+            };
             let indicator_display_w: usize = 2;
             let dur_str = song
                 .duration
                 .map(|d| format_duration(d))
                 .unwrap_or_else(|| "─:──".to_string());
-            let dur_display_w = dur_str.len();
+            // Play count (blank until played to the end once), when shown.
+            let plays_str = match song.play_count {
+                _ if !app.show_play_counts => String::new(),
+                0 => " ".repeat(PLAYS_WIDTH + 2),
+                n => format!("{n:>PLAYS_WIDTH$}  "),
+            };
+            let dur_display_w = dur_str.width() + plays_str.width();
+            let star = if song.starred { " ★" } else { "" };
 
-            let max_name_chars =
-                inner_width.saturating_sub(indicator_display_w + dur_display_w + 2);
-            let name = truncate_name(&song.name, max_name_chars);
-            let name_display_w = name.chars().count();
+            let max_name_w = inner_width.saturating_sub(indicator_display_w + dur_display_w + 4);
+            let name = truncate_name(&song.name, max_name_w);
+            let name_display_w = name.width() + star.width();
 
             let total_used = indicator_display_w + name_display_w + dur_display_w;
             let pad_len = inner_width.saturating_sub(total_used);
 
             let indicator_style = if is_playing {
-                Style::default().fg(GREEN)
+                Style::default().fg(theme.playing)
             } else {
-                Style::default().fg(DARK_GRAY)
+                Style::default().fg(theme.dim)
             };
 
             let name_style = match (is_selected, is_playing) {
-                (true, true) => Style::default().fg(CYAN).add_modifier(Modifier::BOLD),
-                (true, false) => Style::default().fg(WHITE).add_modifier(Modifier::BOLD),
-                (false, true) => Style::default().fg(CYAN),
-                (false, false) => Style::default().fg(GRAY),
+                (true, true) => Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+                (true, false) => Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+                (false, true) => Style::default().fg(theme.accent),
+                (false, false) => Style::default().fg(theme.subtext),
             };
 
             let line = Line::from(vec![
                 Span::styled(indicator, indicator_style),
                 Span::styled(name, name_style),
+                Span::styled(star, Style::default().fg(theme.marker)),
                 Span::raw(" ".repeat(pad_len)),
-                Span::styled(dur_str, Style::default().fg(DARK_GRAY)),
+                Span::styled(plays_str, Style::default().fg(theme.dim)),
+                Span::styled(dur_str, Style::default().fg(theme.dim)),
             ]);
 
             let mut item = ListItem::new(line);
             if is_selected {
-                item = item.style(Style::default().bg(HIGHLIGHT_BG));
+                item = item.style(Style::default().bg(theme.highlight_bg));
             }
             item
         })
         .collect();
 
-    let list = List::new(items)
-        .block(block)
-        .highlight_style(Style::default());
+    let list = List::new(items).highlight_style(Style::default());
+
+    frame.render_stateful_widget(list, rows_area, &mut app.list_state);
 
-    frame.render_stateful_widget(list, area, &mut app.list_state);
+    // Scrollbar on the right border, only when the list does not fit.
+    if app.view.len() > app.list_area.height as usize {
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_style(Style::default().fg(theme.dim))
+            .thumb_style(Style::default().fg(theme.accent));
+        let bar_area = Rect {
+            y: area.y + 1,
+            height: area.height.saturating_sub(2),
+            ..area
+        };
+        frame.render_stateful_widget(scrollbar, bar_area, &mut app.scroll_state);
+    }
 }
 
 fn draw_visualizer(frame: &mut Frame, app: &App, area: Rect) {
+    let theme = app.theme;
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(DARK_GRAY));
+        .border_style(Style::default().fg(theme.dim))
+        .title(
+            Line::from(Span::styled(
+                visualizer_title(app),
+                Style::default().fg(theme.dim),
+            ))
+            .right_aligned(),
+        );
 
-    let spectrum = app.spectrum();
     let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Between the last two analyser frames, by the time since the newer one.
+    let (previous, current) = (&app.spectrum_previous, &app.spectrum_current);
+    let t = app.spectrum_blend();
+    let left = lerp_bars(&previous.left, &current.left, t);
+    let left_peaks = lerp_bars(&previous.left_peaks, &current.left_peaks, t);
+
+    if app.visualizer_stereo() {
+        // Left and right spectra side by side, each labelled in its corner.
+        let right = lerp_bars(&previous.right, &current.right, t);
+        let right_peaks = lerp_bars(&previous.right_peaks, &current.right_peaks, t);
+        let [left_area, right_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .spacing(1)
+                .areas(inner);
+        draw_bars(frame, app, left_area, &left, &left_peaks);
+        draw_bars(frame, app, right_area, &right, &right_peaks);
+        let label = Style::default().fg(theme.dim);
+        frame.render_widget(Paragraph::new(Span::styled("L", label)), left_area);
+        frame.render_widget(
+            Paragraph::new(Span::styled("R", label)).alignment(Alignment::Right),
+            right_area,
+        );
+    } else {
+        draw_bars(frame, app, inner, &left, &left_peaks);
+    }
+}
 
-    // Calculate how many bars fit in the available width.
+/// `from` moved a fraction `t` of the way to `to`, bar by bar; just `to`
+/// when the bar count changed in between.
+fn lerp_bars(from: &[u64], to: &[u64], t: f64) -> Vec<u64> {
+    if from.len() != to.len() {
+        return to.to_vec();
+    }
+    from.iter()
+        .zip(to)
+        .map(|(&a, &b)| (a as f64 + (b as f64 - a as f64) * t).round() as u64)
+        .collect()
+}
+
+/// Palette name, plus the sensitivity when it is not the default.
+fn visualizer_title(app: &App) -> String {
+    let sensitivity = app.visualizer_sensitivity();
+    if (sensitivity - 1.0).abs() < 0.01 {
+        format!(" {} ", app.palette.label())
+    } else {
+        format!(" {} · ×{sensitivity:.2} ", app.palette.label())
+    }
+}
+
+/// Draw one spectrum with its peak caps into `area`, resampled to the width.
+fn draw_bars(frame: &mut Frame, app: &App, area: Rect, spectrum: &[u64], peaks: &[u64]) {
+    let theme = app.theme;
+    // Calculate how many bars fit in the available width (none when it is 0;
+    // widened first so the widest terminal cannot overflow).
     let bar_w: u16 = 2;
     let gap: u16 = 1;
-    let max_bars = if inner.width > 0 {
-        ((inner.width + gap) / (bar_w + gap)) as usize
+    let max_bars = (usize::from(area.width) + usize::from(gap)) / usize::from(bar_w + gap);
+
+    let display = resample_spectrum(spectrum, max_bars);
+    let peaks = resample_peaks(peaks, max_bars);
+
+    // Frequency axis on the bottom row, when there is room for it.
+    let area = if area.height >= 4 {
+        let [bars_area, axis_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(area);
+        let freqs = resample_frequencies(&app.spectrum_frequencies(), max_bars);
+        draw_frequency_axis(frame, &theme, axis_area, &freqs, bar_w + gap);
+        bars_area
     } else {
-        0
+        area
     };
 
-    let display = resample_spectrum(&spectrum, max_bars);
+    let height = area.height as u64;
+    if height == 0 {
+        return;
+    }
 
-    let bars: Vec<Bar> = display
-        .iter()
-        .map(|&v| {
-            Bar::default()
-                .value(v)
-                .style(Style::default().fg(CYAN))
-                .text_value(String::new())
-        })
-        .collect();
+    // Bars are drawn cell by cell so each row can take its gradient colour;
+    // the top cell of a bar uses eighth blocks for a smooth height.
+    const EIGHTHS: [&str; 9] = [" ", "▁", "▂", "▃", "▄", "▅", "▆", "▇", "█"];
+    let buf = frame.buffer_mut();
+    for (i, &v) in display.iter().enumerate() {
+        let x = area.x + i as u16 * (bar_w + gap);
+        // A bar below the baseline is drawn as the baseline, dimmed so it
+        // does not pass for signal; taller bars are drawn as they are.
+        let baseline = v < app.min_bar;
+        let filled = v.max(app.min_bar).min(100) * height * 8 / 100;
+        for row in 0..height {
+            let cell = filled.saturating_sub(row * 8).min(8) as usize;
+            if cell == 0 {
+                break;
+            }
+            let level = (row as f64 + 0.5) / height as f64;
+            let color = if baseline {
+                theme.dim
+            } else {
+                palette_color(app.palette, level, theme.accent)
+            };
+            let style = Style::default().fg(color);
+            let y = area.bottom() - 1 - row as u16;
+            buf.set_string(x, y, EIGHTHS[cell].repeat(bar_w as usize), style);
+        }
+    }
 
-    let chart = BarChart::default()
-        .block(block)
-        .data(BarGroup::default().bars(&bars))
-        .bar_width(bar_w)
-        .bar_gap(gap)
-        .max(100);
+    // Peak-hold caps: one cell just above the height each peak reached.
+    let cap = "▔".repeat(bar_w as usize);
+    for (i, &peak) in peaks.iter().enumerate() {
+        if peak == 0 {
+            continue;
+        }
+        let level = (peak * height / 100).min(height - 1) as u16;
+        let x = area.x + i as u16 * (bar_w + gap);
+        let y = area.bottom() - 1 - level;
+        buf.set_string(x, y, &cap, Style::default().fg(theme.text));
+    }
+}
 
-    frame.render_widget(chart, area);
+/// Label sparse frequencies (100, 1k, 10k, …) under the bars nearest to them.
+/// `freqs` holds each displayed bar's centre frequency; `stride` is the
+/// distance in cells from one bar to the next.
+fn draw_frequency_axis(frame: &mut Frame, theme: &Theme, area: Rect, freqs: &[Option<f32>], stride: u16) {
+    const TICKS: [u32; 6] = [50, 100, 500, 1_000, 5_000, 10_000];
+    let buf = frame.buffer_mut();
+    let mut next_free = area.x;
+    for hz in TICKS {
+        // Nearest bar on a log scale, if it is within half an octave.
+        let nearest = freqs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, f)| f.map(|f| (i, (f / hz as f32).log2().abs())))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((bar, distance)) = nearest else {
+            continue;
+        };
+        if distance > 0.5 {
+            continue;
+        }
+        let label = if hz >= 1000 {
+            format!("{}k", hz / 1000)
+        } else {
+            hz.to_string()
+        };
+        let x = area.x + bar as u16 * stride;
+        if x < next_free || x + label.len() as u16 > area.right() {
+            continue;
+        }
+        buf.set_string(x, area.y, &label, Style::default().fg(theme.dim));
+        next_free = x + label.len() as u16 + 1;
+    }
+}
+
+/// Centre frequency of each displayed bar after `resample_spectrum`: the
+/// geometric mean of the grouped bins, or `None` for padding bars.
+fn resample_frequencies(freqs: &[f32], target_len: usize) -> Vec<Option<f32>> {
+    if freqs.is_empty() || target_len == 0 {
+        return vec![None; target_len];
+    }
+    if target_len >= freqs.len() {
+        return (0..target_len).map(|i| freqs.get(i).copied()).collect();
+    }
+    (0..target_len)
+        .map(|i| {
+            let lo = i * freqs.len() / target_len;
+            let hi = ((i + 1) * freqs.len() / target_len)
+                .max(lo + 1)
+                .min(freqs.len());
+            Some((freqs[lo] * freqs[hi - 1]).sqrt())
+        })
+        .collect()
+}
+
+/// Colour of a bar cell at `level` (0.0 = bottom, 1.0 = top of the chart).
+fn palette_color(palette: Palette, level: f64, accent: Color) -> Color {
+    // Linear blend through three stops: bottom, middle, top.
+    let stops: [(u8, u8, u8); 3] = match palette {
+        Palette::Cyan => return accent,
+        Palette::Vu => [(0, 200, 0), (230, 210, 0), (230, 30, 30)],
+        Palette::Ocean => [(20, 60, 200), (0, 200, 220), (230, 250, 255)],
+        Palette::Fire => [(180, 20, 0), (240, 120, 0), (255, 230, 60)],
+    };
+    let level = level.clamp(0.0, 1.0) * 2.0;
+    let (from, to, t) = if level < 1.0 {
+        (stops[0], stops[1], level)
+    } else {
+        (stops[1], stops[2], level - 1.0)
+    };
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    Color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
 }
 
 /// Resample `data` (fixed-size spectrum from the analyser) into `target_len`
@@ -211,12 +771,255 @@ fn resample_spectrum(data: &[u64], target_len: usize) -> Vec<u64> {
         .collect()
 }
 
+/// Like `resample_spectrum`, but keeps the highest peak of each group of
+/// bins so caps do not sink when the chart is narrower than the analyser.
+fn resample_peaks(data: &[u64], target_len: usize) -> Vec<u64> {
+    if data.is_empty() || target_len == 0 {
+        return vec![0; target_len];
+    }
+    if target_len >= data.len() {
+        let mut out = data.to_vec();
+        out.resize(target_len, 0);
+        return out;
+    }
+    (0..target_len)
+        .map(|i| {
+            let lo = i * data.len() / target_len;
+            let hi = ((i + 1) * data.len() / target_len)
+                .max(lo + 1)
+                .min(data.len());
+            data[lo..hi].iter().copied().max().unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Keys column of a help row.
+enum HelpKeys {
+    /// The keys the active keymap binds to each of these actions.
+    Bound(&'static [Action]),
+    /// Keys handled outside the keymap.
+    Fixed(&'static str),
+}
+
+use HelpKeys::{Bound, Fixed};
+
+/// A help category: its title, the action that opens it (shown after the
+/// title) and its (keys, action) rows.
+type HelpSection = (
+    &'static str,
+    Option<Action>,
+    &'static [(HelpKeys, &'static str)],
+);
+
+/// Keybindings shown in the help overlay, grouped by category.
+/// `{seek_step}` and `{seek_step_long}` are filled in from the config.
+const HELP_SECTIONS: &[HelpSection] = &[
+    (
+        "Playback",
+        None,
+        &[
+            (Bound(&[Action::PlayPause]), "Pause / resume"),
+            (Bound(&[Action::Play]), "Play selected"),
+            (Bound(&[Action::Next, Action::Previous]), "Next / previous track"),
+            (Bound(&[Action::Restart]), "Restart track"),
+            (Bound(&[Action::SeekBackward, Action::SeekForward]), "Seek {seek_step}"),
+            (
+                Bound(&[Action::SeekBackwardLong, Action::SeekForwardLong]),
+                "Seek {seek_step_long}",
+            ),
+            (Bound(&[Action::SeekTo]), "Jump to a time"),
+            (Fixed("0 – 9"), "Jump to 0% – 90%"),
+            (Bound(&[Action::RemainingTime]), "Total / remaining time"),
+            (Bound(&[Action::PlayCounts]), "Show / hide play counts"),
+            (Bound(&[Action::Star]), "Star / unstar selected song"),
+            (Bound(&[Action::StarredOnly]), "Show only starred songs"),
+            (Bound(&[Action::History]), "Recently played songs"),
+            (Bound(&[Action::VisualizerPalette]), "Visualizer colours"),
+            (Bound(&[Action::VisualizerStereo]), "Mono / stereo visualizer"),
+            (
+                Bound(&[Action::SensitivityDown, Action::SensitivityUp]),
+                "Visualizer sensitivity",
+            ),
+            (Bound(&[Action::AbLoop]), "A-B loop: set A, set B, clear"),
+            (
+                Bound(&[Action::Bookmark, Action::ClearBookmarks]),
+                "Bookmark here / clear marks",
+            ),
+            (
+                Bound(&[Action::PrevBookmark, Action::NextBookmark]),
+                "Previous / next bookmark",
+            ),
+            (Bound(&[Action::Repeat]), "Cycle repeat"),
+            (Bound(&[Action::Shuffle]), "Toggle shuffle"),
+            (
+                Bound(&[Action::Enqueue, Action::ClearQueue]),
+                "Queue selected / clear queue",
+            ),
+            (Bound(&[Action::PlayFolder]), "Play whole folder"),
+            (
+                Bound(&[Action::SleepTimer, Action::SleepFinishTrack]),
+                "Sleep timer / +track",
+            ),
+        ],
+    ),
+    (
+        "Navigation",
+        None,
+        &[
+            (Bound(&[Action::SelectUp, Action::SelectDown]), "Move selection"),
+            (Bound(&[Action::PageUp, Action::PageDown]), "Move by a page"),
+            (Bound(&[Action::SelectFirst, Action::SelectLast]), "First / last song"),
+            (Fixed("gg"), "First song"),
+            (Fixed("5j 12G"), "Count: 5 down, row 12"),
+            (Bound(&[Action::SelectPlaying]), "Go to playing song"),
+            (Fixed("Alt+letter"), "Jump to next song starting with it"),
+            (Bound(&[Action::Search]), "Search"),
+            (Bound(&[Action::Sort]), "Cycle sort order"),
+            (Bound(&[Action::ClearSearch]), "Clear search"),
+            (Bound(&[Action::ClosePlaylist]), "Leave playlist / folder up"),
+            (Bound(&[Action::Browse]), "Folder browser"),
+            (Bound(&[Action::BrowseFocus]), "Folders / tracks pane"),
+        ],
+    ),
+    (
+        "Volume & speed",
+        None,
+        &[
+            (Bound(&[Action::VolumeUp, Action::VolumeDown]), "Volume up / down"),
+            (Bound(&[Action::VolumeSet]), "Type a volume"),
+            (Bound(&[Action::BalanceLeft, Action::BalanceRight]), "Balance left / right"),
+            (Bound(&[Action::SpeedDown, Action::SpeedUp]), "Speed down / up"),
+            (Bound(&[Action::ReplayGain]), "ReplayGain on / off"),
+            (Bound(&[Action::Mono]), "Mono downmix on / off"),
+        ],
+    ),
+    (
+        "Equalizer",
+        Some(Action::EqToggle),
+        &[
+            (Fixed("← / →"), "Select band"),
+            (Fixed("↑ k / ↓ j"), "Band gain"),
+            (Fixed("⇧↑ K / ⇧↓ J"), "Band gain in 0.5 dB"),
+            (Fixed("Tab"), "Next preset"),
+            (Fixed("0 – 9 -"), "Type a gain, Enter to set"),
+            (Fixed("Backspace"), "Reset to flat"),
+            (Fixed("b"), "Bypass"),
+            (Fixed("f / F"), "Band frequency"),
+            (Fixed("[ / ]"), "Q"),
+            (Fixed("p / P"), "Pre-amp"),
+            (Fixed("s / x"), "Save / clear EQ for track"),
+            (Fixed("Esc"), "Close"),
+        ],
+    ),
+    (
+        "General",
+        None,
+        &[
+            (Bound(&[Action::Help]), "Toggle this help"),
+            (Bound(&[Action::Quit]), "Quit"),
+            (Fixed("Ctrl+C"), "Quit"),
+        ],
+    ),
+];
+
+/// The keys bound to `actions`: an action's keys separated by spaces, the
+/// actions by slashes, e.g. `↑ k / ↓ j`.
+fn bound_keys(keymap: &KeyMap, actions: &[Action]) -> String {
+    actions
+        .iter()
+        .map(|&action| {
+            let keys = keymap.keys(action);
+            if keys.is_empty() { "none".to_string() } else { keys.join(" ") }
+        })
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+fn help_lines(sections: &[HelpSection], app: &App, keymap: &KeyMap) -> Vec<Line<'static>> {
+    let theme = &app.theme;
+    let seek_step = format!("{} s", app.seek_step(false).as_secs());
+    let seek_step_long = format!("{} s", app.seek_step(true).as_secs());
+    let mut lines = Vec::new();
+    for (title, opened_by, rows) in sections {
+        if !lines.is_empty() {
+            lines.push(Line::raw(""));
+        }
+        let title = match opened_by {
+            Some(action) => format!("{title} ({})", bound_keys(keymap, &[*action])),
+            None => title.to_string(),
+        };
+        lines.push(Line::from(Span::styled(
+            title,
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+        )));
+        for (keys, action) in rows.iter() {
+            let keys = match keys {
+                Bound(actions) => bound_keys(keymap, actions),
+                Fixed(keys) => keys.to_string(),
+            };
+            lines.push(Line::from(vec![
+                // At least one space, however long the keys are.
+                Span::styled(format!("  {keys:<14} "), Style::default().fg(theme.text)),
+                Span::styled(
+                    action
+                        .replace("{seek_step}", &seek_step)
+                        .replace("{seek_step_long}", &seek_step_long),
+                    Style::default().fg(theme.subtext),
+                ),
+            ]));
+        }
+    }
+    lines
+}
+
+/// Help overlay: every keybinding, grouped by category, in two columns.
+fn draw_help_popup(frame: &mut Frame, app: &App, keymap: &KeyMap) {
+    let theme = &app.theme;
+    let area = frame.area();
+    let popup_rect = centered_rect(
+        area,
+        area.width.saturating_sub(4),
+        area.height.saturating_sub(2),
+    );
+
+    frame.render_widget(Clear, popup_rect);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.popup_bg))
+        .padding(Padding::horizontal(1))
+        .title(Line::from(vec![
+            Span::styled(
+                " Keybindings ",
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(" Esc/{} close ", bound_keys(keymap, &[Action::Help])),
+                Style::default().fg(theme.dim),
+            ),
+        ]));
+
+    let inner = block.inner(popup_rect);
+    frame.render_widget(block, popup_rect);
+
+    let columns = Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+    let (left, right) = HELP_SECTIONS.split_at(2);
+    frame.render_widget(Paragraph::new(help_lines(left, app, keymap)), columns[0]);
+    frame.render_widget(Paragraph::new(help_lines(right, app, keymap)), columns[1]);
+}
+
 /// Equalizer popup: Clear the area first, then draw a centered box with solid background and borders.
 fn draw_eq_popup(frame: &mut Frame, app: &App) {
+    let theme = app.theme;
     const POPUP_W: u16 = 44;
-    const POPUP_H: u16 = 14;
+    let bands = app.eq_band_count();
+    // Borders, title gap, Q line and hints around one row per band.
+    let popup_h = u16::try_from(11 + bands).unwrap_or(u16::MAX);
     let area = frame.area();
-    let popup_rect = centered_rect(area, POPUP_W, POPUP_H);
+    let popup_rect = centered_rect(area, POPUP_W.min(area.width), popup_h.min(area.height));
 
     // Clear the popup area so background text does not show through.
     frame.render_widget(Clear, popup_rect);
@@ -224,134 +1027,336 @@ fn draw_eq_popup(frame: &mut Frame, app: &App) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(CYAN))
-        .style(Style::default().bg(EQ_POPUP_BG))
+        .border_style(Style::default().fg(theme.accent))
+        .style(Style::default().bg(theme.popup_bg))
         .title(Line::from(vec![
             Span::styled(
                 " Equalizer ",
-                Style::default().fg(CYAN).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(" {} ", app.eq_preset_name()),
+                Style::default().fg(theme.marker),
             ),
-            Span::styled(" Ctrl+E close ", Style::default().fg(DARK_GRAY)),
         ]));
+    // Why the bands differ from the global EQ.
+    let block = if app.eq_track_curve() {
+        block.title(
+            Line::from(Span::styled(
+                " ♪ Track EQ ",
+                Style::default().fg(theme.marker).add_modifier(Modifier::BOLD),
+            ))
+            .right_aligned(),
+        )
+    } else {
+        block.title(
+            Line::from(Span::styled(" Ctrl+E close ", Style::default().fg(theme.dim)))
+                .right_aligned(),
+        )
+    };
 
     let inner = block.inner(popup_rect);
     frame.render_widget(block, popup_rect);
+    if inner.width == 0 || inner.height == 0 {
+        return;
+    }
 
     let selected = app.eq_selected_band();
+    let rows = eq_popup_rows(bands, selected, inner.height as usize);
 
+    // Narrow popups drop the frequency column first, then shrink the gauge.
     let label_w = 8u16;
     let db_w = 8u16;
-    let gauge_w = inner.width.saturating_sub(label_w + db_w + 2).max(4);
+    let freq_w = if inner.width >= label_w + 7 + db_w + 2 + 4 { 7 } else { 0 };
+    let gauge_w = inner.width.saturating_sub(label_w + freq_w + db_w + 2);
+    let row_rect = |row_y: u16, x: u16, width: u16| {
+        Rect::new(inner.x.saturating_add(x), row_y, width, 1).intersection(inner)
+    };
 
-    for i in 0..EqState::BAND_COUNT {
-        let label = format!("{:<6}", EqState::band_name(i));
-        let row_y = inner.y + 2 + i as u16;
-        if row_y >= inner.y + inner.height {
-            break;
-        }
-        let db = app.eq_band_gain_db(i);
-        let ratio = ((db + 12.0) / 24.0).clamp(0.0, 1.0) as f64;
-        let is_selected = i == selected;
+    for (row, kind) in rows.into_iter().enumerate() {
+        let row_y = inner.y + row as u16;
+        match kind {
+            EqRow::Gap => {}
+            EqRow::Band(i) => {
+                let label = format!("{:<7}", EqState::band_name(i, bands));
+                let db = app.eq_band_gain_db(i);
+                let ratio = ((db + 12.0) / 24.0).clamp(0.0, 1.0) as f64;
+                let is_selected = i == selected;
 
-        let style = if is_selected {
-            Style::default().fg(CYAN).add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(GRAY)
-        };
-        let gauge_style = if is_selected {
-            Style::default().fg(CYAN)
-        } else {
-            Style::default().fg(DARK_GRAY)
-        };
+                let style = if is_selected {
+                    Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(theme.subtext)
+                };
+                let gauge_style = if is_selected {
+                    Style::default().fg(theme.accent)
+                } else {
+                    Style::default().fg(theme.dim)
+                };
 
-        let label_rect = Rect::new(inner.x + 1, row_y, label_w, 1);
-        let gauge_rect = Rect::new(inner.x + 1 + label_w, row_y, gauge_w, 1);
-        let db_rect = Rect::new(inner.x + 1 + label_w + gauge_w, row_y, db_w, 1);
+                let label_rect = row_rect(row_y, 1, label_w);
+                let freq_rect = row_rect(row_y, 1 + label_w, freq_w);
+                let gauge_rect = row_rect(row_y, 1 + label_w + freq_w, gauge_w);
+                let db_rect = row_rect(row_y, 1 + label_w + freq_w + gauge_w, db_w);
 
-        frame.render_widget(
-            Paragraph::new(Line::from(Span::styled(label, style))),
-            label_rect,
-        );
+                frame.render_widget(
+                    Paragraph::new(Line::from(Span::styled(label, style))),
+                    label_rect,
+                );
 
-        let gauge = Gauge::default()
-            .gauge_style(gauge_style)
-            .ratio(ratio)
-            .label(Span::raw(""));
-        frame.render_widget(gauge, gauge_rect);
+                frame.render_widget(
+                    Paragraph::new(Line::from(Span::styled(
+                        format_freq(app.eq_band_freq(i)),
+                        Style::default().fg(theme.dim),
+                    ))),
+                    freq_rect,
+                );
 
-        let db_str = format!("{:+.0} dB", db);
-        frame.render_widget(
-            Paragraph::new(Line::from(Span::styled(db_str, style))),
-            db_rect,
-        );
+                // A gain being typed for the selected band shows on its gauge.
+                let gauge_label = match &app.eq_state.edit {
+                    Some(buf) if is_selected => Span::styled(
+                        format!("{buf}▏ dB"),
+                        Style::default().fg(theme.text).add_modifier(Modifier::BOLD),
+                    ),
+                    _ => Span::raw(""),
+                };
+                let gauge = Gauge::default()
+                    .gauge_style(gauge_style)
+                    .ratio(ratio)
+                    .label(gauge_label);
+                frame.render_widget(gauge, gauge_rect);
+
+                let db_str = if db.fract() == 0.0 {
+                    format!("{db:+.0} dB")
+                } else {
+                    format!("{db:+.1} dB")
+                };
+                frame.render_widget(
+                    Paragraph::new(Line::from(Span::styled(db_str, style))),
+                    db_rect,
+                );
+            }
+            EqRow::Params => {
+                let mut q_spans = vec![
+                    Span::styled("Q ", Style::default().fg(theme.dim)),
+                    Span::styled(format!("{:.2}", app.eq_q()), Style::default().fg(theme.text)),
+                    Span::styled("   Pre-amp ", Style::default().fg(theme.dim)),
+                    Span::styled(
+                        format!("{:+.1} dB", app.eq_preamp_db()),
+                        Style::default().fg(theme.text),
+                    ),
+                ];
+                if !app.eq_enabled() {
+                    q_spans.push(Span::styled(
+                        "   BYPASSED",
+                        Style::default().fg(theme.marker).add_modifier(Modifier::BOLD),
+                    ));
+                }
+                frame.render_widget(
+                    Paragraph::new(Line::from(q_spans)),
+                    row_rect(row_y, 1, inner.width.saturating_sub(1)),
+                );
+            }
+            EqRow::PresetHint => {
+                let preset_hint = Line::from(vec![
+                    Span::styled("Tab preset  ", Style::default().fg(theme.dim)),
+                    Span::styled("⌫ flat  ", Style::default().fg(theme.dim)),
+                    Span::styled("b bypass  ", Style::default().fg(theme.dim)),
+                    Span::styled("0-9 set dB", Style::default().fg(theme.dim)),
+                ]);
+                frame.render_widget(Paragraph::new(preset_hint), row_rect(row_y, 0, inner.width));
+            }
+            EqRow::TuningHint => {
+                let tuning_hint = Line::from(vec![
+                    Span::styled("f F freq  ", Style::default().fg(theme.dim)),
+                    Span::styled("[ ] Q  ", Style::default().fg(theme.dim)),
+                    Span::styled("p P pre-amp  ", Style::default().fg(theme.dim)),
+                    Span::styled("s x track", Style::default().fg(theme.dim)),
+                ]);
+                frame.render_widget(Paragraph::new(tuning_hint), row_rect(row_y, 0, inner.width));
+            }
+            EqRow::KeyHint => {
+                let hint = Line::from(vec![
+                    Span::styled("← → band  ", Style::default().fg(theme.dim)),
+                    Span::styled("↑ ↓ gain  ", Style::default().fg(theme.dim)),
+                    Span::styled("Esc/Ctrl+E close", Style::default().fg(theme.dim)),
+                ]);
+                frame.render_widget(Paragraph::new(hint), row_rect(row_y, 0, inner.width));
+            }
+        }
     }
+}
 
-    let hint = Line::from(vec![
-        Span::styled("← → band  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("↑ ↓ gain  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("Esc/Ctrl+E close", Style::default().fg(DARK_GRAY)),
+/// One line inside the EQ popup.
+#[derive(Clone, Copy, PartialEq)]
+enum EqRow {
+    Gap,
+    Band(usize),
+    /// Q, pre-amp and bypass state.
+    Params,
+    PresetHint,
+    TuningHint,
+    KeyHint,
+}
+
+/// The lines of the EQ popup that fit in `height` rows. With too little
+/// room the blank lines go first, bottom up, then the hints and the Q
+/// line, and finally bands far from the `selected` one.
+fn eq_popup_rows(bands: usize, selected: usize, height: usize) -> Vec<EqRow> {
+    let mut rows = vec![EqRow::Gap, EqRow::Gap];
+    rows.extend((0..bands).map(EqRow::Band));
+    rows.extend([
+        EqRow::Gap,
+        EqRow::Params,
+        EqRow::Gap,
+        EqRow::PresetHint,
+        EqRow::TuningHint,
+        EqRow::KeyHint,
+        EqRow::Gap,
     ]);
-    let hint_rect = Rect::new(
-        inner.x,
-        inner.y + inner.height.saturating_sub(2),
-        inner.width,
-        1,
-    );
-    frame.render_widget(Paragraph::new(hint), hint_rect);
+    for drop in [EqRow::Gap, EqRow::PresetHint, EqRow::TuningHint, EqRow::Params, EqRow::KeyHint] {
+        while rows.len() > height
+            && let Some(last) = rows.iter().rposition(|&row| row == drop)
+        {
+            rows.remove(last);
+        }
+    }
+    if rows.len() <= height {
+        return rows;
+    }
+    // Only bands are left: keep a window of them around the selected one.
+    let first = selected.saturating_sub(height / 2).min(bands.saturating_sub(height));
+    rows.into_iter().skip(first).take(height).collect()
+}
+
+/// Actions listed in the footer, each under its first bound key.
+const FOOTER_KEYS: &[(&[Action], &str)] = &[
+    (&[Action::PlayPause], "Pause"),
+    (&[Action::SelectUp, Action::SelectDown], "Nav"),
+    (&[Action::Play], "Play"),
+    (&[Action::Next, Action::Previous], "Next/Prev"),
+    (&[Action::SeekBackward, Action::SeekForward], "Seek"),
+    (&[Action::VolumeUp, Action::VolumeDown], "Vol"),
+    (&[Action::Repeat], "Repeat"),
+    (&[Action::Shuffle], "Shuffle"),
+    (&[Action::Enqueue], "Queue"),
+    (&[Action::Help], "Help"),
+    (&[Action::Quit], "Quit"),
+];
+
+/// A key name as the footer shows it, where room is tight.
+fn short_key(key: &str) -> String {
+    match key {
+        "Space" => "␣".to_string(),
+        "Enter" => "⏎".to_string(),
+        key => key.to_string(),
+    }
 }
 
-fn draw_now_playing(frame: &mut Frame, app: &App, area: Rect) {
+fn draw_now_playing(frame: &mut Frame, app: &mut App, keymap: &KeyMap, area: Rect) {
+    let theme = app.theme;
+    let format_title = app.now_playing_format().map_or_else(Line::default, |format| {
+        Line::from(Span::styled(
+            format!(" {} ", format_audio(format)),
+            Style::default().fg(theme.dim),
+        ))
+        .right_aligned()
+    });
     let block = Block::default()
         .title(Line::from(vec![Span::styled(
             " Now Playing ",
-            Style::default().fg(CYAN).add_modifier(Modifier::BOLD),
+            Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
         )]))
+        .title(format_title)
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(DARK_GRAY))
+        .border_style(Style::default().fg(theme.dim))
         .padding(Padding::new(2, 2, 1, 0));
 
     let inner = block.inner(area);
     frame.render_widget(block, area);
 
+    app.progress_area = Rect::default();
     if inner.height < 5 || inner.width < 20 {
         return;
     }
 
+    // ── Cover art (left of the text, when the track has one) ──
+    let inner = if inner.width >= COVER_MIN_WIDTH
+        && let Some(art) = app.cover_art()
+    {
+        let [art_area, _, text_area] = Layout::horizontal([
+            Constraint::Length(COVER_WIDTH),
+            Constraint::Length(2),
+            Constraint::Min(0),
+        ])
+        .areas(inner);
+        frame.render_stateful_widget(StatefulImage::default(), art_area, art);
+        text_area
+    } else {
+        inner
+    };
+
     let chunks = Layout::vertical([
         Constraint::Length(1), // Song title
         Constraint::Length(1), // Progress bar
         Constraint::Length(1), // Volume + Repeat
-        Constraint::Length(1), // Spacer
+        Constraint::Length(1), // Spacer / error
         Constraint::Length(1), // Controls
     ])
     .split(inner);
 
     // ── Now playing title ──
-    let icon = if app.is_playing() {
-        Span::styled("▶  ", Style::default().fg(GREEN))
+    let loading = app.loading_frame();
+    let icon = if let Some(frame) = loading {
+        Span::styled(
+            format!("{}  ", SPINNER[frame % SPINNER.len()]),
+            Style::default().fg(theme.marker),
+        )
+    } else if app.is_playing() {
+        Span::styled("▶  ", Style::default().fg(theme.playing))
     } else if app.now_playing.is_some() {
-        Span::styled("⏸  ", Style::default().fg(YELLOW))
+        Span::styled("⏸  ", Style::default().fg(theme.marker))
     } else {
-        Span::styled("■  ", Style::default().fg(DARK_GRAY))
+        Span::styled("■  ", Style::default().fg(theme.dim))
     };
 
-    let title = Line::from(vec![
+    // A name wider than the room left by the icon and "Loading…" scrolls
+    // round as a marquee, and the album is left out.
+    let suffix = if loading.is_some() { 10 } else { 0 };
+    let room = (chunks[0].width as usize).saturating_sub(3 + suffix);
+    let name = app.now_playing_name().to_string();
+    let overflow = name.width() > room;
+    let name = if overflow {
+        let start = app.title_scroll(room);
+        marquee(&name, start, room)
+    } else {
+        name
+    };
+    let mut title = vec![
         icon,
-        Span::styled(
-            app.now_playing_name(),
-            Style::default().fg(WHITE).add_modifier(Modifier::BOLD),
-        ),
-    ]);
-    frame.render_widget(Paragraph::new(title), chunks[0]);
+        Span::styled(name, Style::default().fg(theme.text).add_modifier(Modifier::BOLD)),
+    ];
+    if !overflow && let Some(album) = app.now_playing_album() {
+        title.push(Span::styled(
+            format!("  ·  {album}"),
+            Style::default().fg(theme.dim),
+        ));
+    }
+    if loading.is_some() {
+        title.push(Span::styled("  Loading…", Style::default().fg(theme.dim)));
+    }
+    frame.render_widget(Paragraph::new(Line::from(title)), chunks[0]);
 
     // ── Progress bar ──
     let pos = app.current_position();
     let dur = app.current_duration();
     let pos_str = format_duration(pos);
-    let dur_str = dur
-        .map(|d| format_duration(d))
-        .unwrap_or_else(|| "─:──".to_string());
+    let dur_str = match dur {
+        Some(d) if app.show_remaining => format!("-{}", format_duration(d.saturating_sub(pos))),
+        Some(d) => format_duration(d),
+        None => "─:──".to_string(),
+    };
 
     let bar_width = (chunks[1].width as usize).saturating_sub(pos_str.len() + dur_str.len() + 2);
     let ratio = match dur {
@@ -359,57 +1364,332 @@ fn draw_now_playing(frame: &mut Frame, app: &App, area: Rect) {
         _ => 0.0,
     };
     let filled = (ratio * bar_width as f64) as usize;
-    let empty = bar_width.saturating_sub(filled);
-
-    let progress = Line::from(vec![
-        Span::styled(pos_str, Style::default().fg(WHITE)),
-        Span::raw(" "),
-        Span::styled("━".repeat(filled), Style::default().fg(CYAN)),
-        Span::styled("─".repeat(empty), Style::default().fg(DARK_GRAY)),
-        Span::raw(" "),
-        Span::styled(dur_str, Style::default().fg(DARK_GRAY)),
-    ]);
+    app.progress_area = Rect::new(
+        chunks[1].x + pos_str.len() as u16 + 1,
+        chunks[1].y,
+        bar_width as u16,
+        1,
+    );
+
+    let mut bar: Vec<Span> = (0..bar_width)
+        .map(|i| {
+            if i < filled {
+                Span::styled("━", Style::default().fg(theme.accent))
+            } else {
+                Span::styled("─", Style::default().fg(theme.dim))
+            }
+        })
+        .collect();
+    // Markers (bookmarks, then A-B loop points) replace the bar cell at their position.
+    if let Some(d) = dur.filter(|d| !d.is_zero() && bar_width > 0) {
+        let bookmark_style = Style::default().fg(theme.text);
+        let loop_style = Style::default().fg(theme.marker).add_modifier(Modifier::BOLD);
+        let markers = app
+            .current_bookmarks()
+            .iter()
+            .map(|&at| (at, "╿", bookmark_style))
+            .chain(app.loop_a.map(|at| (at, "A", loop_style)))
+            .chain(app.loop_b.map(|at| (at, "B", loop_style)));
+        for (at, symbol, style) in markers {
+            let cell = (at.as_secs_f64() / d.as_secs_f64() * bar_width as f64) as usize;
+            bar[cell.min(bar_width - 1)] = Span::styled(symbol, style);
+        }
+    }
+
+    // Without a length (e.g. a stream) the elapsed time is all there is.
+    let pos_style = match dur {
+        Some(_) => Style::default().fg(theme.text),
+        None => Style::default().fg(theme.accent).add_modifier(Modifier::BOLD),
+    };
+    let mut progress = vec![Span::styled(pos_str, pos_style), Span::raw(" ")];
+    progress.extend(bar);
+    progress.push(Span::raw(" "));
+    progress.push(Span::styled(dur_str, Style::default().fg(theme.dim)));
+    let progress = Line::from(progress);
     frame.render_widget(Paragraph::new(progress), chunks[1]);
 
     // ── Volume + Repeat ──
     let vol = app.volume_percent();
     let repeat_mode = app.repeat;
     let repeat_style = if repeat_mode != RepeatMode::Off {
-        Style::default().fg(CYAN)
+        Style::default().fg(theme.accent)
     } else {
-        Style::default().fg(DARK_GRAY)
+        Style::default().fg(theme.dim)
     };
 
-    let vol_repeat = Line::from(vec![
-        Span::styled("Vol ", Style::default().fg(DARK_GRAY)),
-        Span::styled(format!("{vol}%"), Style::default().fg(WHITE)),
+    let shuffle_style = if app.shuffle {
+        Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default().fg(theme.dim)
+    };
+
+    let mut vol_repeat = vec![Span::styled("Vol ", Style::default().fg(theme.dim))];
+    if app.volume_bar {
+        // Full at the configured maximum, drawn like the progress bar.
+        let ratio = f64::from(vol) / f64::from(app.volume_max_percent().max(1));
+        let filled = ((ratio * VOLUME_BAR_WIDTH as f64).round() as usize).min(VOLUME_BAR_WIDTH);
+        vol_repeat.push(Span::styled("━".repeat(filled), Style::default().fg(theme.accent)));
+        vol_repeat.push(Span::styled(
+            "─".repeat(VOLUME_BAR_WIDTH - filled),
+            Style::default().fg(theme.dim),
+        ));
+        vol_repeat.push(Span::raw(" "));
+    }
+    vol_repeat.extend([
+        Span::styled(format!("{vol}%"), Style::default().fg(theme.text)),
         Span::raw("    "),
         Span::styled("⟳ Repeat: ", repeat_style),
         Span::styled(
             repeat_mode.label(),
             repeat_style.add_modifier(Modifier::BOLD),
         ),
+        Span::raw("    "),
+        Span::styled("⤮ Shuffle", shuffle_style),
     ]);
-    frame.render_widget(Paragraph::new(vol_repeat), chunks[2]);
+    let balance = app.balance_percent();
+    if balance != 0 {
+        let side = if balance < 0 { 'L' } else { 'R' };
+        vol_repeat.push(Span::raw("    "));
+        vol_repeat.push(Span::styled("Bal ", Style::default().fg(theme.dim)));
+        vol_repeat.push(Span::styled(
+            format!("{side}{}%", balance.unsigned_abs()),
+            Style::default().fg(theme.text),
+        ));
+    }
+    let speed = app.speed();
+    if (speed - 1.0).abs() > f32::EPSILON {
+        vol_repeat.push(Span::raw("    "));
+        vol_repeat.push(Span::styled("Speed ", Style::default().fg(theme.dim)));
+        vol_repeat.push(Span::styled(
+            format!("{speed:.2}×"),
+            Style::default().fg(theme.marker),
+        ));
+    }
+    if app.replay_gain() {
+        // The tagged gain of the track, or a dash when it has none.
+        let gain = app
+            .now_playing_gain_db()
+            .map_or_else(|| "—".to_string(), |db| format!("{db:+.1} dB"));
+        vol_repeat.push(Span::raw("    "));
+        vol_repeat.push(Span::styled("RG ", Style::default().fg(theme.accent)));
+        vol_repeat.push(Span::styled(gain, Style::default().fg(theme.text)));
+    }
+    if let Some(left) = app.sleep_remaining() {
+        vol_repeat.push(Span::raw("    "));
+        vol_repeat.push(Span::styled("☾ ", Style::default().fg(theme.accent)));
+        vol_repeat.push(Span::styled(
+            format_duration(left),
+            Style::default().fg(theme.text),
+        ));
+        if app.sleep_finish_track {
+            vol_repeat.push(Span::styled(" +track", Style::default().fg(theme.dim)));
+        }
+    }
+    if !app.queue.is_empty() {
+        vol_repeat.push(Span::raw("    "));
+        vol_repeat.push(Span::styled("Queue ", Style::default().fg(theme.dim)));
+        vol_repeat.push(Span::styled(
+            app.queue.len().to_string(),
+            Style::default().fg(theme.text),
+        ));
+    }
+    frame.render_widget(Paragraph::new(Line::from(vol_repeat)), chunks[2]);
+
+    // ── Playback error, else status of the last action (both transient) ──
+    if let Some(error) = &app.last_error {
+        let error = Line::from(vec![
+            Span::styled("✖ ", Style::default().fg(theme.error)),
+            Span::styled(error.as_str(), Style::default().fg(theme.error)),
+        ]);
+        frame.render_widget(Paragraph::new(error), chunks[3]);
+    } else if let Some((status, _)) = &app.status {
+        let status = Line::from(vec![
+            Span::styled("✓ ", Style::default().fg(theme.accent)),
+            Span::styled(status.as_str(), Style::default().fg(theme.dim)),
+        ]);
+        frame.render_widget(Paragraph::new(status), chunks[3]);
+    }
 
     // ── Controls ──
-    let controls = Line::from(vec![
-        Span::styled("␣", Style::default().fg(CYAN)),
-        Span::styled(" Pause  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("↑↓", Style::default().fg(CYAN)),
-        Span::styled(" Nav  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("⏎", Style::default().fg(CYAN)),
-        Span::styled(" Play  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("n/p", Style::default().fg(CYAN)),
-        Span::styled(" Next/Prev  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("←→", Style::default().fg(CYAN)),
-        Span::styled(" Seek  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("±", Style::default().fg(CYAN)),
-        Span::styled(" Vol  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("r", Style::default().fg(CYAN)),
-        Span::styled(" Repeat  ", Style::default().fg(DARK_GRAY)),
-        Span::styled("q", Style::default().fg(CYAN)),
-        Span::styled(" Quit", Style::default().fg(DARK_GRAY)),
-    ]);
+    let mut controls = Vec::new();
+    for (actions, label) in FOOTER_KEYS {
+        let keys: Vec<String> = actions
+            .iter()
+            .filter_map(|&action| keymap.keys(action).into_iter().next())
+            .map(|key| short_key(&key))
+            .collect();
+        if keys.is_empty() {
+            continue;
+        }
+        if !controls.is_empty() {
+            controls.push(Span::raw("  "));
+        }
+        controls.push(Span::styled(keys.join("/"), Style::default().fg(theme.accent)));
+        controls.push(Span::styled(format!(" {label}"), Style::default().fg(theme.dim)));
+    }
+    let controls = Line::from(controls);
     frame.render_widget(Paragraph::new(controls), chunks[4]);
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{Terminal, backend::TestBackend};
+
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::app::tests::app_with_songs;
+    use crate::keymap::KeySpec;
+
+    /// Render the whole UI at every size up to `max_w` × `max_h`.
+    fn draw_at_every_size(app: &mut App, max_w: u16, max_h: u16) {
+        for width in 0..=max_w {
+            for height in 0..=max_h {
+                let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+                terminal.draw(|frame| draw(frame, app, &KeyMap::default())).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn tiny_terminals_render() {
+        let (mut app, _) = app_with_songs(3);
+        for (width, height) in [(1, 1), (2, 3)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|frame| draw(frame, &mut app, &KeyMap::default())).unwrap();
+        }
+    }
+
+    #[test]
+    fn small_terminals_render_everything() {
+        let (mut app, _) = app_with_songs(3);
+        draw_at_every_size(&mut app, 24, 24);
+
+        app.play_selected();
+        app.set_status("Queued (1 up next)");
+        draw_at_every_size(&mut app, 24, 24);
+
+        app.toggle_visualizer_stereo();
+        app.toggle_browse();
+        app.help_open = true;
+        app.eq_state.popup_open = true;
+        app.prompt_seek();
+        draw_at_every_size(&mut app, 24, 24);
+    }
+
+    fn help_text(app: &App, keymap: &KeyMap) -> Vec<String> {
+        help_lines(HELP_SECTIONS, app, keymap)
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn help_shows_the_configured_seek_steps() {
+        let (app, _) = app_with_songs(1);
+        let text = help_text(&app, &KeyMap::default());
+        assert!(text.iter().any(|line| line.ends_with("Seek 5 s")));
+        assert!(text.iter().any(|line| line.ends_with("Seek 30 s")));
+        assert!(!text.iter().any(|line| line.contains('{') && line.contains("seek")));
+    }
+
+    #[test]
+    fn help_lists_the_keys_of_the_active_keymap() {
+        let (app, _) = app_with_songs(1);
+        let text = help_text(&app, &KeyMap::default());
+        assert!(text.contains(&"  ↑ k / ↓ j      Move selection".to_string()));
+        assert!(text.contains(&"Equalizer (Ctrl+E)".to_string()));
+
+        let overrides = HashMap::from([
+            ("select_down".to_string(), KeySpec::Many(vec!["Down".into(), "t".into()])),
+            ("previous".to_string(), KeySpec::Many(Vec::new())),
+        ]);
+        let text = help_text(&app, &KeyMap::with_overrides(&overrides));
+        assert!(text.contains(&"  ↑ k / ↓ t      Move selection".to_string()));
+        assert!(text.contains(&"  n / none       Next / previous track".to_string()));
+    }
+
+    #[test]
+    fn visualizer_folds_away_while_stopped() {
+        // Row of the song list's bottom border in a 40 × 20 terminal.
+        fn list_bottom(app: &mut App) -> u16 {
+            let mut terminal = Terminal::new(TestBackend::new(40, 20)).unwrap();
+            terminal.draw(|frame| draw(frame, app, &KeyMap::default())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..20).find(|&y| buffer[(0, y)].symbol() == "╰").unwrap()
+        }
+
+        let (mut app, _) = app_with_songs(3);
+        assert_eq!(list_bottom(&mut app), 11);
+        app.play_selected();
+        assert_eq!(list_bottom(&mut app), 5);
+    }
+
+    #[test]
+    fn hidden_and_reordered_panels_render() {
+        let (mut app, _) = app_with_songs(3);
+        app.play_selected();
+        app.panels = vec![Panel::NowPlaying, Panel::Visualizer];
+        draw_at_every_size(&mut app, 24, 24);
+
+        // Now playing on top: its title sits on the row under the list.
+        let mut terminal = Terminal::new(TestBackend::new(40, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, &mut app, &KeyMap::default())).unwrap();
+        let row: String = (0..40)
+            .map(|x| terminal.backend().buffer()[(x, 6)].symbol().to_string())
+            .collect();
+        assert!(row.contains("Now Playing"), "{row}");
+
+        app.panels.clear();
+        draw_at_every_size(&mut app, 24, 24);
+        assert_eq!(app.progress_area, Rect::default());
+    }
+
+    #[test]
+    fn empty_library_renders() {
+        let (mut app, _) = app_with_songs(0);
+        draw_at_every_size(&mut app, 12, 12);
+    }
+
+    #[test]
+    fn eq_rows_fit_any_height() {
+        for height in 0..20 {
+            assert!(eq_popup_rows(10, 9, height).len() <= height);
+        }
+    }
+
+    #[test]
+    fn resampling_to_nothing_is_empty() {
+        assert!(resample_spectrum(&[50; 32], 0).is_empty());
+        assert!(resample_peaks(&[50; 32], 0).is_empty());
+        assert!(resample_frequencies(&[100.0; 32], 0).is_empty());
+        assert_eq!(truncate_name("Song", 0), "…");
+    }
+
+    #[test]
+    fn wide_names_truncate_by_columns() {
+        assert_eq!(truncate_name("日本語の歌", 10), "日本語の歌");
+        assert_eq!(truncate_name("日本語の歌", 5), "日本…");
+        assert_eq!(truncate_name("日本語の歌", 6), "日本…");
+        assert_eq!(truncate_name("🎵 Song", 4), "🎵 …");
+        assert!(truncate_name("日本語の歌", 7).width() <= 7);
+    }
+
+    #[test]
+    fn session_time_counts_hours_and_minutes() {
+        assert_eq!(format_session(Duration::from_secs(59)), "0:00");
+        assert_eq!(format_session(Duration::from_secs(42 * 60 + 5)), "0:42");
+        assert_eq!(format_session(Duration::from_secs(3 * 3600 + 7 * 60)), "3:07");
+    }
+
+    #[test]
+    fn marquee_wraps_round() {
+        assert_eq!(marquee("Song 1", 0, 4), "Song");
+        assert_eq!(marquee("Song 1", 4, 4), " 1  ");
+        assert_eq!(marquee("Song 1", 9, 6), "·   So");
+        assert_eq!(marquee("Song 1", 13, 4), "Song");
+        // Cut by columns: the third wide character would need columns 5-6.
+        assert_eq!(marquee("日本語", 0, 5), "日本 ");
+        assert_eq!(marquee("日本語", 1, 4), "本語");
+    }
+}
@@ -1,27 +1,135 @@
 use std::collections::VecDeque;
-use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use rodio::Source;
 use rustfft::num_complex::Complex;
-use rustfft::FftPlanner;
+use rustfft::{Fft, FftPlanner};
+use serde::Deserialize;
 
-/// FFT window size -- must be a power of two.
+/// Default FFT window size -- must be a power of two.
 const FFT_SIZE: usize = 2048;
 
-/// Number of output frequency bars.
-pub const NUM_BARS: usize = 32;
+/// Default number of output frequency bars.
+const NUM_BARS: usize = 32;
 
-/// Ring-buffer capacity (keeps ~4 FFT frames of samples).
-const BUFFER_CAP: usize = FFT_SIZE * 4;
-
-/// Smoothing factor for the decay animation (0.0 = instant, 1.0 = frozen).
+/// Default smoothing factor for the decay animation (0.0 = instant, 1.0 = frozen).
 const DECAY: f64 = 0.55;
 
+/// Share of its old level a rising bar keeps per `SMOOTHING_FRAME`.
+const RISE: f64 = 0.2;
+
+/// Frame length the smoothing factors (`RISE`, `decay`, `PEAK_DECAY`) are
+/// given for. Frames of any other length, from `interval_ms` or a thread
+/// that woke late, scale them so the bars move at the same speed.
+const SMOOTHING_FRAME: Duration = Duration::from_millis(30);
+
+/// Default pause between two analyses of the FFT thread.
+const INTERVAL_MS: u64 = 30;
+
+/// Range of the user-adjustable sensitivity (multiplier on the normalised level).
+const MIN_SENSITIVITY: f32 = 0.25;
+const MAX_SENSITIVITY: f32 = 4.0;
+
+/// Decay of the peak-hold caps per `SMOOTHING_FRAME`; closer to 1.0 falls
+/// slower than `DECAY`.
+const PEAK_DECAY: f64 = 0.94;
+
+/// Without new samples for this long (playback stopped or stalled) the bars
+/// fall as if the input were silent.
+const STALE_AFTER: Duration = Duration::from_millis(200);
+
+/// Time between analyses once the bars have fallen to zero with no samples
+/// coming in, so a paused or stopped player does not keep the CPU awake.
+const IDLE_INTERVAL: Duration = Duration::from_millis(250);
+
 pub type SampleBuffer = Arc<Mutex<VecDeque<f32>>>;
 
+// ─── Configuration ───────────────────────────────────────────────────────────
+
+/// Analyser tuning, read from the `[visualizer]` table of `config.toml`.
+/// Missing keys keep their defaults.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct VisualizerConfig {
+    /// FFT window size in samples; a power of two between 256 and 16384.
+    pub fft_size: usize,
+    /// Number of frequency bars (4–128).
+    pub bars: usize,
+    /// Smoothing factor for falling bars per 30 ms, 0.0 (instant) up to but
+    /// not 1.0.
+    pub decay: f64,
+    /// Milliseconds between analyses (5–1000).
+    pub interval_ms: u64,
+    /// Height, on the bars' 0–100 scale, of the faint baseline drawn where
+    /// a bar is lower (0–10; 0 draws none).
+    pub min_bar: u64,
+}
+
+impl Default for VisualizerConfig {
+    fn default() -> Self {
+        Self {
+            fft_size: FFT_SIZE,
+            bars: NUM_BARS,
+            decay: DECAY,
+            interval_ms: INTERVAL_MS,
+            min_bar: 0,
+        }
+    }
+}
+
+impl VisualizerConfig {
+    /// Replace out-of-range values with their defaults, reporting each one
+    /// on stderr.
+    pub fn validated(self) -> Self {
+        let default = Self::default();
+        let mut config = self;
+        if !config.fft_size.is_power_of_two() || !(256..=16384).contains(&config.fft_size) {
+            eprintln!(
+                "config: visualizer.fft_size {} must be a power of two in 256..=16384, using {}",
+                config.fft_size, default.fft_size
+            );
+            config.fft_size = default.fft_size;
+        }
+        if !(4..=128).contains(&config.bars) {
+            eprintln!(
+                "config: visualizer.bars {} must be in 4..=128, using {}",
+                config.bars, default.bars
+            );
+            config.bars = default.bars;
+        }
+        if !(0.0..1.0).contains(&config.decay) {
+            eprintln!(
+                "config: visualizer.decay {} must be in 0.0..1.0, using {}",
+                config.decay, default.decay
+            );
+            config.decay = default.decay;
+        }
+        if !(5..=1000).contains(&config.interval_ms) {
+            eprintln!(
+                "config: visualizer.interval_ms {} must be in 5..=1000, using {}",
+                config.interval_ms, default.interval_ms
+            );
+            config.interval_ms = default.interval_ms;
+        }
+        if config.min_bar > 10 {
+            eprintln!(
+                "config: visualizer.min_bar {} must be in 0..=10, using {}",
+                config.min_bar, default.min_bar
+            );
+            config.min_bar = default.min_bar;
+        }
+        config
+    }
+
+    /// Ring-buffer capacity per channel (keeps ~4 FFT frames of samples).
+    fn buffer_capacity(&self) -> usize {
+        self.fft_size * 4
+    }
+}
+
 // ─── Source wrapper ──────────────────────────────────────────────────────────
 
 /// Transparent wrapper around any `Source<Item = f32>` that copies every
@@ -29,11 +137,13 @@ pub type SampleBuffer = Arc<Mutex<VecDeque<f32>>>;
 pub struct VisualizerSource<S> {
     inner: S,
     buffer: SampleBuffer,
+    /// Most samples kept in `buffer`; older ones are dropped.
+    cap: usize,
 }
 
 impl<S> VisualizerSource<S> {
-    pub fn new(inner: S, buffer: SampleBuffer) -> Self {
-        Self { inner, buffer }
+    pub fn new(inner: S, buffer: SampleBuffer, cap: usize) -> Self {
+        Self { inner, buffer, cap }
     }
 }
 
@@ -46,8 +156,8 @@ impl<S: Source<Item = f32>> Iterator for VisualizerSource<S> {
         // try_lock so we never block the audio thread
         if let Ok(mut buf) = self.buffer.try_lock() {
             buf.push_back(sample);
-            if buf.len() > BUFFER_CAP {
-                let excess = buf.len() - BUFFER_CAP;
+            if buf.len() > self.cap {
+                let excess = buf.len() - self.cap;
                 buf.drain(..excess);
             }
         }
@@ -69,49 +179,252 @@ impl<S: Source<Item = f32>> Source for VisualizerSource<S> {
         self.inner.total_duration()
     }
     fn try_seek(&mut self, pos: Duration) -> Result<(), rodio::source::SeekError> {
-        // Clear captured samples on seek -- position just changed.
-        if let Ok(mut buf) = self.buffer.lock() {
-            buf.clear();
-        }
+        // Captured samples are kept so the spectrum does not blank out; new
+        // ones replace them within a frame.
         self.inner.try_seek(pos)
     }
 }
 
+// ─── Spectrum analysis ───────────────────────────────────────────────────────
+
+/// Windowed FFT of a block of samples, mapped to log-spaced bars normalised
+/// to 0..100. It keeps no state between calls, so the same samples always
+/// give the same bars; the background thread drives it, but it works just as
+/// well on any caller-supplied buffer.
+pub struct Analysis {
+    fft: Arc<dyn Fft<f32>>,
+    /// Hann window coefficients, one per FFT input sample.
+    window: Vec<f32>,
+    bars: usize,
+    /// Multiplier on the normalised bar level (`f32` bits); above 1.0 makes
+    /// quiet material move the bars more.
+    sensitivity: AtomicU32,
+}
+
+impl Analysis {
+    /// Plan an FFT of `fft_size` samples (a power of two) feeding `bars`
+    /// bars.
+    pub fn new(fft_size: usize, bars: usize) -> Self {
+        let fft = FftPlanner::<f32>::new().plan_fft_forward(fft_size);
+        let window = (0..fft_size)
+            .map(|i| {
+                0.5 * (1.0
+                    - (2.0 * std::f32::consts::PI * i as f32 / (fft_size - 1) as f32).cos())
+            })
+            .collect();
+        Self {
+            fft,
+            window,
+            bars,
+            sensitivity: AtomicU32::new(1.0f32.to_bits()),
+        }
+    }
+
+    pub fn fft_size(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn sensitivity(&self) -> f32 {
+        f32::from_bits(self.sensitivity.load(Ordering::Relaxed))
+    }
+
+    /// Set the sensitivity multiplier, clamped to 0.25–4.0.
+    pub fn set_sensitivity(&self, sensitivity: f32) {
+        let s = sensitivity.clamp(MIN_SENSITIVITY, MAX_SENSITIVITY);
+        self.sensitivity.store(s.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Bars of interleaved `samples` with `channels` channels, mixed down
+    /// to mono. Only the first `fft_size` frames are used; with fewer every
+    /// bar is zero.
+    pub fn analyze(&self, samples: &[f32], channels: usize) -> Vec<f64> {
+        let mono: Vec<f32> = samples
+            .chunks(channels.max(1))
+            .map(|c| c.iter().sum::<f32>() / c.len() as f32)
+            .collect();
+        self.bars_of(&mono)
+    }
+
+    /// Bars of channel `channel` of interleaved `samples`; a source with
+    /// fewer channels feeds its last one (mono feeds both sides).
+    fn analyze_channel(&self, samples: &[f32], channels: usize, channel: usize) -> Vec<f64> {
+        let picked: Vec<f32> = samples
+            .chunks(channels.max(1))
+            .map(|frame| frame[channel.min(frame.len() - 1)])
+            .collect();
+        self.bars_of(&picked)
+    }
+
+    /// Windowed FFT of one channel, mapped to `bars` log-spaced bars
+    /// normalised to 0..100 and scaled by the sensitivity.
+    fn bars_of(&self, samples: &[f32]) -> Vec<f64> {
+        let fft_size = self.fft_size();
+        let gain = self.sensitivity() as f64;
+        if samples.len() < fft_size {
+            return vec![0.0; self.bars];
+        }
+
+        // ── apply Hann window → complex buffer ───────────────────────────
+        let mut fft_buf: Vec<Complex<f32>> = samples[..fft_size]
+            .iter()
+            .zip(self.window.iter())
+            .map(|(&s, &w)| Complex::new(s * w, 0.0))
+            .collect();
+
+        // ── run FFT in-place ─────────────────────────────────────────────
+        self.fft.process(&mut fft_buf);
+
+        // ── magnitudes of positive frequencies ───────────────────────────
+        let half = fft_size / 2;
+        let magnitudes: Vec<f32> = fft_buf[..half].iter().map(|c| c.norm()).collect();
+
+        // ── map to bars with logarithmic frequency spacing ───────────────
+        (0..self.bars)
+            .map(|i| {
+                let (lo, hi) = bar_bins(i, fft_size, self.bars);
+                let sum: f32 = magnitudes[lo..hi].iter().sum();
+                let avg = sum / (hi - lo) as f32;
+
+                // Convert to dB then normalise into 0..100
+                let db = 20.0 * (avg.max(1e-10)).log10() as f64;
+                ((db + 20.0) / 55.0 * 100.0 * gain).clamp(0.0, 100.0)
+            })
+            .collect()
+    }
+}
+
 // ─── Background spectrum analyser ────────────────────────────────────────────
 
+/// Bars and peak-hold values of one spectrum (mono, or one stereo channel).
+#[derive(Clone)]
+struct Bars {
+    levels: Vec<f64>,
+    /// Peak-hold value per bar: jumps to a new high, then decays by `PEAK_DECAY`.
+    peaks: Vec<f64>,
+}
+
+impl Bars {
+    fn new(bars: usize) -> Self {
+        Self {
+            levels: vec![0.0; bars],
+            peaks: vec![0.0; bars],
+        }
+    }
+
+    /// Blend freshly computed bars in, `elapsed` after the last update: rise
+    /// fast, fall by `decay` per `SMOOTHING_FRAME`; peaks hold.
+    fn update(&mut self, fresh: &[f64], decay: f64, elapsed: Duration) {
+        let frames = elapsed.as_secs_f64() / SMOOTHING_FRAME.as_secs_f64();
+        let rise = RISE.powf(frames);
+        let fall = decay.powf(frames);
+        let peak_fall = PEAK_DECAY.powf(frames);
+        for ((level, peak), &new) in self.levels.iter_mut().zip(&mut self.peaks).zip(fresh) {
+            let keep = if new > *level { rise } else { fall };
+            *level = *level * keep + new * (1.0 - keep);
+            *peak = if *level >= *peak { *level } else { *peak * peak_fall };
+        }
+    }
+
+    /// Every bar and peak has fallen below what shows as 1.
+    fn is_silent(&self) -> bool {
+        self.levels.iter().chain(&self.peaks).all(|&v| v < 0.5)
+    }
+
+    fn clear(&mut self) {
+        self.levels.iter_mut().for_each(|v| *v = 0.0);
+        self.peaks.iter_mut().for_each(|v| *v = 0.0);
+    }
+}
+
+/// Latest analysis shared with the UI. In stereo mode `left` holds the left
+/// channel and `right` the right one; otherwise `left` is the mono mix.
+struct SpectrumState {
+    left: Bars,
+    right: Bars,
+    /// When the thread last wrote the bars; `None` before it first has.
+    updated: Option<Instant>,
+}
+
+/// FFT bin range `lo..hi` of bar `i` out of `bars`: logarithmic edges
+/// `half^(i/bars) .. half^((i+1)/bars)`.
+fn bar_bins(i: usize, fft_size: usize, bars: usize) -> (usize, usize) {
+    let half = fft_size / 2;
+    let lo = ((half as f64).powf(i as f64 / bars as f64)) as usize;
+    let hi = ((half as f64).powf((i + 1) as f64 / bars as f64)) as usize;
+    let lo = lo.max(1).min(half - 1);
+    let hi = hi.max(lo + 1).min(half);
+    (lo, hi)
+}
+
+fn rounded(v: &[f64]) -> Vec<u64> {
+    v.iter().map(|&v| v.round() as u64).collect()
+}
+
 /// Runs a dedicated thread that periodically grabs samples from the shared
 /// ring-buffer, applies a Hann window, runs an FFT, and writes the resulting
 /// spectrum bars (normalised 0-100) into shared state that the UI can read
 /// without blocking.
 pub struct SpectrumAnalyzer {
     sample_buffer: SampleBuffer,
-    spectrum: Arc<Mutex<Vec<f64>>>,
+    state: Arc<Mutex<SpectrumState>>,
     channels: Arc<AtomicU16>,
+    /// Sample rate of the current source, which sets each bar's frequency.
+    sample_rate: AtomicU32,
+    /// Analyse left and right separately instead of a mono mix.
+    stereo: Arc<AtomicBool>,
+    /// Playback is paused: the bars fall to zero instead of holding the last
+    /// samples' spectrum.
+    paused: Arc<AtomicBool>,
+    /// The bars are not on screen: the thread skips the FFT and idles.
+    hidden: Arc<AtomicBool>,
+    /// FFT and bar mapping, shared with the thread.
+    analysis: Arc<Analysis>,
+    config: VisualizerConfig,
     running: Arc<AtomicBool>,
     thread: Option<JoinHandle<()>>,
 }
 
 impl SpectrumAnalyzer {
-    pub fn new() -> Self {
+    /// Start the analyser thread with `config`, which should already be
+    /// validated.
+    pub fn new(config: VisualizerConfig) -> Self {
         let sample_buffer: SampleBuffer =
-            Arc::new(Mutex::new(VecDeque::with_capacity(BUFFER_CAP)));
-        let spectrum = Arc::new(Mutex::new(vec![0.0f64; NUM_BARS]));
+            Arc::new(Mutex::new(VecDeque::with_capacity(config.buffer_capacity())));
+        let state = Arc::new(Mutex::new(SpectrumState {
+            left: Bars::new(config.bars),
+            right: Bars::new(config.bars),
+            updated: None,
+        }));
         let channels = Arc::new(AtomicU16::new(2));
+        let stereo = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
+        let hidden = Arc::new(AtomicBool::new(false));
+        let analysis = Arc::new(Analysis::new(config.fft_size, config.bars));
         let running = Arc::new(AtomicBool::new(true));
 
         let buf = sample_buffer.clone();
-        let spec = spectrum.clone();
+        let st = state.clone();
         let ch = channels.clone();
+        let stereo_mode = stereo.clone();
+        let pause = paused.clone();
+        let hide = hidden.clone();
+        let analyse = analysis.clone();
         let run = running.clone();
 
         let thread = std::thread::spawn(move || {
-            Self::fft_loop(buf, spec, ch, run);
+            Self::fft_loop(buf, st, ch, stereo_mode, pause, hide, analyse, config, run);
         });
 
         Self {
             sample_buffer,
-            spectrum,
+            state,
             channels,
+            sample_rate: AtomicU32::new(44_100),
+            stereo,
+            paused,
+            hidden,
+            analysis,
+            config,
             running,
             thread: Some(thread),
         }
@@ -125,123 +438,178 @@ impl SpectrumAnalyzer {
         self.sample_buffer.clone()
     }
 
+    /// Most samples `VisualizerSource` should keep in the ring-buffer.
+    pub fn buffer_capacity(&self) -> usize {
+        self.config.buffer_capacity()
+    }
+
     /// Tell the analyser how many interleaved channels the current source has.
     pub fn set_channels(&self, ch: u16) {
         self.channels.store(ch, Ordering::Relaxed);
     }
 
-    /// Read the latest spectrum bars (each value 0..=100).
-    pub fn spectrum(&self) -> Vec<u64> {
-        self.spectrum
+    pub fn set_sample_rate(&self, rate: u32) {
+        self.sample_rate.store(rate, Ordering::Relaxed);
+    }
+
+    /// Centre frequency in Hz of each bar (geometric mean of its bin edges),
+    /// for the current sample rate.
+    pub fn bar_frequencies(&self) -> Vec<f32> {
+        let VisualizerConfig { fft_size, bars, .. } = self.config;
+        let bin_hz = self.sample_rate.load(Ordering::Relaxed) as f32 / fft_size as f32;
+        (0..bars)
+            .map(|i| {
+                let (lo, hi) = bar_bins(i, fft_size, bars);
+                (lo as f32 * (hi - 1).max(lo) as f32).sqrt() * bin_hz
+            })
+            .collect()
+    }
+
+    pub fn sensitivity(&self) -> f32 {
+        self.analysis.sensitivity()
+    }
+
+    /// Set the sensitivity multiplier, clamped to 0.25–4.0.
+    pub fn set_sensitivity(&self, sensitivity: f32) {
+        self.analysis.set_sensitivity(sensitivity);
+    }
+
+    pub fn is_stereo(&self) -> bool {
+        self.stereo.load(Ordering::Relaxed)
+    }
+
+    /// Switch between a mono mix and separate left/right spectra.
+    pub fn set_stereo(&self, stereo: bool) {
+        self.stereo.store(stereo, Ordering::Relaxed);
+    }
+
+    /// Tell the analyser whether playback is paused, so the bars fall away
+    /// rather than freezing.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Tell the analyser whether the bars are off screen. While hidden no
+    /// FFT runs; shown again, the bars rise from zero.
+    pub fn set_hidden(&self, hidden: bool) {
+        self.hidden.store(hidden, Ordering::Relaxed);
+    }
+
+    /// Read the latest left and right spectrum bars (each value 0..=100).
+    /// Outside stereo mode the left holds the mono mix; in stereo mode mono
+    /// sources show the same on both.
+    pub fn spectrum_stereo(&self) -> (Vec<u64>, Vec<u64>) {
+        self.state
+            .lock()
+            .map(|s| (rounded(&s.left.levels), rounded(&s.right.levels)))
+            .unwrap_or_else(|_| (vec![0; self.config.bars], vec![0; self.config.bars]))
+    }
+
+    /// Left and right peak-hold values, one per bar, like `spectrum_stereo`.
+    pub fn peaks_stereo(&self) -> (Vec<u64>, Vec<u64>) {
+        self.state
             .lock()
-            .map(|s| s.iter().map(|&v| v.round() as u64).collect())
-            .unwrap_or_else(|_| vec![0; NUM_BARS])
+            .map(|s| (rounded(&s.left.peaks), rounded(&s.right.peaks)))
+            .unwrap_or_else(|_| (vec![0; self.config.bars], vec![0; self.config.bars]))
     }
 
-    /// Clear both the sample buffer and the spectrum (e.g. on track change).
+    /// When the bars were last updated, roughly every `interval_ms`.
+    pub fn updated(&self) -> Option<Instant> {
+        self.state.lock().ok().and_then(|s| s.updated)
+    }
+
+    /// Clear the sample buffer, spectra and peaks (e.g. on track change).
     pub fn clear(&self) {
         if let Ok(mut buf) = self.sample_buffer.lock() {
             buf.clear();
         }
-        if let Ok(mut spec) = self.spectrum.lock() {
-            spec.iter_mut().for_each(|v| *v = 0.0);
+        if let Ok(mut state) = self.state.lock() {
+            state.left.clear();
+            state.right.clear();
         }
     }
 
     // ── background thread ────────────────────────────────────────────────
 
+    #[allow(clippy::too_many_arguments)]
     fn fft_loop(
         buf: SampleBuffer,
-        spec: Arc<Mutex<Vec<f64>>>,
+        state: Arc<Mutex<SpectrumState>>,
         ch: Arc<AtomicU16>,
+        stereo: Arc<AtomicBool>,
+        paused: Arc<AtomicBool>,
+        hidden: Arc<AtomicBool>,
+        analysis: Arc<Analysis>,
+        config: VisualizerConfig,
         run: Arc<AtomicBool>,
     ) {
-        let mut planner = FftPlanner::<f32>::new();
-        let fft = planner.plan_fft_forward(FFT_SIZE);
-
-        // Pre-compute Hann window coefficients once.
-        let window: Vec<f32> = (0..FFT_SIZE)
-            .map(|i| {
-                0.5 * (1.0
-                    - (2.0 * std::f32::consts::PI * i as f32 / (FFT_SIZE - 1) as f32).cos())
-            })
-            .collect();
-
-        let mut prev = vec![0.0f64; NUM_BARS];
+        let VisualizerConfig { fft_size, bars, decay, interval_ms, .. } = config;
+
+        // Smoothed bars live here and are copied into `state` each frame, so
+        // the UI never waits on the FFT.
+        let mut left = Bars::new(bars);
+        let mut right = Bars::new(bars);
+        let silence = vec![0.0; bars];
+        // Samples of the previous analysis, to notice when no new ones arrive.
+        let mut previous: Vec<f32> = Vec::new();
+        let mut last_fresh = Instant::now();
+        let mut last_frame = Instant::now();
+        let mut idle = false;
 
         while run.load(Ordering::Relaxed) {
-            std::thread::sleep(Duration::from_millis(30));
+            std::thread::sleep(if idle {
+                IDLE_INTERVAL
+            } else {
+                Duration::from_millis(interval_ms)
+            });
+            // The sleep can run long; smoothing goes by the time that passed.
+            let elapsed = last_frame.elapsed();
+            last_frame = Instant::now();
 
             let channels = ch.load(Ordering::Relaxed).max(1) as usize;
-
-            // ── grab the most recent FFT_SIZE * channels samples ─────────
-            let raw: Vec<f32> = {
-                let Ok(guard) = buf.lock() else {
-                    continue;
-                };
-                let needed = FFT_SIZE * channels;
-                if guard.len() < needed {
-                    continue;
-                }
-                let start = guard.len() - needed;
-                guard.range(start..).copied().collect()
+            let hidden = hidden.load(Ordering::Relaxed);
+
+            // ── grab the most recent fft_size * channels samples ─────────
+            let raw: Option<Vec<f32>> = if hidden || paused.load(Ordering::Relaxed) {
+                None
+            } else {
+                buf.lock().ok().and_then(|guard| {
+                    let needed = fft_size * channels;
+                    let start = guard.len().checked_sub(needed)?;
+                    Some(guard.range(start..).copied().collect())
+                })
             };
-
-            // ── mix interleaved channels down to mono ────────────────────
-            let mono: Vec<f32> = raw
-                .chunks(channels)
-                .map(|c| c.iter().sum::<f32>() / c.len() as f32)
-                .collect();
-
-            if mono.len() < FFT_SIZE {
-                continue;
+            let raw = raw.filter(|raw| {
+                if *raw != previous {
+                    last_fresh = Instant::now();
+                }
+                last_fresh.elapsed() < STALE_AFTER
+            });
+
+            idle = raw.is_none() && left.is_silent() && right.is_silent();
+            if hidden {
+                // Off screen: nothing to animate.
+                left.clear();
+                right.clear();
+            } else if let Some(raw) = raw {
+                if stereo.load(Ordering::Relaxed) {
+                    // A mono source feeds both sides.
+                    left.update(&analysis.analyze_channel(&raw, channels, 0), decay, elapsed);
+                    right.update(&analysis.analyze_channel(&raw, channels, 1), decay, elapsed);
+                } else {
+                    left.update(&analysis.analyze(&raw, channels), decay, elapsed);
+                }
+                previous = raw;
+            } else {
+                // Paused or stalled: let the bars fall.
+                left.update(&silence, decay, elapsed);
+                right.update(&silence, decay, elapsed);
             }
 
-            // ── apply Hann window → complex buffer ───────────────────────
-            let mut fft_buf: Vec<Complex<f32>> = mono[..FFT_SIZE]
-                .iter()
-                .zip(window.iter())
-                .map(|(&s, &w)| Complex::new(s * w, 0.0))
-                .collect();
-
-            // ── run FFT in-place ─────────────────────────────────────────
-            fft.process(&mut fft_buf);
-
-            // ── magnitudes of positive frequencies ───────────────────────
-            let half = FFT_SIZE / 2;
-            let magnitudes: Vec<f32> = fft_buf[..half].iter().map(|c| c.norm()).collect();
-
-            // ── map to bars with logarithmic frequency spacing ───────────
-            let new_spec: Vec<f64> = (0..NUM_BARS)
-                .map(|i| {
-                    // Logarithmic bin edges: half^(i/NUM_BARS) .. half^((i+1)/NUM_BARS)
-                    let lo =
-                        ((half as f64).powf(i as f64 / NUM_BARS as f64)) as usize;
-                    let hi =
-                        ((half as f64).powf((i + 1) as f64 / NUM_BARS as f64)) as usize;
-                    let lo = lo.max(1).min(half - 1);
-                    let hi = hi.max(lo + 1).min(half);
-
-                    let sum: f32 = magnitudes[lo..hi].iter().sum();
-                    let avg = sum / (hi - lo) as f32;
-
-                    // Convert to dB then normalise into 0..100
-                    let db = 20.0 * (avg.max(1e-10)).log10() as f64;
-                    let normalized = ((db + 20.0) / 55.0 * 100.0).clamp(0.0, 100.0);
-
-                    // Asymmetric smoothing: rise fast, decay slowly
-                    if normalized > prev[i] {
-                        prev[i] * 0.2 + normalized * 0.8
-                    } else {
-                        prev[i] * DECAY + normalized * (1.0 - DECAY)
-                    }
-                })
-                .collect();
-
-            prev.clone_from(&new_spec);
-
-            if let Ok(mut guard) = spec.lock() {
-                *guard = new_spec;
+            if let Ok(mut guard) = state.lock() {
+                guard.left.clone_from(&left);
+                guard.right.clone_from(&right);
+                guard.updated = Some(Instant::now());
             }
         }
     }
@@ -255,3 +623,102 @@ impl Drop for SpectrumAnalyzer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FFT_SIZE: usize = 2048;
+    const BARS: usize = 32;
+    const SAMPLE_RATE: f32 = 44_100.0;
+
+    fn sine(freq: f32, amplitude: f32) -> Vec<f32> {
+        (0..FFT_SIZE)
+            .map(|t| {
+                amplitude * (2.0 * std::f32::consts::PI * freq * t as f32 / SAMPLE_RATE).sin()
+            })
+            .collect()
+    }
+
+    fn loudest(bars: &[f64]) -> usize {
+        (0..bars.len()).max_by(|&a, &b| bars[a].total_cmp(&bars[b])).unwrap()
+    }
+
+    #[test]
+    fn sine_lands_in_its_bar() {
+        let analysis = Analysis::new(FFT_SIZE, BARS);
+        let tones = [(100.0, 7), (440.0, 14), (1_000.0, 17), (5_000.0, 25), (10_000.0, 28)];
+        for (freq, bar) in tones {
+            let bars = analysis.analyze(&sine(freq, 0.05), 1);
+            assert_eq!(loudest(&bars), bar, "{freq} Hz: {bars:?}");
+
+            let bin = (freq * FFT_SIZE as f32 / SAMPLE_RATE) as usize;
+            let (lo, hi) = bar_bins(bar, FFT_SIZE, BARS);
+            assert!((lo..hi).contains(&bin), "{freq} Hz is bin {bin}, bar {bar} spans {lo}..{hi}");
+        }
+    }
+
+    #[test]
+    fn stereo_mixes_down_to_mono() {
+        let analysis = Analysis::new(FFT_SIZE, BARS);
+        let mono = sine(440.0, 0.05);
+        let stereo: Vec<f32> = mono.iter().flat_map(|&s| [s, s]).collect();
+        assert_eq!(analysis.analyze(&stereo, 2), analysis.analyze(&mono, 1));
+    }
+
+    #[test]
+    fn silence_gives_zero_bars() {
+        let analysis = Analysis::new(FFT_SIZE, BARS);
+        let bars = analysis.analyze(&vec![0.0; FFT_SIZE], 1);
+        assert_eq!(bars, vec![0.0; BARS]);
+    }
+
+    #[test]
+    fn short_buffer_gives_zero_bars() {
+        let analysis = Analysis::new(FFT_SIZE, BARS);
+        let bars = analysis.analyze(&sine(440.0, 1.0)[..FFT_SIZE - 1], 1);
+        assert_eq!(bars, vec![0.0; BARS]);
+    }
+
+    #[test]
+    fn full_scale_square_saturates() {
+        let analysis = Analysis::new(FFT_SIZE, BARS);
+        let square: Vec<f32> = sine(440.0, 1.0)
+            .iter()
+            .map(|&s| if s >= 0.0 { 1.0 } else { -1.0 })
+            .collect();
+        let bars = analysis.analyze(&square, 1);
+        assert!(bars.iter().all(|&b| (0.0..=100.0).contains(&b)), "{bars:?}");
+        assert!(bars[14] > 99.0, "{bars:?}");
+    }
+
+    #[test]
+    fn sensitivity_scales_bars() {
+        let analysis = Analysis::new(FFT_SIZE, BARS);
+        let samples = sine(1_000.0, 0.05);
+        let normal = analysis.analyze(&samples, 1)[17];
+        analysis.set_sensitivity(0.5);
+        let halved = analysis.analyze(&samples, 1)[17];
+        assert!((halved - normal / 2.0).abs() < 1e-9, "{normal} → {halved}");
+    }
+
+    #[test]
+    fn smoothing_follows_elapsed_time() {
+        let fresh = [100.0, 0.0];
+        let mut once = Bars::new(2);
+        once.levels = vec![50.0, 50.0];
+        let mut twice = once.clone();
+
+        // One 30 ms frame keeps the factors as given.
+        once.update(&fresh, DECAY, SMOOTHING_FRAME);
+        assert!((once.levels[0] - (50.0 * RISE + 100.0 * (1.0 - RISE))).abs() < 1e-9);
+        assert!((once.levels[1] - 50.0 * DECAY).abs() < 1e-9);
+
+        // Two frames of half the length end up at the same levels.
+        twice.update(&fresh, DECAY, SMOOTHING_FRAME / 2);
+        twice.update(&fresh, DECAY, SMOOTHING_FRAME / 2);
+        for (a, b) in once.levels.iter().zip(&twice.levels) {
+            assert!((a - b).abs() < 1e-9, "{:?} vs {:?}", once.levels, twice.levels);
+        }
+    }
+}
@@ -0,0 +1,53 @@
+//! Watches the music directory for added, removed and renamed files so the
+//! library can be rescanned while the player runs. Bursts of events (e.g.
+//! copying an album) are debounced into a single rescan.
+
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Quiet period after the last change before a rescan is due.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+pub struct LibraryWatcher {
+    /// Kept alive for as long as events should be delivered.
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>,
+    /// Time of the latest change not yet answered by a rescan.
+    last_change: Option<Instant>,
+}
+
+impl LibraryWatcher {
+    /// Watch `dir` recursively. `None` when the platform or directory cannot
+    /// be watched; the library then only updates on restart.
+    pub fn new(dir: &Path) -> Option<Self> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).ok()?;
+        watcher.watch(dir, RecursiveMode::Recursive).ok()?;
+        Some(Self {
+            _watcher: watcher,
+            events,
+            last_change: None,
+        })
+    }
+
+    /// Collect pending events; true once changes have arrived and none have
+    /// followed for `DEBOUNCE`. Reading files (access events, e.g. playing
+    /// a track) does not count as a change.
+    pub fn poll(&mut self) -> bool {
+        for event in self.events.try_iter() {
+            if event.is_ok_and(|e| !e.kind.is_access()) {
+                self.last_change = Some(Instant::now());
+            }
+        }
+        match self.last_change {
+            Some(at) if at.elapsed() >= DEBOUNCE => {
+                self.last_change = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}